@@ -0,0 +1,225 @@
+//! The RSA keypair used to exchange the shared secret during the login
+//!
+//! A single [`ServerKey`] is created at startup and used for all the connections, as generating
+//! a new key for every login is slow.
+
+/// The size of the keys generated by [`ServerKey::load_or_generate`], vanilla servers use 1024
+pub const DEFAULT_KEY_BITS: u32 = 2048;
+
+#[derive(Debug)]
+pub enum KeyError {
+    Io(std::io::Error),
+    Crypto(openssl::error::ErrorStack),
+    /// The client did not send back the verify token of the encryption request
+    InvalidVerifyToken,
+}
+
+impl From<std::io::Error> for KeyError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<openssl::error::ErrorStack> for KeyError {
+    fn from(value: openssl::error::ErrorStack) -> Self {
+        Self::Crypto(value)
+    }
+}
+
+pub struct ServerKey {
+    private_key: openssl::rsa::Rsa<openssl::pkey::Private>,
+    /// The DER encoding of the public key, as send in the encryption request
+    public_key: Vec<u8>,
+}
+
+impl core::fmt::Debug for ServerKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ServerKey")
+            .field("bits", &self.bits())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ServerKey {
+    pub fn generate(bits: u32) -> Result<Self, KeyError> {
+        Ok(Self::new(openssl::rsa::Rsa::generate(bits)?))
+    }
+
+    /// Loads the private key from a PEM encoded PKCS#1 or PKCS#8 key
+    pub fn from_pem(pem: &[u8]) -> Result<Self, KeyError> {
+        let private_key = openssl::pkey::PKey::private_key_from_pem(pem)?.rsa()?;
+        Ok(Self::new(private_key))
+    }
+
+    /// Loads the key from the PEM file at the path, or generates a new one with the given size
+    /// and stores it there if the file does not exist yet
+    pub fn load_or_generate(
+        path: impl AsRef<std::path::Path>,
+        bits: u32,
+    ) -> Result<Self, KeyError> {
+        let path = path.as_ref();
+
+        match std::fs::read(path) {
+            Ok(pem) => Self::from_pem(&pem),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let key = Self::generate(bits)?;
+                write_private(path, &key.to_pem()?)?;
+                Ok(key)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn new(private_key: openssl::rsa::Rsa<openssl::pkey::Private>) -> Self {
+        let public_key =
+            protocol::login::encryption::asn1_encode_key(private_key.n(), private_key.e());
+
+        Self {
+            private_key,
+            public_key,
+        }
+    }
+
+    /// The private key as PEM encoded PKCS#1
+    pub fn to_pem(&self) -> Result<Vec<u8>, KeyError> {
+        Ok(self.private_key.private_key_to_pem()?)
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.private_key.size() * 8
+    }
+
+    /// The DER encoded public key
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    /// Decrypts data the client encrypted with the public key
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, KeyError> {
+        let mut result = vec![0; self.private_key.size() as usize];
+        let written =
+            self.private_key
+                .private_decrypt(data, &mut result, openssl::rsa::Padding::PKCS1)?;
+        result.truncate(written);
+        Ok(result)
+    }
+
+    /// Decrypts the encryption response of the client and checks that it contains the verify
+    /// token of the request, returning the shared secret
+    pub fn decrypt_response(
+        &self,
+        response: &protocol::login::server::EncryptionResponse,
+        verify_token: &[u8],
+    ) -> Result<Vec<u8>, KeyError> {
+        let shared_secret = self.decrypt(&response.shared_secret)?;
+        if self.decrypt(&response.verify_token)? != verify_token {
+            return Err(KeyError::InvalidVerifyToken);
+        }
+
+        Ok(shared_secret)
+    }
+}
+
+/// Creates the file only readable by the owner, as it contains the private key
+fn write_private(path: &std::path::Path, content: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    options.open(path)?.write_all(content)
+}
+
+/// A random verify token for the encryption request of a single login
+pub fn verify_token() -> [u8; 4] {
+    let mut token = [0; 4];
+    openssl::rand::rand_bytes(&mut token).expect("Generating random bytes");
+    token
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encrypt(public_key: &[u8], data: &[u8]) -> Vec<u8> {
+        let key = openssl::rsa::Rsa::public_key_from_der(public_key).unwrap();
+        let mut result = vec![0; key.size() as usize];
+        let written = key
+            .public_encrypt(data, &mut result, openssl::rsa::Padding::PKCS1)
+            .unwrap();
+        result.truncate(written);
+        result
+    }
+
+    #[test]
+    fn key_sizes() {
+        for bits in [1024, 2048] {
+            let key = ServerKey::generate(bits).unwrap();
+            assert_eq!(bits, key.bits());
+            assert_eq!(
+                key.private_key.public_key_to_der().unwrap(),
+                key.public_key()
+            );
+
+            let secret = [42; 16];
+            let encrypted = encrypt(key.public_key(), &secret);
+            assert_eq!(secret.to_vec(), key.decrypt(&encrypted).unwrap());
+        }
+    }
+
+    #[test]
+    fn pem_roundtrip() {
+        let key = ServerKey::generate(1024).unwrap();
+        let loaded = ServerKey::from_pem(&key.to_pem().unwrap()).unwrap();
+        assert_eq!(key.public_key(), loaded.public_key());
+
+        assert!(ServerKey::from_pem(b"not a key").is_err());
+    }
+
+    #[test]
+    fn load_or_generate() {
+        let path = std::env::temp_dir().join(format!("server-key-{}.pem", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let generated = ServerKey::load_or_generate(&path, 1024).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(0o600, mode & 0o777);
+        }
+        let loaded = ServerKey::load_or_generate(&path, 2048).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(1024, loaded.bits());
+        assert_eq!(generated.public_key(), loaded.public_key());
+    }
+
+    #[test]
+    fn verify_tokens() {
+        assert_ne!(verify_token(), verify_token());
+    }
+
+    #[test]
+    fn encryption_response() {
+        let key = ServerKey::generate(1024).unwrap();
+        let token = verify_token();
+        let response = |token: &[u8]| protocol::login::server::EncryptionResponse {
+            shared_secret: encrypt(key.public_key(), &[42; 16]),
+            verify_token: encrypt(key.public_key(), token),
+        };
+
+        assert_eq!(
+            vec![42; 16],
+            key.decrypt_response(&response(&token), &token).unwrap()
+        );
+
+        let wrong: Vec<u8> = token.iter().map(|b| !b).collect();
+        assert!(matches!(
+            key.decrypt_response(&response(&wrong), &token),
+            Err(KeyError::InvalidVerifyToken)
+        ));
+    }
+}
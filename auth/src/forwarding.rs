@@ -0,0 +1,195 @@
+//! Velocity-style modern player info forwarding
+//!
+//! When running behind a Velocity proxy, the proxy performs the authentication and forwards the
+//! player information to us using a Login Plugin Request/Response on the
+//! `velocity:player_info` channel. The response is signed with a HMAC-SHA256 using a secret
+//! shared between the proxy and the server.
+//!
+//! [Docs](https://docs.papermc.io/velocity/player-information-forwarding)
+
+use protocol::{
+    general::{PString, ParseError, VarInt},
+    login::{client::LoginPluginRequest, client::Property, server::LoginPluginResponse},
+    serialize::SerializeItem,
+};
+
+/// The channel used for the forwarding
+pub const CHANNEL: &str = "velocity:player_info";
+
+/// The forwarding version without the chat signing keys
+pub const MODERN_DEFAULT: u8 = 1;
+
+const SIGNATURE_LENGTH: usize = 32;
+
+/// The player information forwarded by the proxy
+#[derive(Debug, PartialEq, Clone)]
+pub struct ForwardedPlayer {
+    /// The address of the player connecting to the proxy
+    pub address: String,
+    pub uuid: u128,
+    pub name: String,
+    pub properties: Vec<Property>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ForwardingError {
+    /// The proxy did not understand the request, so the server is likely not behind a proxy
+    /// using modern forwarding
+    NotForwarded,
+    /// The response was not signed using the configured secret
+    InvalidSignature,
+    UnsupportedVersion(i32),
+    Parse(ParseError),
+}
+
+/// Computes the HMAC-SHA256 of the data with the given secret
+pub fn hmac_sha256(secret: &[u8], data: &[u8]) -> Vec<u8> {
+    let key = openssl::pkey::PKey::hmac(secret).expect("Any secret is a valid HMAC key");
+    let mut signer = openssl::sign::Signer::new(openssl::hash::MessageDigest::sha256(), &key)
+        .expect("SHA256 is supported for HMAC");
+    signer.update(data).expect("Updating a HMAC cant fail");
+    signer.sign_to_vec().expect("Signing a HMAC cant fail")
+}
+
+/// Creates the request send to the proxy
+pub fn request(message_id: i32) -> LoginPluginRequest {
+    LoginPluginRequest {
+        message_id: VarInt(message_id),
+        channel: PString(CHANNEL.into()),
+        data: vec![MODERN_DEFAULT],
+    }
+}
+
+/// Verifies the signature of the response and extracts the forwarded player information
+pub fn verify(
+    secret: &[u8],
+    response: &LoginPluginResponse,
+) -> Result<ForwardedPlayer, ForwardingError> {
+    let data = response
+        .data
+        .as_ref()
+        .ok_or(ForwardingError::NotForwarded)?;
+    if data.len() < SIGNATURE_LENGTH {
+        return Err(ForwardingError::InvalidSignature);
+    }
+
+    let (signature, content) = data.split_at(SIGNATURE_LENGTH);
+    let expected = hmac_sha256(secret, content);
+    if !openssl::memcmp::eq(signature, &expected) {
+        return Err(ForwardingError::InvalidSignature);
+    }
+
+    ForwardedPlayer::parse(content)
+}
+
+impl ForwardedPlayer {
+    fn parse(i: &[u8]) -> Result<Self, ForwardingError> {
+        let parse_err = |e: nom::Err<ParseError>| match e {
+            nom::Err::Error(e) | nom::Err::Failure(e) => ForwardingError::Parse(e),
+            nom::Err::Incomplete(_) => ForwardingError::Parse(ParseError::Other),
+        };
+
+        let (i, version) = VarInt::parse(i).map_err(parse_err)?;
+        if version.0 != MODERN_DEFAULT as i32 {
+            return Err(ForwardingError::UnsupportedVersion(version.0));
+        }
+
+        let (i, address) = PString::parse(i).map_err(parse_err)?;
+        let (i, uuid) = u128::parse(i).map_err(parse_err)?;
+        let (i, name) = PString::parse(i).map_err(parse_err)?;
+        let (_, properties) = Vec::<Property>::parse(i).map_err(parse_err)?;
+
+        Ok(Self {
+            address: address.0.into_owned(),
+            uuid,
+            name: name.0.into_owned(),
+            properties,
+        })
+    }
+
+    /// Creates the signed response, like the proxy would send it
+    pub fn response(&self, secret: &[u8], message_id: i32) -> LoginPluginResponse {
+        let fields = (
+            VarInt(MODERN_DEFAULT as i32),
+            PString(self.address.as_str().into()),
+            self.uuid,
+            (PString(self.name.as_str().into()), self.properties.clone()),
+        );
+
+        let mut content = vec![0; fields.slen()];
+        let remaining = fields
+            .serialize(&mut content)
+            .expect("The buffer has the correct size")
+            .len();
+        content.truncate(content.len() - remaining);
+
+        let mut data = hmac_sha256(secret, &content);
+        data.extend(content);
+
+        LoginPluginResponse {
+            message_id: VarInt(message_id),
+            data: Some(data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player() -> ForwardedPlayer {
+        ForwardedPlayer {
+            address: "127.0.0.1".to_string(),
+            uuid: 0x069a79f444e94726a5befca90e38aaf5,
+            name: "Notch".to_string(),
+            properties: vec![Property {
+                name: PString("textures".into()),
+                value: PString("value".into()),
+                signature: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let response = player().response(b"secret", 1);
+
+        assert_eq!(Ok(player()), verify(b"secret", &response));
+    }
+
+    #[test]
+    fn wrong_secret() {
+        let response = player().response(b"secret", 1);
+
+        assert_eq!(
+            Err(ForwardingError::InvalidSignature),
+            verify(b"other", &response)
+        );
+    }
+
+    #[test]
+    fn not_forwarded() {
+        let response = LoginPluginResponse {
+            message_id: VarInt(1),
+            data: None,
+        };
+
+        assert_eq!(
+            Err(ForwardingError::NotForwarded),
+            verify(b"secret", &response)
+        );
+    }
+
+    #[test]
+    fn hmac() {
+        // RFC 4231 Test Case 2
+        assert_eq!(
+            vec![
+                0x5b, 0xdc, 0xc1, 0x46, 0xbf, 0x60, 0x75, 0x4e, 0x6a, 0x04, 0x24, 0x26, 0x08, 0x95,
+                0x75, 0xc7, 0x5a, 0x00, 0x3f, 0x08, 0x9d, 0x27, 0x39, 0x83, 0x9d, 0xec, 0x58, 0xb9,
+                0x64, 0xec, 0x38, 0x43
+            ],
+            hmac_sha256(b"Jefe", b"what do ya want for nothing?")
+        );
+    }
+}
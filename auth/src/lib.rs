@@ -0,0 +1,333 @@
+//! Verifying the identity of players during the login
+//!
+//! Shared by the server and the proxy, which both verify players with a session server and
+//! exchange the verified profile using [`forwarding`].
+
+use protocol::{general::PString, login::client::Property};
+
+pub mod encryption;
+pub mod forwarding;
+
+/// The player as verified by an [`Authenticator`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub uuid: u128,
+    pub name: String,
+    /// The skin and cape of the player, signed by the session server
+    pub properties: Vec<Property>,
+}
+
+/// The state of the encryption of the connection, which the client also used to announce the
+/// join to the session server
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub server_id: String,
+    pub shared_secret: Vec<u8>,
+    /// The DER encoded public key send in the encryption request
+    pub public_key: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum AuthError {
+    /// The session server does not know about the player joining, either because the client is
+    /// not logged in or because the session does not match
+    NotAuthenticated,
+    /// The authenticator requires encryption, but the connection is not encrypted
+    MissingSession,
+    Request(reqwest::Error),
+    /// The session server responded with an unexpected status code
+    Status(u16),
+    /// The profile returned by the session server has an invalid UUID
+    InvalidProfile(uuid::Error),
+    /// A public key returned by the services API could not be decoded
+    InvalidServicesKey,
+}
+
+pub trait Authenticator {
+    /// Whether the connection has to be encrypted before authenticating, the resulting session
+    /// is then passed to [`authenticate`](Self::authenticate)
+    fn requires_encryption(&self) -> bool;
+
+    fn authenticate(
+        &self,
+        name: &str,
+        session: Option<&Session>,
+    ) -> impl core::future::Future<Output = Result<Profile, AuthError>> + Send;
+}
+
+/// Accepts every player without encryption and gives them the offline UUID of their name, like
+/// a vanilla server with `online-mode=false`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OfflineAuthenticator;
+
+impl Authenticator for OfflineAuthenticator {
+    fn requires_encryption(&self) -> bool {
+        false
+    }
+
+    async fn authenticate(
+        &self,
+        name: &str,
+        _session: Option<&Session>,
+    ) -> Result<Profile, AuthError> {
+        Ok(Profile {
+            uuid: protocol::login::offline_uuid(name),
+            name: name.to_string(),
+            properties: Vec::new(),
+        })
+    }
+}
+
+/// Verifies the players with a session server, which the client told about joining the server
+/// before sending the encryption response
+#[derive(Debug, Clone)]
+pub struct OnlineAuthenticator {
+    session_server: String,
+    client: reqwest::Client,
+}
+
+impl OnlineAuthenticator {
+    /// The session server of Mojang, which is used by the vanilla client
+    pub const MOJANG: &'static str = "https://sessionserver.mojang.com";
+
+    /// Uses the session server at the base URL, without a trailing `/`
+    pub fn new(session_server: impl Into<String>) -> Self {
+        Self {
+            session_server: session_server.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+impl Authenticator for OnlineAuthenticator {
+    fn requires_encryption(&self) -> bool {
+        true
+    }
+
+    async fn authenticate(
+        &self,
+        name: &str,
+        session: Option<&Session>,
+    ) -> Result<Profile, AuthError> {
+        let session = session.ok_or(AuthError::MissingSession)?;
+        let hash = protocol::login::encryption::server_hash(
+            &session.server_id,
+            &session.shared_secret,
+            &session.public_key,
+        );
+
+        let response = self
+            .client
+            .get(format!(
+                "{}/session/minecraft/hasJoined",
+                self.session_server
+            ))
+            .query(&[("username", name), ("serverId", &hash)])
+            .send()
+            .await
+            .map_err(AuthError::Request)?;
+
+        match response.status() {
+            reqwest::StatusCode::OK => {}
+            reqwest::StatusCode::NO_CONTENT => return Err(AuthError::NotAuthenticated),
+            other => return Err(AuthError::Status(other.as_u16())),
+        };
+
+        let profile: ProfileResponse = response.json().await.map_err(AuthError::Request)?;
+        let uuid = uuid::Uuid::parse_str(&profile.id).map_err(AuthError::InvalidProfile)?;
+
+        Ok(Profile {
+            uuid: uuid.as_u128(),
+            name: profile.name,
+            properties: profile
+                .properties
+                .into_iter()
+                .map(|p| Property {
+                    name: PString(p.name.into()),
+                    value: PString(p.value.into()),
+                    signature: Some(PString(p.signature.into())),
+                })
+                .collect(),
+        })
+    }
+}
+
+#[derive(Debug, serde_derive::Deserialize)]
+struct ProfileResponse {
+    id: String,
+    name: String,
+    properties: Vec<ProfileProperty>,
+}
+
+#[derive(Debug, serde_derive::Deserialize)]
+struct ProfileProperty {
+    name: String,
+    value: String,
+    signature: String,
+}
+
+/// The public keys of the Mojang services, which sign the chat session keys of the players
+pub const SERVICES_KEYS: &str = "https://api.minecraftservices.com/publickeys";
+
+#[derive(Debug, serde_derive::Deserialize)]
+struct ServicesKeysResponse {
+    #[serde(rename = "playerCertificateKeys")]
+    player_certificate_keys: Vec<ServicesKey>,
+}
+
+#[derive(Debug, serde_derive::Deserialize)]
+struct ServicesKey {
+    /// The base64 encoded DER of the key
+    #[serde(rename = "publicKey")]
+    public_key: String,
+}
+
+/// Loads the keys used to sign the chat session keys from the services API at the URL, usually
+/// [`SERVICES_KEYS`]
+pub async fn fetch_services_keys(
+    url: &str,
+) -> Result<Vec<openssl::pkey::PKey<openssl::pkey::Public>>, AuthError> {
+    use base64::Engine;
+
+    let response = reqwest::get(url).await.map_err(AuthError::Request)?;
+    if response.status() != reqwest::StatusCode::OK {
+        return Err(AuthError::Status(response.status().as_u16()));
+    }
+
+    let keys: ServicesKeysResponse = response.json().await.map_err(AuthError::Request)?;
+    keys.player_certificate_keys
+        .into_iter()
+        .map(|key| {
+            let der = base64::prelude::BASE64_STANDARD
+                .decode(key.public_key)
+                .map_err(|_| AuthError::InvalidServicesKey)?;
+            openssl::pkey::PKey::public_key_from_der(&der)
+                .map_err(|_| AuthError::InvalidServicesKey)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Answers a single request like a session server would, returning the request line
+    async fn session_server(listener: tokio::net::TcpListener, status: &str, body: &str) -> String {
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        let mut request = Vec::new();
+        while !request.ends_with(b"\r\n\r\n") {
+            let mut buffer = [0; 1024];
+            let read = stream.read(&mut buffer).await.unwrap();
+            assert_ne!(0, read);
+            request.extend_from_slice(&buffer[..read]);
+        }
+
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+
+        let request = String::from_utf8(request).unwrap();
+        request.lines().next().unwrap().to_string()
+    }
+
+    fn session() -> Session {
+        Session {
+            server_id: String::new(),
+            shared_secret: vec![42; 16],
+            public_key: vec![1, 2, 3],
+        }
+    }
+
+    #[tokio::test]
+    async fn offline() {
+        let profile = OfflineAuthenticator
+            .authenticate("Notch", None)
+            .await
+            .unwrap();
+
+        assert_eq!(0xb50ad385_829d_3141_a216_7e7d7539ba7f, profile.uuid);
+        assert_eq!("Notch", profile.name);
+    }
+
+    #[tokio::test]
+    async fn online() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let authenticator =
+            OnlineAuthenticator::new(format!("http://{}", listener.local_addr().unwrap()));
+
+        let body = r#"{"id":"069a79f444e94726a5befca90e38aaf5","name":"Notch","properties":[{"name":"textures","value":"abc","signature":"def"}]}"#;
+        let session = session();
+        let (request, profile) = tokio::join!(
+            session_server(listener, "200 OK", body),
+            authenticator.authenticate("Notch", Some(&session))
+        );
+
+        let hash = protocol::login::encryption::server_hash(
+            "",
+            &session.shared_secret,
+            &session.public_key,
+        );
+        assert_eq!(
+            format!(
+                "GET /session/minecraft/hasJoined?username=Notch&serverId={} HTTP/1.1",
+                hash
+            ),
+            request
+        );
+
+        let profile = profile.unwrap();
+        assert_eq!(0x069a79f4_44e9_4726_a5be_fca90e38aaf5, profile.uuid);
+        assert_eq!("textures", profile.properties[0].name.0);
+        assert_eq!(Some(PString("def".into())), profile.properties[0].signature);
+    }
+
+    #[tokio::test]
+    async fn online_not_joined() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let authenticator =
+            OnlineAuthenticator::new(format!("http://{}", listener.local_addr().unwrap()));
+
+        let session = session();
+        let (_, result) = tokio::join!(
+            session_server(listener, "204 No Content", ""),
+            authenticator.authenticate("Notch", Some(&session))
+        );
+        assert!(matches!(result, Err(AuthError::NotAuthenticated)));
+
+        assert!(matches!(
+            authenticator.authenticate("Notch", None).await,
+            Err(AuthError::MissingSession)
+        ));
+    }
+
+    #[tokio::test]
+    async fn services_keys() {
+        use base64::Engine;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/publickeys", listener.local_addr().unwrap());
+
+        let key = openssl::rsa::Rsa::generate(1024).unwrap();
+        let der = key.public_key_to_der().unwrap();
+        let body = format!(
+            r#"{{"profilePropertyKeys":[],"playerCertificateKeys":[{{"publicKey":"{}"}}]}}"#,
+            base64::prelude::BASE64_STANDARD.encode(&der)
+        );
+        let (request, keys) = tokio::join!(
+            session_server(listener, "200 OK", &body),
+            fetch_services_keys(&url)
+        );
+
+        assert_eq!("GET /publickeys HTTP/1.1", request);
+        let keys = keys.unwrap();
+        assert_eq!(1, keys.len());
+        assert_eq!(der, keys[0].public_key_to_der().unwrap());
+    }
+}
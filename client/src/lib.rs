@@ -0,0 +1,751 @@
+//! A headless client for scripted bots, used to load test and integration test a server
+//!
+//! The [`Client`] joins a server in offline mode like the vanilla client would. Once playing, it
+//! answers keep alives and teleports on its own and keeps track of the loaded chunks, the
+//! entities and its own position, while the bot drives it by consuming [`Event`]s.
+//!
+//! ```ignore
+//! let mut client = Client::connect("localhost:25565", &ClientConfig::new("Bot")).await?;
+//! client.chat("Hello").await?;
+//! loop {
+//!     if let Event::SystemMessage(message) = client.next_event().await? {
+//!         println!("{:?}", message);
+//!     }
+//! }
+//! ```
+
+use networking::{Connection, ConnectionConfig, ConnectionState, NetworkError, Transport};
+use protocol::{
+    general::{PString, ResourcePackResult, TextComponent, VarInt},
+    packet::{Packet, PacketContent, RawPacket},
+};
+
+mod world;
+pub use world::{Entity, PlayerPosition, World};
+
+pub use protocol::login::offline_uuid;
+
+/// The protocol version send in the handshake
+pub const PROTOCOL_VERSION: i32 = 766;
+
+/// The rate send in the acknowledgement of a chunk batch, this is the most the vanilla client
+/// ever asks for
+const CHUNKS_PER_TICK: f32 = 64.0;
+
+#[derive(Debug)]
+pub enum ClientError {
+    Network(NetworkError),
+    /// The server refused the login, the reason is a JSON text component
+    LoginRejected(String),
+    /// The server closed the connection during the configuration or while playing
+    Disconnected(TextComponent),
+    /// The server is in online mode, which is not supported
+    EncryptionRequested,
+    /// The server enabled compression, which is not supported
+    CompressionRequested,
+}
+
+impl From<NetworkError> for ClientError {
+    fn from(value: NetworkError) -> Self {
+        Self::Network(value)
+    }
+}
+
+impl From<std::io::Error> for ClientError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Network(value.into())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientConfig {
+    pub name: String,
+    /// The address send in the handshake, which the server may use for virtual hosts
+    pub server_addr: String,
+    pub server_port: u16,
+    pub locale: String,
+    pub view_distance: i8,
+    pub connection: ConnectionConfig,
+}
+
+impl ClientConfig {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            server_addr: "localhost".into(),
+            server_port: 25565,
+            locale: "en_us".into(),
+            view_distance: 8,
+            connection: ConnectionConfig::default(),
+        }
+    }
+}
+
+/// The things happening in the world, returned by [`Client::next_event`] after the client
+/// updated its state
+#[derive(Debug, PartialEq)]
+pub enum Event {
+    /// The server moved the player, the teleport is already confirmed
+    Teleported(PlayerPosition),
+    ChunkLoaded {
+        x: i32,
+        z: i32,
+    },
+    ChunkUnloaded {
+        x: i32,
+        z: i32,
+    },
+    EntitySpawned(i32),
+    EntitiesRemoved(Vec<i32>),
+    /// A message from the server itself, like the join message or the output of a command
+    SystemMessage(TextComponent),
+    PlayerMessage {
+        sender: u128,
+        message: String,
+    },
+    /// Any packet the client does not handle itself
+    Packet(RawPacket),
+}
+
+/// Parses the raw packet, packets that fail to parse are only logged as the client does not
+/// need to understand everything the server sends
+fn parse_raw<D, F>(mut parser: F, packet: &RawPacket) -> Option<D>
+where
+    F: protocol::packet::PacketContentParser<D>,
+{
+    match parser(packet.id, &packet.data) {
+        Ok(([], p)) => Some(p),
+        Ok(_) | Err(_) => {
+            tracing::debug!(id = packet.id.0, "Skipping unknown packet");
+            None
+        }
+    }
+}
+
+pub struct Client<T> {
+    connection: Connection<T>,
+    uuid: u128,
+    name: String,
+    entity_id: i32,
+    world: World,
+}
+
+impl Client<networking::UnencryptedConnection<tokio::net::TcpStream>> {
+    /// Connects to the server and joins it
+    pub async fn connect<A>(addr: A, config: &ClientConfig) -> Result<Self, ClientError>
+    where
+        A: tokio::net::ToSocketAddrs,
+    {
+        let stream = tokio::net::TcpStream::connect(addr).await?;
+        config.connection.configure_tcp(&stream)?;
+
+        let connection = Connection::with_config(
+            networking::UnencryptedConnection::new(stream),
+            bytes::BytesMut::with_capacity(4096),
+            config.connection.clone(),
+        );
+
+        Self::join(connection, config).await
+    }
+}
+
+impl<T> Client<T>
+where
+    T: Transport,
+{
+    /// Performs the handshake, login and configuration on the connection and waits until the
+    /// server let the player into the world
+    pub async fn join(
+        mut connection: Connection<T>,
+        config: &ClientConfig,
+    ) -> Result<Self, ClientError> {
+        connection.set_state(ConnectionState::Handshake);
+        send(
+            &mut connection,
+            protocol::handshake::server::Handshaking {
+                protocol_version: VarInt(PROTOCOL_VERSION),
+                server_addr: PString(config.server_addr.clone().into()),
+                server_port: config.server_port,
+                next_state: protocol::handshake::server::NextState::Login,
+            },
+        )
+        .await?;
+
+        connection.set_state(ConnectionState::Login);
+        let (uuid, name) = login(&mut connection, config).await?;
+
+        connection.set_state(ConnectionState::Configuration);
+        configuration(&mut connection, config).await?;
+
+        connection.set_state(ConnectionState::Play);
+        let login = connection
+            .recv_packet(protocol::play::client::Login::parse)
+            .await?;
+        tracing::info!(entity_id = login.inner.entity_id, "Joined the world");
+
+        Ok(Self {
+            connection,
+            uuid,
+            name,
+            entity_id: login.inner.entity_id,
+            world: World::default(),
+        })
+    }
+
+    /// Receives packets until one results in an event for the bot, the packets that only need a
+    /// response are handled along the way
+    pub async fn next_event(&mut self) -> Result<Event, ClientError> {
+        use protocol::play::client::Play;
+
+        loop {
+            let raw = self.connection.recv_rawpacket().await?;
+
+            let Some(packet) = parse_raw(Play::parse, &raw) else {
+                return Ok(Event::Packet(raw));
+            };
+
+            if let Some(event) = self.world.update(&packet) {
+                if let Play::SynchronizePlayerPosition(p) = &packet {
+                    self.confirm_teleport(p.teleport_id).await?;
+                }
+                return Ok(event);
+            }
+
+            match packet {
+                Play::KeepAlive(k) => {
+                    send(
+                        &mut self.connection,
+                        protocol::play::server::KeepAlive { id: k.id },
+                    )
+                    .await?;
+                }
+                Play::ChunkBatchFinished(_) => {
+                    send(
+                        &mut self.connection,
+                        protocol::play::server::ChunkBatchReceived {
+                            chunks_per_tick: CHUNKS_PER_TICK,
+                        },
+                    )
+                    .await?;
+                }
+                Play::AddResourcePack(p) => {
+                    for result in loaded_pack_results() {
+                        send(
+                            &mut self.connection,
+                            protocol::play::server::ResourcePackResponse {
+                                uuid: p.uuid,
+                                result,
+                            },
+                        )
+                        .await?;
+                    }
+                }
+                Play::CookieRequest(p) => {
+                    send(
+                        &mut self.connection,
+                        protocol::play::server::CookieResponse {
+                            key: p.key,
+                            payload: None,
+                        },
+                    )
+                    .await?;
+                }
+                Play::SystemChatMessage(p) if !p.overlay => {
+                    return Ok(Event::SystemMessage(p.content));
+                }
+                Play::PlayerChatMessage(p) => {
+                    return Ok(Event::PlayerMessage {
+                        sender: p.sender,
+                        message: p.message.0.into_owned(),
+                    });
+                }
+                Play::Disconnect(p) => return Err(ClientError::Disconnected(p.reason)),
+                _ => {}
+            };
+        }
+    }
+
+    /// Receives events until the predicate matches one, which is returned
+    pub async fn wait_for<F>(&mut self, mut predicate: F) -> Result<Event, ClientError>
+    where
+        F: FnMut(&Event) -> bool,
+    {
+        loop {
+            let event = self.next_event().await?;
+            if predicate(&event) {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// Sends an unsigned chat message
+    pub async fn chat(&mut self, message: impl Into<String>) -> Result<(), ClientError> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+
+        send(
+            &mut self.connection,
+            protocol::play::server::ChatMessage {
+                message: PString(message.into().into()),
+                timestamp,
+                salt: 0,
+                signature: None,
+                last_seen: protocol::chat::LastSeenUpdate {
+                    offset: VarInt(0),
+                    acknowledged: 0,
+                },
+            },
+        )
+        .await
+    }
+
+    /// Runs the command, without the leading `/`
+    pub async fn command(&mut self, command: impl Into<String>) -> Result<(), ClientError> {
+        send(
+            &mut self.connection,
+            protocol::play::server::ChatCommand {
+                command: PString(command.into().into()),
+            },
+        )
+        .await
+    }
+
+    /// Moves the player to the position, the server may still teleport the player back if the
+    /// movement is not allowed
+    pub async fn move_to(&mut self, x: f64, y: f64, z: f64) -> Result<(), ClientError> {
+        let position = PlayerPosition {
+            x,
+            y,
+            z,
+            ..self.world.position()
+        };
+        self.world.set_position(position);
+
+        send(
+            &mut self.connection,
+            protocol::play::server::SetPlayerPosition {
+                x,
+                feet_y: y,
+                z,
+                on_ground: true,
+            },
+        )
+        .await
+    }
+
+    pub async fn look(&mut self, yaw: f32, pitch: f32) -> Result<(), ClientError> {
+        let position = PlayerPosition {
+            yaw,
+            pitch,
+            ..self.world.position()
+        };
+        self.world.set_position(position);
+
+        send(
+            &mut self.connection,
+            protocol::play::server::SetPlayerRotation {
+                yaw,
+                pitch,
+                on_ground: true,
+            },
+        )
+        .await
+    }
+
+    /// Confirms the teleport and sends the new position, like the vanilla client does
+    async fn confirm_teleport(&mut self, teleport_id: VarInt) -> Result<(), ClientError> {
+        send(
+            &mut self.connection,
+            protocol::play::server::ConfirmTeleportation { teleport_id },
+        )
+        .await?;
+
+        let position = self.world.position();
+        send(
+            &mut self.connection,
+            protocol::play::server::SetPlayerPositionAndRotation {
+                x: position.x,
+                feet_y: position.y,
+                z: position.z,
+                yaw: position.yaw,
+                pitch: position.pitch,
+                on_ground: false,
+            },
+        )
+        .await
+    }
+}
+
+impl<T> Client<T> {
+    pub fn uuid(&self) -> u128 {
+        self.uuid
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn entity_id(&self) -> i32 {
+        self.entity_id
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// The underlying connection, to send packets the client has no method for
+    pub fn connection(&mut self) -> &mut Connection<T> {
+        &mut self.connection
+    }
+}
+
+async fn send<T, D>(connection: &mut Connection<T>, packet: D) -> Result<(), ClientError>
+where
+    T: Transport,
+    D: PacketContent,
+{
+    connection.send_packet(&Packet { inner: packet }).await?;
+    Ok(())
+}
+
+/// The bot does not render anything, so resource packs are reported as loaded without
+/// downloading them, otherwise servers requiring a pack would kick it
+fn loaded_pack_results() -> [ResourcePackResult; 3] {
+    [
+        ResourcePackResult::Accepted,
+        ResourcePackResult::Downloaded,
+        ResourcePackResult::SuccessfullyLoaded,
+    ]
+}
+
+/// Logs in using the offline UUID, returning the UUID and name assigned by the server
+async fn login<T>(
+    connection: &mut Connection<T>,
+    config: &ClientConfig,
+) -> Result<(u128, String), ClientError>
+where
+    T: Transport,
+{
+    use protocol::login::client::Login;
+
+    send(
+        connection,
+        protocol::login::server::LoginStart {
+            name: PString(config.name.clone().into()),
+            uuid: offline_uuid(&config.name),
+        },
+    )
+    .await?;
+
+    loop {
+        let packet = connection.recv_packet(Login::parse).await?;
+        match packet.inner {
+            Login::LoginSuccess(p) => {
+                send(connection, protocol::login::server::LoginAck {}).await?;
+                return Ok((p.uuid, p.name.0.into_owned()));
+            }
+            Login::Disconnect(p) => {
+                return Err(ClientError::LoginRejected(p.reason.0.into_owned()))
+            }
+            Login::EncryptionRequest(_) => return Err(ClientError::EncryptionRequested),
+            Login::SetCompression(_) => return Err(ClientError::CompressionRequested),
+            Login::LoginPluginRequest(p) => {
+                send(
+                    connection,
+                    protocol::login::server::LoginPluginResponse {
+                        message_id: p.message_id,
+                        data: None,
+                    },
+                )
+                .await?;
+            }
+            Login::CookieRequest(p) => {
+                send(
+                    connection,
+                    protocol::login::server::CookieResponse {
+                        key: p.key,
+                        payload: None,
+                    },
+                )
+                .await?;
+            }
+        };
+    }
+}
+
+/// Answers the configuration packets until the server finishes the configuration
+async fn configuration<T>(
+    connection: &mut Connection<T>,
+    config: &ClientConfig,
+) -> Result<(), ClientError>
+where
+    T: Transport,
+{
+    use protocol::configuration::client::Configuration;
+
+    send(
+        connection,
+        protocol::configuration::server::ClientInformation {
+            locale: PString(config.locale.clone().into()),
+            view_distance: config.view_distance,
+            chat_mode: VarInt(0),
+            chat_colors: true,
+            displayed_skin_parts: 0x7f,
+            main_hand: VarInt(1),
+            enable_text_filtering: false,
+            allow_server_listings: true,
+        },
+    )
+    .await?;
+
+    loop {
+        let raw = connection.recv_rawpacket().await?;
+
+        let Some(packet) = parse_raw(Configuration::parse, &raw) else {
+            continue;
+        };
+
+        match packet {
+            Configuration::KeepAlive(k) => {
+                send(
+                    connection,
+                    protocol::configuration::server::KeepAlive { id: k.id },
+                )
+                .await?;
+            }
+            // Claiming to know all the packs means the server does not have to send the
+            // content of the registries, which the client does not use anyway
+            Configuration::KnownPacks(p) => {
+                send(
+                    connection,
+                    protocol::configuration::server::KnownPacks { packs: p.packs },
+                )
+                .await?;
+            }
+            Configuration::AddResourcePack(p) => {
+                for result in loaded_pack_results() {
+                    send(
+                        connection,
+                        protocol::configuration::server::ResourcePackResponse {
+                            uuid: p.uuid,
+                            result,
+                        },
+                    )
+                    .await?;
+                }
+            }
+            Configuration::CookieRequest(p) => {
+                send(
+                    connection,
+                    protocol::configuration::server::CookieResponse {
+                        key: p.key,
+                        payload: None,
+                    },
+                )
+                .await?;
+            }
+            Configuration::Disconnect(p) => return Err(ClientError::Disconnected(p.reason)),
+            Configuration::Finish(_) => {
+                send(connection, protocol::configuration::server::AckFinish {}).await?;
+                return Ok(());
+            }
+            _ => {}
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use networking::memory::MemoryTransport;
+
+    use super::*;
+
+    async fn recv<D, F>(connection: &mut Connection<MemoryTransport>, parser: F) -> D
+    where
+        F: protocol::packet::PacketContentParser<D>,
+    {
+        connection.recv_packet(parser).await.unwrap().inner
+    }
+
+    async fn send_to<D>(connection: &mut Connection<MemoryTransport>, packet: D)
+    where
+        D: PacketContent,
+    {
+        connection
+            .send_packet(&Packet { inner: packet })
+            .await
+            .unwrap();
+    }
+
+    fn play_login(entity_id: i32) -> protocol::play::client::Login {
+        protocol::play::client::Login {
+            entity_id,
+            is_hardcore: false,
+            dimensions: vec![PString("minecraft:overworld".into())],
+            max_players: VarInt(20),
+            view_distance: VarInt(8),
+            simulation_distance: VarInt(8),
+            reduced_debug_info: false,
+            enable_respawn_rule: true,
+            do_limited_crafting: false,
+            dimension_type: VarInt(0),
+            dimension_name: PString("minecraft:overworld".into()),
+            hashed_seed: 0,
+            game_mode: 0,
+            previous_game_mode: -1,
+            is_debug: false,
+            is_flat: true,
+            death_location: None,
+            portal_cooldown: VarInt(0),
+            enforce_secure_chat: false,
+        }
+    }
+
+    /// Joins a scripted server and checks that the client answers the packets that need a
+    /// response on its own
+    #[tokio::test]
+    async fn join_and_play() {
+        let (connection, mut server) = networking::memory::pair(64 * 1024, Default::default());
+        let config = ClientConfig::new("Bot");
+
+        let script = async move {
+            let handshake =
+                recv(&mut server, protocol::handshake::server::Handshaking::parse).await;
+            assert_eq!(PROTOCOL_VERSION, handshake.protocol_version.0);
+
+            let start = recv(&mut server, protocol::login::server::LoginStart::parse).await;
+            assert_eq!(offline_uuid("Bot"), start.uuid);
+            send_to(
+                &mut server,
+                protocol::login::client::LoginSuccess {
+                    uuid: start.uuid,
+                    name: start.name,
+                    properites: Vec::new(),
+                },
+            )
+            .await;
+            recv(&mut server, protocol::login::server::LoginAck::parse).await;
+
+            let information = recv(
+                &mut server,
+                protocol::configuration::server::ClientInformation::parse,
+            )
+            .await;
+            assert_eq!(8, information.view_distance);
+
+            let packs = vec![(
+                PString("minecraft".into()),
+                PString("core".into()),
+                PString("1.20.6".into()),
+            )];
+            send_to(
+                &mut server,
+                protocol::configuration::client::KnownPacks {
+                    packs: packs.clone(),
+                },
+            )
+            .await;
+            let known = recv(
+                &mut server,
+                protocol::configuration::server::KnownPacks::parse,
+            )
+            .await;
+            assert_eq!(packs, known.packs);
+
+            send_to(
+                &mut server,
+                protocol::configuration::client::KeepAlive { id: 7 },
+            )
+            .await;
+            let keep_alive = recv(
+                &mut server,
+                protocol::configuration::server::KeepAlive::parse,
+            )
+            .await;
+            assert_eq!(7, keep_alive.id);
+
+            send_to(&mut server, protocol::configuration::client::Finish {}).await;
+            recv(
+                &mut server,
+                protocol::configuration::server::AckFinish::parse,
+            )
+            .await;
+
+            send_to(&mut server, play_login(12)).await;
+            send_to(
+                &mut server,
+                protocol::play::client::SynchronizePlayerPosition {
+                    x: 0.5,
+                    y: 64.0,
+                    z: 0.5,
+                    yaw: 0.0,
+                    pitch: 0.0,
+                    flags: 0,
+                    teleport_id: VarInt(5),
+                },
+            )
+            .await;
+            let confirm = recv(
+                &mut server,
+                protocol::play::server::ConfirmTeleportation::parse,
+            )
+            .await;
+            assert_eq!(5, confirm.teleport_id.0);
+            let position = recv(
+                &mut server,
+                protocol::play::server::SetPlayerPositionAndRotation::parse,
+            )
+            .await;
+            assert_eq!((0.5, 64.0, 0.5), (position.x, position.feet_y, position.z));
+
+            send_to(&mut server, protocol::play::client::KeepAlive { id: 9 }).await;
+            send_to(
+                &mut server,
+                protocol::play::client::SystemChatMessage {
+                    content: TextComponent::text("Welcome"),
+                    overlay: false,
+                },
+            )
+            .await;
+            let keep_alive = recv(&mut server, protocol::play::server::KeepAlive::parse).await;
+            assert_eq!(9, keep_alive.id);
+
+            let command = recv(&mut server, protocol::play::server::ChatCommand::parse).await;
+            assert_eq!("spawn", command.command.0);
+
+            send_to(
+                &mut server,
+                protocol::play::client::Disconnect {
+                    reason: TextComponent::text("Bye"),
+                },
+            )
+            .await;
+        };
+
+        let bot = async move {
+            let mut client = Client::join(connection, &config).await.unwrap();
+            assert_eq!(12, client.entity_id());
+            assert_eq!("Bot", client.name());
+
+            let event = client.next_event().await.unwrap();
+            assert!(matches!(event, Event::Teleported(_)));
+            assert_eq!(64.0, client.world().position().y);
+
+            let event = client.next_event().await.unwrap();
+            assert_eq!(Event::SystemMessage(TextComponent::text("Welcome")), event);
+
+            client.command("spawn").await.unwrap();
+
+            match client.next_event().await {
+                Err(ClientError::Disconnected(reason)) => {
+                    assert_eq!(TextComponent::text("Bye"), reason)
+                }
+                other => panic!("Expected to be disconnected: {:?}", other),
+            };
+        };
+
+        tokio::join!(script, bot);
+    }
+}
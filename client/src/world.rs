@@ -0,0 +1,230 @@
+use std::collections::{HashMap, HashSet};
+
+use protocol::play::client::Play;
+use registry::EntityType;
+
+use crate::Event;
+
+/// The position of the player controlled by the client
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PlayerPosition {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+/// An entity the server told the client about
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entity {
+    pub uuid: u128,
+    /// The type of the entity, `None` if the server sent an unknown id
+    pub kind: Option<EntityType>,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+/// The part of the world the client knows about, only the positions of chunks and entities are
+/// tracked and not their content
+#[derive(Debug, Default)]
+pub struct World {
+    position: PlayerPosition,
+    chunks: HashSet<(i32, i32)>,
+    entities: HashMap<i32, Entity>,
+}
+
+impl World {
+    pub fn position(&self) -> PlayerPosition {
+        self.position
+    }
+
+    pub(crate) fn set_position(&mut self, position: PlayerPosition) {
+        self.position = position;
+    }
+
+    /// The `(x, z)` coordinates of the loaded chunks
+    pub fn chunks(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.chunks.iter().copied()
+    }
+
+    pub fn is_loaded(&self, chunk_x: i32, chunk_z: i32) -> bool {
+        self.chunks.contains(&(chunk_x, chunk_z))
+    }
+
+    pub fn entities(&self) -> &HashMap<i32, Entity> {
+        &self.entities
+    }
+
+    pub fn entity(&self, entity_id: i32) -> Option<&Entity> {
+        self.entities.get(&entity_id)
+    }
+
+    /// Applies the packet to the world, returning the event for the bot if it changed something
+    /// the bot might care about
+    pub(crate) fn update(&mut self, packet: &Play) -> Option<Event> {
+        match packet {
+            Play::SynchronizePlayerPosition(p) => {
+                // Each bit of the flags marks the matching field as relative to the current value
+                let relative = |bit: i8, current: f64, value: f64| {
+                    if p.flags & bit != 0 {
+                        current + value
+                    } else {
+                        value
+                    }
+                };
+
+                let current = self.position;
+                self.position = PlayerPosition {
+                    x: relative(0x01, current.x, p.x),
+                    y: relative(0x02, current.y, p.y),
+                    z: relative(0x04, current.z, p.z),
+                    yaw: relative(0x08, current.yaw as f64, p.yaw as f64) as f32,
+                    pitch: relative(0x10, current.pitch as f64, p.pitch as f64) as f32,
+                };
+                Some(Event::Teleported(self.position))
+            }
+            Play::ChunkDataAndUpdateLight(p) => {
+                self.chunks.insert((p.chunk_x, p.chunk_z));
+                Some(Event::ChunkLoaded {
+                    x: p.chunk_x,
+                    z: p.chunk_z,
+                })
+            }
+            Play::UnloadChunk(p) => {
+                self.chunks.remove(&(p.chunk_x, p.chunk_z));
+                Some(Event::ChunkUnloaded {
+                    x: p.chunk_x,
+                    z: p.chunk_z,
+                })
+            }
+            Play::SpawnEntity(p) => {
+                self.entities.insert(
+                    p.entity_id.0,
+                    Entity {
+                        uuid: p.entity_uuid,
+                        kind: EntityType::from_id(p.ty.0),
+                        x: p.x,
+                        y: p.y,
+                        z: p.z,
+                    },
+                );
+                Some(Event::EntitySpawned(p.entity_id.0))
+            }
+            Play::RemoveEntities(p) => {
+                for id in p.entity_ids.iter() {
+                    self.entities.remove(&id.0);
+                }
+                Some(Event::EntitiesRemoved(
+                    p.entity_ids.iter().map(|id| id.0).collect(),
+                ))
+            }
+            Play::UpdateEntityPosition(p) => {
+                self.move_entity(p.entity_id.0, p.delta_x, p.delta_y, p.delta_z);
+                None
+            }
+            Play::UpdateEntityPositionAndRotation(p) => {
+                self.move_entity(p.entity_id.0, p.delta_x, p.delta_y, p.delta_z);
+                None
+            }
+            Play::TeleportEntity(p) => {
+                if let Some(entity) = self.entities.get_mut(&p.entity_id.0) {
+                    entity.x = p.x;
+                    entity.y = p.y;
+                    entity.z = p.z;
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// The deltas are in 1/4096 of a block
+    fn move_entity(&mut self, entity_id: i32, delta_x: i16, delta_y: i16, delta_z: i16) {
+        if let Some(entity) = self.entities.get_mut(&entity_id) {
+            entity.x += delta_x as f64 / 4096.0;
+            entity.y += delta_y as f64 / 4096.0;
+            entity.z += delta_z as f64 / 4096.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use protocol::general::VarInt;
+    use protocol::play::client::{
+        RemoveEntities, SpawnEntity, SynchronizePlayerPosition, UpdateEntityPosition,
+    };
+
+    use super::*;
+
+    #[test]
+    fn relative_teleport() {
+        let mut world = World::default();
+
+        let teleport = |x, flags| {
+            Play::SynchronizePlayerPosition(SynchronizePlayerPosition {
+                x,
+                y: 64.0,
+                z: 10.0,
+                yaw: 90.0,
+                pitch: 0.0,
+                flags,
+                teleport_id: VarInt(1),
+            })
+        };
+
+        world.update(&teleport(5.0, 0));
+        let event = world.update(&teleport(2.5, 0x01 | 0x08));
+
+        let expected = PlayerPosition {
+            x: 7.5,
+            y: 64.0,
+            z: 10.0,
+            yaw: 180.0,
+            pitch: 0.0,
+        };
+        assert_eq!(expected, world.position());
+        assert_eq!(Some(Event::Teleported(expected)), event);
+    }
+
+    #[test]
+    fn entity_lifecycle() {
+        let mut world = World::default();
+
+        let event = world.update(&Play::SpawnEntity(SpawnEntity {
+            entity_id: VarInt(3),
+            entity_uuid: 42,
+            ty: VarInt(EntityType::Player.id()),
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+            pitch: 0,
+            yaw: 0,
+            head_yaw: 0,
+            data: VarInt(0),
+            velocity_x: 0,
+            velocity_y: 0,
+            velocity_z: 0,
+        }));
+        assert_eq!(Some(Event::EntitySpawned(3)), event);
+
+        world.update(&Play::UpdateEntityPosition(UpdateEntityPosition {
+            entity_id: VarInt(3),
+            delta_x: 4096,
+            delta_y: -2048,
+            delta_z: 0,
+            on_ground: true,
+        }));
+        let entity = world.entity(3).unwrap();
+        assert_eq!(Some(EntityType::Player), entity.kind);
+        assert_eq!((2.0, 1.5, 3.0), (entity.x, entity.y, entity.z));
+
+        let event = world.update(&Play::RemoveEntities(RemoveEntities {
+            entity_ids: vec![VarInt(3)],
+        }));
+        assert_eq!(Some(Event::EntitiesRemoved(vec![3])), event);
+        assert!(world.entities().is_empty());
+    }
+}
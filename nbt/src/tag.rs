@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Tag {
     End,
     Byte(i8),
@@ -18,141 +18,91 @@ pub enum Tag {
 }
 
 impl Tag {
+    /// The ID used to identify the type of the tag
+    pub fn id(&self) -> u8 {
+        match self {
+            Self::End => 0x00,
+            Self::Byte(_) => 0x01,
+            Self::Short(_) => 0x02,
+            Self::Int(_) => 0x03,
+            Self::Long(_) => 0x04,
+            Self::Float(_) => 0x05,
+            Self::Double(_) => 0x06,
+            Self::ByteArray(_) => 0x07,
+            Self::String_(_) => 0x08,
+            Self::List(_) => 0x09,
+            Self::Compound(_) => 0x0a,
+            Self::IntArray(_) => 0x0b,
+            Self::LongArray(_) => 0x0c,
+        }
+    }
+
+    /// Parses a complete tag, including its ID and name.
+    ///
+    /// If `network` is set, a compound tag is expected to have no name, like the root compound
+    /// send over the network since 1.20.2
     pub fn parse(
         from_file: bool,
         network: bool,
     ) -> impl FnMut(&[u8]) -> nom::IResult<&[u8], (String, Self), ()> {
         move |i| {
-            nom::branch::alt((
-                nom::combinator::map(
-                    nom::sequence::tuple((nom::bytes::streaming::tag([0x00]),)),
-                    |_| (String::new(), Self::End),
-                ),
-                nom::combinator::map(
-                    nom::sequence::tuple((
-                        nom::bytes::streaming::tag([0x01]),
-                        Self::parse_name,
-                        nom::number::streaming::i8,
-                    )),
-                    |(_, name, v)| (name, Self::Byte(v)),
-                ),
-                nom::combinator::map(
-                    nom::sequence::tuple((
-                        nom::bytes::streaming::tag([0x02]),
-                        Self::parse_name,
-                        nom::number::streaming::be_i16,
-                    )),
-                    |(_, name, v)| (name, Self::Short(v)),
-                ),
-                nom::combinator::map(
-                    nom::sequence::tuple((
-                        nom::bytes::streaming::tag([0x03]),
-                        Self::parse_name,
-                        nom::number::streaming::be_i32,
-                    )),
-                    |(_, name, v)| (name, Self::Int(v)),
-                ),
-                nom::combinator::map(
-                    nom::sequence::tuple((
-                        nom::bytes::streaming::tag([0x04]),
-                        Self::parse_name,
-                        nom::number::streaming::be_i64,
-                    )),
-                    |(_, name, v)| (name, Self::Long(v)),
-                ),
-                nom::combinator::map(
-                    nom::sequence::tuple((
-                        nom::bytes::streaming::tag([0x05]),
-                        Self::parse_name,
-                        nom::number::streaming::be_f32,
-                    )),
-                    |(_, name, v)| (name, Self::Float(v)),
-                ),
-                nom::combinator::map(
-                    nom::sequence::tuple((
-                        nom::bytes::streaming::tag([0x06]),
-                        Self::parse_name,
-                        nom::number::streaming::be_f64,
-                    )),
-                    |(_, name, v)| (name, Self::Double(v)),
-                ),
-                nom::combinator::map(
-                    nom::sequence::tuple((
-                        nom::bytes::streaming::tag([0x07]),
-                        Self::parse_name,
-                        Self::parse_byte_array,
-                    )),
-                    |(_, name, v)| (name, Self::ByteArray(v)),
-                ),
-                nom::combinator::map(
-                    nom::sequence::tuple((
-                        nom::bytes::streaming::tag([0x08]),
-                        Self::parse_name,
-                        Self::parse_string,
-                    )),
-                    |(_, name, string)| (name, Self::String_(string)),
-                ),
-                nom::combinator::map(
-                    nom::sequence::tuple((
-                        nom::bytes::streaming::tag([0x09]),
-                        Self::parse_name,
-                        Self::parse_list,
-                    )),
-                    |(_, name, v)| (name, Self::List(v)),
-                ),
-                nom::combinator::map(
-                    nom::sequence::tuple((
-                        nom::bytes::streaming::tag([0x0a]),
-                        nom::combinator::cond(!network, Self::parse_name),
-                        Self::parse_compound_inner,
-                    )),
-                    |(_, name, parts)| (name.unwrap_or(String::new()), Self::Compound(parts)),
-                ),
-                nom::combinator::map(
-                    nom::sequence::tuple((
-                        nom::bytes::streaming::tag([0x0b]),
-                        Self::parse_name,
-                        Self::parse_int_array,
-                    )),
-                    |(_, name, v)| (name, Self::IntArray(v)),
-                ),
-                nom::combinator::map(
-                    nom::sequence::tuple((
-                        nom::bytes::streaming::tag([0x0c]),
-                        Self::parse_name,
-                        Self::parse_long_array,
-                    )),
-                    |(_, name, v)| (name, Self::LongArray(v)),
-                ),
-            ))(i)
-        }
-    }
+            let (i, id) = nom::number::streaming::u8(i)?;
+            if id == 0x00 {
+                return Ok((i, (String::new(), Self::End)));
+            }
 
-    fn parse_name(i: &[u8]) -> nom::IResult<&[u8], String, ()> {
-        let (i, length) = nom::number::streaming::be_u16(i)?;
+            let (i, name) = if network && id == 0x0a {
+                (i, String::new())
+            } else {
+                Self::parse_name(i)?
+            };
 
-        let raw_name = &i[..length as usize];
-        let i = &i[length as usize..];
+            let (i, value) = Self::parse_payload(id)(i)?;
 
-        let name = core::str::from_utf8(raw_name).unwrap();
+            Ok((i, (name, value)))
+        }
+    }
 
-        Ok((i, name.to_string()))
+    /// Parses only the payload of a tag with the given ID, like the entries of a list
+    pub fn parse_payload(id: u8) -> impl FnMut(&[u8]) -> nom::IResult<&[u8], Self, ()> {
+        move |i| match id {
+            0x00 => Ok((i, Self::End)),
+            0x01 => nom::combinator::map(nom::number::streaming::i8, Self::Byte)(i),
+            0x02 => nom::combinator::map(nom::number::streaming::be_i16, Self::Short)(i),
+            0x03 => nom::combinator::map(nom::number::streaming::be_i32, Self::Int)(i),
+            0x04 => nom::combinator::map(nom::number::streaming::be_i64, Self::Long)(i),
+            0x05 => nom::combinator::map(nom::number::streaming::be_f32, Self::Float)(i),
+            0x06 => nom::combinator::map(nom::number::streaming::be_f64, Self::Double)(i),
+            0x07 => nom::combinator::map(Self::parse_byte_array, Self::ByteArray)(i),
+            0x08 => nom::combinator::map(Self::parse_string, Self::String_)(i),
+            0x09 => nom::combinator::map(Self::parse_list, Self::List)(i),
+            0x0a => nom::combinator::map(Self::parse_compound_inner, Self::Compound)(i),
+            0x0b => nom::combinator::map(Self::parse_int_array, Self::IntArray)(i),
+            0x0c => nom::combinator::map(Self::parse_long_array, Self::LongArray)(i),
+            _ => Err(nom::Err::Error(())),
+        }
+    }
+
+    fn parse_name(i: &[u8]) -> nom::IResult<&[u8], String, ()> {
+        Self::parse_string(i)
     }
 
     fn parse_byte_array(i: &[u8]) -> nom::IResult<&[u8], Vec<u8>, ()> {
         let (i, length) = nom::number::streaming::be_i32(i)?;
+        if length < 0 {
+            return Err(nom::Err::Error(()));
+        }
 
-        let raw_content = &i[..length as usize];
-        let i = &i[length as usize..];
+        let (i, raw_content) = nom::bytes::streaming::take(length as usize)(i)?;
 
         Ok((i, raw_content.to_vec()))
     }
 
     fn parse_string(i: &[u8]) -> nom::IResult<&[u8], String, ()> {
         let (i, length) = nom::number::streaming::be_u16(i)?;
+        let (i, raw_str) = nom::bytes::streaming::take(length as usize)(i)?;
 
-        let raw_str = core::str::from_utf8(&i[..length as usize]).unwrap();
-        let i = &i[length as usize..];
+        let raw_str = core::str::from_utf8(raw_str).map_err(|_| nom::Err::Error(()))?;
 
         Ok((i, raw_str.to_string()))
     }
@@ -160,19 +110,23 @@ impl Tag {
     fn parse_list(i: &[u8]) -> nom::IResult<&[u8], Vec<Tag>, ()> {
         let (i, expected_tag) = nom::number::streaming::u8(i)?;
 
-        let (i, length) = nom::number::streaming::be_i32(i)?;
+        let (mut i, length) = nom::number::streaming::be_i32(i)?;
 
-        let mut result = Vec::with_capacity(length as usize);
+        let mut result = Vec::with_capacity(length.max(0) as usize);
         for _ in 0..length {
-            todo!("How do we only parse the content but not the name?")
+            let (n_i, r) = Self::parse_payload(expected_tag)(i)?;
+            i = n_i;
+            result.push(r);
         }
 
         Ok((i, result))
     }
 
     fn parse_compound_inner(i: &[u8]) -> nom::IResult<&[u8], HashMap<String, Tag>, ()> {
-        let (i, (inner, _)) =
-            nom::multi::many_till(Self::parse(false, false), nom::bytes::complete::tag([0x00]))(i)?;
+        let (i, (inner, _)) = nom::multi::many_till(
+            Self::parse(false, false),
+            nom::bytes::streaming::tag([0x00]),
+        )(i)?;
 
         Ok((i, inner.into_iter().collect()))
     }
@@ -180,7 +134,7 @@ impl Tag {
     fn parse_int_array(i: &[u8]) -> nom::IResult<&[u8], Vec<i32>, ()> {
         let (mut i, length) = nom::number::streaming::be_i32(i)?;
 
-        let mut result = Vec::with_capacity(length as usize);
+        let mut result = Vec::with_capacity(length.max(0) as usize);
         for _ in 0..length {
             let (n_i, r) = nom::number::streaming::be_i32(i)?;
             i = n_i;
@@ -193,7 +147,7 @@ impl Tag {
     fn parse_long_array(i: &[u8]) -> nom::IResult<&[u8], Vec<i64>, ()> {
         let (mut i, length) = nom::number::streaming::be_i32(i)?;
 
-        let mut result = Vec::with_capacity(length as usize);
+        let mut result = Vec::with_capacity(length.max(0) as usize);
         for _ in 0..length {
             let (n_i, r) = nom::number::streaming::be_i64(i)?;
             i = n_i;
@@ -203,6 +157,10 @@ impl Tag {
         Ok((i, result))
     }
 
+    /// Serializes the complete tag, including its ID and name.
+    ///
+    /// If no name is given, the name is omitted completely, which is used for the root tags send
+    /// over the network since 1.20.2
     pub fn serialize(
         &self,
         file: bool,
@@ -210,135 +168,104 @@ impl Tag {
         name: Option<&str>,
         buf: &mut Vec<u8>,
     ) -> Result<(), ()> {
-        match self {
-            Self::End => {
-                buf.push(0x00);
-
-                Ok(())
-            }
-            Self::Byte(val) => {
-                buf.push(0x01);
-
-                let name = name.ok_or(())?;
-                buf.extend((name.len() as u16).to_be_bytes());
-                buf.extend(name.as_bytes());
-
-                buf.extend(val.to_be_bytes());
-
-                Ok(())
-            }
-            Self::Short(val) => {
-                buf.push(0x02);
-
-                let name = name.ok_or(())?;
-                buf.extend((name.len() as u16).to_be_bytes());
-                buf.extend(name.as_bytes());
-
-                buf.extend(val.to_be_bytes());
-
-                Ok(())
-            }
-            Self::Int(val) => {
-                buf.push(0x03);
+        buf.push(self.id());
 
-                let name = name.ok_or(())?;
-                buf.extend((name.len() as u16).to_be_bytes());
-                buf.extend(name.as_bytes());
-
-                buf.extend(val.to_be_bytes());
-
-                Ok(())
-            }
-            Self::Long(val) => {
-                buf.push(0x04);
-
-                let name = name.ok_or(())?;
-                buf.extend((name.len() as u16).to_be_bytes());
-                buf.extend(name.as_bytes());
-
-                buf.extend(val.to_be_bytes());
-
-                Ok(())
-            }
-            Self::Float(val) => {
-                buf.push(0x05);
-
-                let name = name.ok_or(())?;
-                buf.extend((name.len() as u16).to_be_bytes());
-                buf.extend(name.as_bytes());
-
-                buf.extend(val.to_be_bytes());
-
-                Ok(())
-            }
-            Self::Double(val) => {
-                buf.push(0x06);
-
-                let name = name.ok_or(())?;
-                buf.extend((name.len() as u16).to_be_bytes());
-                buf.extend(name.as_bytes());
-
-                buf.extend(val.to_be_bytes());
+        if let Self::End = self {
+            return Ok(());
+        }
 
-                Ok(())
-            }
-            Self::String_(val) => {
-                buf.push(0x08);
+        if let Some(name) = name {
+            Self::serialize_string(name, buf)?;
+        }
 
-                let name = name.ok_or(())?;
-                buf.extend((name.len() as u16).to_be_bytes());
-                buf.extend(name.as_bytes());
+        self.serialize_payload(buf)
+    }
 
-                buf.extend((val.len() as u16).to_be_bytes());
-                buf.extend(val.as_bytes());
+    fn serialize_string(value: &str, buf: &mut Vec<u8>) -> Result<(), ()> {
+        let length: u16 = value.len().try_into().map_err(|_| ())?;
+        buf.extend(length.to_be_bytes());
+        buf.extend(value.as_bytes());
+        Ok(())
+    }
 
-                Ok(())
-            }
+    /// Serializes only the payload of the tag, without its ID and name
+    fn serialize_payload(&self, buf: &mut Vec<u8>) -> Result<(), ()> {
+        match self {
+            Self::End => {}
+            Self::Byte(val) => buf.extend(val.to_be_bytes()),
+            Self::Short(val) => buf.extend(val.to_be_bytes()),
+            Self::Int(val) => buf.extend(val.to_be_bytes()),
+            Self::Long(val) => buf.extend(val.to_be_bytes()),
+            Self::Float(val) => buf.extend(val.to_be_bytes()),
+            Self::Double(val) => buf.extend(val.to_be_bytes()),
+            Self::String_(val) => Self::serialize_string(val, buf)?,
             Self::ByteArray(vs) => {
-                buf.push(0x07);
-
-                let name = name.ok_or(())?;
-                buf.extend((name.len() as u16).to_be_bytes());
-                buf.extend(name.as_bytes());
-
                 buf.extend((vs.len() as i32).to_be_bytes());
                 buf.extend(vs.iter().copied());
-
-                Ok(())
             }
             Self::IntArray(vs) => {
-                buf.push(0x0b);
-
-                let name = name.ok_or(())?;
-                buf.extend((name.len() as u16).to_be_bytes());
-                buf.extend(name.as_bytes());
-
                 buf.extend((vs.len() as i32).to_be_bytes());
-
                 buf.extend(vs.iter().flat_map(|v| v.to_be_bytes()));
-
-                Ok(())
             }
             Self::LongArray(vs) => {
-                buf.push(0x0c);
-
-                let name = name.ok_or(())?;
-                buf.extend((name.len() as u16).to_be_bytes());
-                buf.extend(name.as_bytes());
-
                 buf.extend((vs.len() as i32).to_be_bytes());
-
                 buf.extend(vs.iter().flat_map(|v| v.to_be_bytes()));
-
-                Ok(())
             }
             Self::List(vs) => {
-                dbg!(vs);
-                todo!("List")
+                // An empty list uses the End-Tag as its type
+                let id = vs.first().map(|v| v.id()).unwrap_or(0x00);
+                if vs.iter().any(|v| v.id() != id) {
+                    return Err(());
+                }
+
+                buf.push(id);
+                buf.extend((vs.len() as i32).to_be_bytes());
+                for v in vs {
+                    v.serialize_payload(buf)?;
+                }
+            }
+            Self::Compound(vs) => {
+                // Sorted by name, to get a stable output
+                let mut entries: Vec<_> = vs.iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+
+                for (name, v) in entries {
+                    v.serialize(false, false, Some(name), buf)?;
+                }
+                buf.push(0x00);
             }
+        };
+
+        Ok(())
+    }
+
+    /// The number of bytes written by [`serialize`](Self::serialize) with the same name, without
+    /// serializing the tag
+    pub fn serialized_len(&self, name: Option<&str>) -> usize {
+        if let Self::End = self {
+            return 1;
+        }
+
+        1 + name.map(|n| 2 + n.len()).unwrap_or(0) + self.payload_len()
+    }
+
+    fn payload_len(&self) -> usize {
+        match self {
+            Self::End => 0,
+            Self::Byte(_) => 1,
+            Self::Short(_) => 2,
+            Self::Int(_) | Self::Float(_) => 4,
+            Self::Long(_) | Self::Double(_) => 8,
+            Self::String_(val) => 2 + val.len(),
+            Self::ByteArray(vs) => 4 + vs.len(),
+            Self::IntArray(vs) => 4 + vs.len() * 4,
+            Self::LongArray(vs) => 4 + vs.len() * 8,
+            Self::List(vs) => 1 + 4 + vs.iter().map(|v| v.payload_len()).sum::<usize>(),
             Self::Compound(vs) => {
-                dbg!(vs);
-                todo!("Compound")
+                vs.iter()
+                    .map(|(name, v)| v.serialized_len(Some(name)))
+                    .sum::<usize>()
+                    + 1
             }
         }
     }
@@ -366,6 +293,34 @@ mod tests {
             result
         );
     }
+
+    #[test]
+    fn serialized_len() {
+        let tag = Tag::Compound(
+            [
+                ("byte".into(), Tag::Byte(1)),
+                ("string".into(), Tag::String_("value".into())),
+                (
+                    "list".into(),
+                    Tag::List(vec![Tag::Compound(
+                        [("long".into(), Tag::LongArray(vec![1, 2, 3]))]
+                            .into_iter()
+                            .collect(),
+                    )]),
+                ),
+                ("empty".into(), Tag::List(Vec::new())),
+                ("end".into(), Tag::End),
+            ]
+            .into_iter()
+            .collect(),
+        );
+
+        for name in [None, Some("root")] {
+            let mut buffer = Vec::new();
+            tag.serialize(false, true, name, &mut buffer).unwrap();
+            assert_eq!(buffer.len(), tag.serialized_len(name));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -374,14 +329,17 @@ mod serialize_parse_tests {
 
     macro_rules! parsed {
         ($value:expr, $name:literal) => {{
+            parsed!($value, $name, true)
+        }};
+        ($value:expr, $name:literal, $network:literal) => {{
             let src_value = $value;
 
             let mut buffer = Vec::new();
             src_value
-                .serialize(false, true, Some($name), &mut buffer)
+                .serialize(false, $network, Some($name), &mut buffer)
                 .unwrap();
 
-            let (rem, (parsed_name, parsed_value)) = Tag::parse(false, true)(&buffer).unwrap();
+            let (rem, (parsed_name, parsed_value)) = Tag::parse(false, $network)(&buffer).unwrap();
             assert_eq!(&[] as &[u8], rem);
             assert_eq!($name, parsed_name);
             assert_eq!(src_value, parsed_value);
@@ -448,13 +406,69 @@ mod serialize_parse_tests {
 
     #[test]
     fn compound_float() {
+        // Root compounds only have a name outside of the network format
         parsed!(
             Tag::Compound(
                 vec![("first".to_string(), Tag::Float(123.0))]
                     .into_iter()
                     .collect()
             ),
-            "test"
+            "test",
+            false
+        );
+    }
+
+    #[test]
+    fn compound_network() {
+        let src_value = Tag::Compound(
+            vec![
+                ("first".to_string(), Tag::Float(123.0)),
+                (
+                    "nested".to_string(),
+                    Tag::Compound(
+                        vec![("inner".to_string(), Tag::String_("value".into()))]
+                            .into_iter()
+                            .collect(),
+                    ),
+                ),
+                (
+                    "list".to_string(),
+                    Tag::List(vec![
+                        Tag::Compound(vec![("a".to_string(), Tag::Byte(1))].into_iter().collect()),
+                        Tag::Compound(HashMap::new()),
+                    ]),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        );
+
+        let mut buffer = Vec::new();
+        src_value.serialize(false, true, None, &mut buffer).unwrap();
+        assert_eq!(&[0x0a, 0x05, 0x00, 0x05], &buffer[..4]);
+
+        let (rem, (parsed_name, parsed_value)) = Tag::parse(false, true)(&buffer).unwrap();
+        assert_eq!(&[] as &[u8], rem);
+        assert_eq!("", parsed_name);
+        assert_eq!(src_value, parsed_value);
+    }
+
+    #[test]
+    fn empty_list() {
+        parsed!(Tag::List(Vec::new()), "test");
+    }
+
+    #[test]
+    fn mixed_list() {
+        let mut buffer = Vec::new();
+        assert_eq!(
+            Err(()),
+            Tag::List(vec![Tag::Byte(1), Tag::Int(2)]).serialize(
+                false,
+                true,
+                Some("test"),
+                &mut buffer
+            )
         );
     }
 }
@@ -0,0 +1,205 @@
+//! The AES-128-CFB8 stream cipher used for encrypted connections
+//!
+//! After the login both directions are encrypted independently, using the shared secret as the
+//! key and the IV. The backend is selected by the `openssl` and `rustcrypto` features, with
+//! [`DefaultCipher`] preferring OpenSSL if both are enabled.
+
+#[cfg(not(any(feature = "openssl", feature = "rustcrypto")))]
+compile_error!("Either the `openssl` or the `rustcrypto` feature has to be enabled");
+
+/// The length of the shared secret in bytes
+pub const SECRET_LENGTH: usize = 16;
+
+#[cfg(feature = "openssl")]
+pub type DefaultCipher = OpenSslCipher;
+#[cfg(all(feature = "rustcrypto", not(feature = "openssl")))]
+pub type DefaultCipher = RustCryptoCipher;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Encrypt,
+    Decrypt,
+}
+
+#[derive(Debug)]
+pub enum CipherError {
+    /// The shared secret does not have [`SECRET_LENGTH`] bytes
+    InvalidSecretLength(usize),
+    #[cfg(feature = "openssl")]
+    OpenSsl(openssl::error::ErrorStack),
+}
+
+/// The cipher for one direction of a connection
+pub trait StreamCipher: Sized {
+    fn new(direction: Direction, shared_secret: &[u8]) -> Result<Self, CipherError>;
+
+    /// Encrypts or decrypts the data in place, continuing the stream of the previous calls
+    fn apply(&mut self, data: &mut [u8]) -> Result<(), CipherError>;
+}
+
+fn check_secret(shared_secret: &[u8]) -> Result<(), CipherError> {
+    if shared_secret.len() != SECRET_LENGTH {
+        return Err(CipherError::InvalidSecretLength(shared_secret.len()));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "openssl")]
+pub struct OpenSslCipher {
+    ctx: openssl::cipher_ctx::CipherCtx,
+}
+
+#[cfg(feature = "openssl")]
+impl StreamCipher for OpenSslCipher {
+    fn new(direction: Direction, shared_secret: &[u8]) -> Result<Self, CipherError> {
+        check_secret(shared_secret)?;
+
+        let cipher = openssl::cipher::Cipher::aes_128_cfb8();
+        let mut ctx = openssl::cipher_ctx::CipherCtx::new()?;
+        match direction {
+            Direction::Encrypt => {
+                ctx.encrypt_init(Some(cipher), Some(shared_secret), Some(shared_secret))?
+            }
+            Direction::Decrypt => {
+                ctx.decrypt_init(Some(cipher), Some(shared_secret), Some(shared_secret))?
+            }
+        };
+
+        Ok(Self { ctx })
+    }
+
+    fn apply(&mut self, data: &mut [u8]) -> Result<(), CipherError> {
+        let len = data.len();
+        self.ctx.cipher_update_inplace(data, len)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "openssl")]
+impl From<openssl::error::ErrorStack> for CipherError {
+    fn from(value: openssl::error::ErrorStack) -> Self {
+        Self::OpenSsl(value)
+    }
+}
+
+#[cfg(feature = "rustcrypto")]
+pub enum RustCryptoCipher {
+    Encrypt(cfb8::Encryptor<aes::Aes128>),
+    Decrypt(cfb8::Decryptor<aes::Aes128>),
+}
+
+#[cfg(feature = "rustcrypto")]
+impl StreamCipher for RustCryptoCipher {
+    fn new(direction: Direction, shared_secret: &[u8]) -> Result<Self, CipherError> {
+        use aes::cipher::KeyIvInit;
+
+        check_secret(shared_secret)?;
+
+        let key = aes::cipher::generic_array::GenericArray::from_slice(shared_secret);
+        Ok(match direction {
+            Direction::Encrypt => Self::Encrypt(cfb8::Encryptor::new(key, key)),
+            Direction::Decrypt => Self::Decrypt(cfb8::Decryptor::new(key, key)),
+        })
+    }
+
+    fn apply(&mut self, data: &mut [u8]) -> Result<(), CipherError> {
+        use aes::cipher::{inout::InOutBuf, BlockDecryptMut, BlockEncryptMut};
+
+        // CFB8 works on blocks of a single byte, so there is never a remainder
+        let (blocks, _) = InOutBuf::from(data).into_chunks();
+        match self {
+            Self::Encrypt(c) => c.encrypt_blocks_inout_mut(blocks),
+            Self::Decrypt(c) => c.decrypt_blocks_inout_mut(blocks),
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The key and plaintext of the CFB8-AES128 example from NIST SP 800-38A, F.3.7, but with the
+    // key also used as the IV like the protocol does. The ciphertext was created using
+    // `openssl enc -aes-128-cfb8`.
+    const SECRET: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f,
+        0x3c,
+    ];
+    const PLAINTEXT: [u8; 18] = [
+        0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17,
+        0x2a, 0xae, 0x2d,
+    ];
+    const CIPHERTEXT: [u8; 18] = [
+        0x14, 0x11, 0x8e, 0x07, 0x10, 0xeb, 0x1b, 0x4c, 0xa3, 0x0a, 0xe2, 0xde, 0x24, 0x74, 0x7e,
+        0xfb, 0x61, 0x73,
+    ];
+
+    /// Encrypts and decrypts the data in uneven chunks, as the stream is split into packets
+    fn roundtrip<C>(data: &[u8]) -> Vec<u8>
+    where
+        C: StreamCipher,
+    {
+        let mut encrypt = C::new(Direction::Encrypt, &SECRET).unwrap();
+        let mut decrypt = C::new(Direction::Decrypt, &SECRET).unwrap();
+
+        let mut encrypted = data.to_vec();
+        for chunk in encrypted.chunks_mut(7) {
+            encrypt.apply(chunk).unwrap();
+        }
+
+        let mut decrypted = encrypted.clone();
+        for chunk in decrypted.chunks_mut(5) {
+            decrypt.apply(chunk).unwrap();
+        }
+        assert_eq!(data, decrypted);
+
+        encrypted
+    }
+
+    fn stream() -> Vec<u8> {
+        (0..=255u8).cycle().take(1000).collect()
+    }
+
+    #[test]
+    #[cfg(feature = "openssl")]
+    fn openssl_stream() {
+        assert!(matches!(
+            OpenSslCipher::new(Direction::Encrypt, &SECRET[..15]),
+            Err(CipherError::InvalidSecretLength(15))
+        ));
+
+        let mut cipher = OpenSslCipher::new(Direction::Encrypt, &SECRET).unwrap();
+        let mut data = PLAINTEXT;
+        cipher.apply(&mut data).unwrap();
+        assert_eq!(CIPHERTEXT, data);
+
+        roundtrip::<OpenSslCipher>(&stream());
+    }
+
+    #[test]
+    #[cfg(feature = "rustcrypto")]
+    fn rustcrypto_stream() {
+        assert!(matches!(
+            RustCryptoCipher::new(Direction::Decrypt, &SECRET[..15]),
+            Err(CipherError::InvalidSecretLength(15))
+        ));
+
+        let mut cipher = RustCryptoCipher::new(Direction::Encrypt, &SECRET).unwrap();
+        let mut data = PLAINTEXT;
+        cipher.apply(&mut data).unwrap();
+        assert_eq!(CIPHERTEXT, data);
+
+        roundtrip::<RustCryptoCipher>(&stream());
+    }
+
+    #[test]
+    #[cfg(all(feature = "openssl", feature = "rustcrypto"))]
+    fn identical_backends() {
+        let data = stream();
+        assert_eq!(
+            roundtrip::<OpenSslCipher>(&data),
+            roundtrip::<RustCryptoCipher>(&data)
+        );
+    }
+}
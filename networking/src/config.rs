@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+/// The state of the protocol the connection is currently in, used to select the read timeout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    Handshake,
+    Status,
+    Login,
+    Configuration,
+    Play,
+}
+
+/// The limits and timeouts of a [`Connection`](crate::Connection)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionConfig {
+    /// The time to wait for a complete packet in the handshake state
+    pub handshake_timeout: Duration,
+    pub status_timeout: Duration,
+    pub login_timeout: Duration,
+    pub configuration_timeout: Duration,
+    /// The time to wait for a complete packet while playing, the client sends at least a
+    /// movement packet every second so this should only be hit by dead connections
+    pub play_timeout: Duration,
+    /// The time a single packet may take to be written, this is what stops a client that does
+    /// not read from its socket from holding up the sender forever
+    pub write_timeout: Duration,
+    /// The largest allowed length of a packet, checked before the packet is buffered
+    pub max_frame_size: usize,
+    /// Disables Nagle's algorithm on TCP streams, so small packets are not delayed
+    pub nodelay: bool,
+}
+
+impl ConnectionConfig {
+    /// The largest packet the vanilla client accepts, 2^21 - 1 bytes
+    pub const VANILLA_MAX_FRAME_SIZE: usize = 2097151;
+
+    pub fn read_timeout(&self, state: ConnectionState) -> Duration {
+        match state {
+            ConnectionState::Handshake => self.handshake_timeout,
+            ConnectionState::Status => self.status_timeout,
+            ConnectionState::Login => self.login_timeout,
+            ConnectionState::Configuration => self.configuration_timeout,
+            ConnectionState::Play => self.play_timeout,
+        }
+    }
+
+    /// Applies the socket options of the config to the stream
+    pub fn configure_tcp(&self, stream: &tokio::net::TcpStream) -> std::io::Result<()> {
+        stream.set_nodelay(self.nodelay)
+    }
+}
+
+impl Default for ConnectionConfig {
+    fn default() -> Self {
+        Self {
+            handshake_timeout: Duration::from_secs(10),
+            status_timeout: Duration::from_secs(10),
+            login_timeout: Duration::from_secs(30),
+            configuration_timeout: Duration::from_secs(30),
+            play_timeout: Duration::from_secs(30),
+            write_timeout: Duration::from_secs(10),
+            max_frame_size: Self::VANILLA_MAX_FRAME_SIZE,
+            nodelay: true,
+        }
+    }
+}
@@ -1,98 +1,158 @@
-use crate::{TransportReceive, TransportSend};
+use crate::{
+    cipher::{DefaultCipher, StreamCipher},
+    NetworkError, SplitTransport, TransportReceive, TransportSend,
+};
 
-pub struct EncryptedConnection<S> {
+/// Reads from the stream and decrypts the new data in the buffer
+async fn recv_decrypted<S, C>(
+    stream: &mut S,
+    cipher: &mut C,
+    buf: &mut bytes::BytesMut,
+) -> Result<usize, NetworkError>
+where
+    S: tokio::io::AsyncRead + core::marker::Unpin,
+    C: StreamCipher,
+{
+    use tokio::io::AsyncReadExt;
+
+    let start = buf.len();
+    let read = stream.read_buf(buf).await?;
+    cipher.apply(&mut buf[start..])?;
+
+    Ok(read)
+}
+
+/// Encrypts the data in place and writes it to the stream
+async fn send_encrypted<S, C>(
+    stream: &mut S,
+    cipher: &mut C,
+    mut data: Vec<u8>,
+) -> Result<(), NetworkError>
+where
+    S: tokio::io::AsyncWrite + core::marker::Unpin,
+    C: StreamCipher,
+{
+    use tokio::io::AsyncWriteExt;
+
+    cipher.apply(&mut data)?;
+    stream.write_all(&data).await?;
+    Ok(())
+}
+
+pub struct EncryptedConnection<S, C = DefaultCipher> {
     stream: S,
-    read_c: openssl::symm::Crypter,
-    write_c: openssl::symm::Crypter,
-    recv_target: Vec<u8>,
-    decrypt_target: Vec<u8>,
+    read: C,
+    write: C,
+}
+
+/// The receiving half of a split [`EncryptedConnection`]
+pub struct EncryptedReader<S, C = DefaultCipher> {
+    stream: tokio::io::ReadHalf<S>,
+    read: C,
+}
+
+/// The sending half of a split [`EncryptedConnection`]
+pub struct EncryptedWriter<S, C = DefaultCipher> {
+    stream: tokio::io::WriteHalf<S>,
+    write: C,
 }
 
-impl<S> EncryptedConnection<S> {
-    pub fn new(stream: S, read: openssl::symm::Crypter, write: openssl::symm::Crypter) -> Self {
+impl<S, C> EncryptedConnection<S, C> {
+    pub fn new(stream: S, read: C, write: C) -> Self {
         Self {
             stream,
-            read_c: read,
-            write_c: write,
-            recv_target: vec![0; 1028],
-            decrypt_target: vec![0; 1028],
+            read,
+            write,
         }
     }
 }
 
-impl<S> TransportReceive for EncryptedConnection<S>
+impl<S, C> SplitTransport for EncryptedConnection<S, C>
 where
-    S: tokio::io::AsyncRead + core::marker::Unpin,
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + core::marker::Unpin,
+    C: StreamCipher,
 {
-    async fn recv(&mut self, buf: &mut bytes::BytesMut) -> Result<usize, ()> {
-        use bytes::BufMut;
-        use tokio::io::AsyncReadExt;
-
-        let read = self.stream.read(&mut self.recv_target).await.map_err(|e| {
-            dbg!(e);
-            ()
-        })?;
-        if read == 0 {
-            return Ok(0);
-        }
+    type Reader = EncryptedReader<S, C>;
+    type Writer = EncryptedWriter<S, C>;
 
-        let decrypted = self
-            .read_c
-            .update(&self.recv_target[..read], &mut self.decrypt_target)
-            .map_err(|e| {
-                dbg!(e);
-                ()
-            })?;
-        if decrypted == 0 {
-            todo!();
-        }
+    fn split(self) -> (Self::Reader, Self::Writer) {
+        let (read_half, write_half) = tokio::io::split(self.stream);
+
+        (
+            EncryptedReader {
+                stream: read_half,
+                read: self.read,
+            },
+            EncryptedWriter {
+                stream: write_half,
+                write: self.write,
+            },
+        )
+    }
+}
 
-        buf.put_slice(&self.decrypt_target[..decrypted]);
+impl<S, C> TransportReceive for EncryptedConnection<S, C>
+where
+    S: tokio::io::AsyncRead + core::marker::Unpin,
+    C: StreamCipher,
+{
+    async fn recv(&mut self, buf: &mut bytes::BytesMut) -> Result<usize, NetworkError> {
+        recv_decrypted(&mut self.stream, &mut self.read, buf).await
+    }
+}
 
-        Ok(decrypted)
+impl<S, C> TransportReceive for EncryptedReader<S, C>
+where
+    S: tokio::io::AsyncRead,
+    C: StreamCipher,
+{
+    async fn recv(&mut self, buf: &mut bytes::BytesMut) -> Result<usize, NetworkError> {
+        recv_decrypted(&mut self.stream, &mut self.read, buf).await
     }
 }
 
-impl<S> TransportSend for EncryptedConnection<S>
+impl<S, C> TransportSend for EncryptedConnection<S, C>
 where
     S: tokio::io::AsyncWrite + core::marker::Unpin,
+    C: StreamCipher,
 {
-    async fn send_packet<D>(&mut self, packet: &protocol::packet::Packet<D>) -> Result<(), ()>
+    async fn send_packet<D>(
+        &mut self,
+        packet: &protocol::packet::Packet<D>,
+    ) -> Result<(), NetworkError>
     where
         D: protocol::packet::PacketContent,
     {
-        use tokio::io::AsyncWriteExt;
-
-        let raw_bytes = packet.serialize();
-        let mut output = vec![0; raw_bytes.len()];
-
-        let encrypted = self.write_c.update(&raw_bytes, &mut output).unwrap();
-
-        self.stream
-            .write_all(&output[..encrypted])
-            .await
-            .map(|_| ())
-            .map_err(|e| {
-                dbg!(e);
-                ()
-            })
+        send_encrypted(&mut self.stream, &mut self.write, packet.serialize()).await
     }
 
-    async fn send_rawpacket(&mut self, packet: &protocol::packet::RawPacket) -> Result<(), ()> {
-        use tokio::io::AsyncWriteExt;
-
-        let raw_bytes = packet.serialize();
-        let mut output = vec![0; raw_bytes.len()];
+    async fn send_rawpacket(
+        &mut self,
+        packet: &protocol::packet::RawPacket,
+    ) -> Result<(), NetworkError> {
+        send_encrypted(&mut self.stream, &mut self.write, packet.serialize()).await
+    }
+}
 
-        let encrypted = self.write_c.update(&raw_bytes, &mut output).unwrap();
+impl<S, C> TransportSend for EncryptedWriter<S, C>
+where
+    S: tokio::io::AsyncWrite,
+    C: StreamCipher,
+{
+    async fn send_packet<D>(
+        &mut self,
+        packet: &protocol::packet::Packet<D>,
+    ) -> Result<(), NetworkError>
+    where
+        D: protocol::packet::PacketContent,
+    {
+        send_encrypted(&mut self.stream, &mut self.write, packet.serialize()).await
+    }
 
-        self.stream
-            .write_all(&output[..encrypted])
-            .await
-            .map(|_| ())
-            .map_err(|e| {
-                dbg!(e);
-                ()
-            })
+    async fn send_rawpacket(
+        &mut self,
+        packet: &protocol::packet::RawPacket,
+    ) -> Result<(), NetworkError> {
+        send_encrypted(&mut self.stream, &mut self.write, packet.serialize()).await
     }
 }
@@ -0,0 +1,49 @@
+/// The errors of a [`Connection`](crate::Connection) and its transports
+#[derive(Debug)]
+pub enum NetworkError {
+    /// The other side closed the connection
+    Eof,
+    Io(std::io::Error),
+    Parse(protocol::general::ParseError),
+    /// Encrypting or decrypting the stream failed
+    Crypto(crate::cipher::CipherError),
+    /// The packet is larger than the configured maximum
+    FrameTooLarge {
+        size: usize,
+        max: usize,
+    },
+    /// No complete packet was received or the packet could not be send in time
+    Timeout,
+}
+
+impl NetworkError {
+    /// Whether the connection can still be used to tell the other side why it is being closed,
+    /// which is not the case if it is already closed or the stream is broken
+    pub fn can_notify(&self) -> bool {
+        matches!(
+            self,
+            Self::Parse(_) | Self::FrameTooLarge { .. } | Self::Timeout
+        )
+    }
+}
+
+impl From<std::io::Error> for NetworkError {
+    fn from(value: std::io::Error) -> Self {
+        if value.kind() == std::io::ErrorKind::UnexpectedEof {
+            return Self::Eof;
+        }
+        Self::Io(value)
+    }
+}
+
+impl From<crate::cipher::CipherError> for NetworkError {
+    fn from(value: crate::cipher::CipherError) -> Self {
+        Self::Crypto(value)
+    }
+}
+
+impl From<protocol::general::ParseError> for NetworkError {
+    fn from(value: protocol::general::ParseError) -> Self {
+        Self::Parse(value)
+    }
+}
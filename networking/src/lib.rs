@@ -1,9 +1,24 @@
+pub mod cipher;
+
 mod encrypted;
-pub use encrypted::EncryptedConnection;
+pub use encrypted::{EncryptedConnection, EncryptedReader, EncryptedWriter};
 
 mod unencrypted;
 pub use unencrypted::UnencryptedConnection;
 
+mod config;
+pub use config::{ConnectionConfig, ConnectionState};
+
+mod error;
+pub use error::NetworkError;
+
+mod writer;
+pub use writer::PacketSender;
+
+pub mod memory;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
 pub trait Transport: TransportReceive + TransportSend {}
 impl<T> Transport for T where T: TransportSend + TransportReceive {}
 
@@ -11,30 +26,50 @@ pub trait TransportReceive {
     fn recv(
         &mut self,
         buf: &mut bytes::BytesMut,
-    ) -> impl core::future::Future<Output = Result<usize, ()>>;
+    ) -> impl core::future::Future<Output = Result<usize, NetworkError>>;
 }
 pub trait TransportSend {
     fn send_packet<D>(
         &mut self,
         packet: &protocol::packet::Packet<D>,
-    ) -> impl core::future::Future<Output = Result<(), ()>>
+    ) -> impl core::future::Future<Output = Result<(), NetworkError>>
     where
         D: protocol::packet::PacketContent;
 
     fn send_rawpacket(
         &mut self,
         packet: &protocol::packet::RawPacket,
-    ) -> impl core::future::Future<Output = Result<(), ()>>;
+    ) -> impl core::future::Future<Output = Result<(), NetworkError>>;
+}
+
+/// A transport that can be split into independent halves for receiving and sending, which keep
+/// their own state like the cipher of their direction
+pub trait SplitTransport {
+    type Reader: TransportReceive;
+    type Writer: TransportSend;
+
+    fn split(self) -> (Self::Reader, Self::Writer);
 }
 
 pub struct Connection<T> {
     transport: T,
     buffer: bytes::BytesMut,
+    config: ConnectionConfig,
+    state: ConnectionState,
 }
 
 impl<T> Connection<T> {
     pub fn new(transport: T, buffer: bytes::BytesMut) -> Self {
-        Self { transport, buffer }
+        Self::with_config(transport, buffer, ConnectionConfig::default())
+    }
+
+    pub fn with_config(transport: T, buffer: bytes::BytesMut, config: ConnectionConfig) -> Self {
+        Self {
+            transport,
+            buffer,
+            config,
+            state: ConnectionState::Handshake,
+        }
     }
 
     pub fn map_transport<F, T2>(self, func: F) -> Connection<T2>
@@ -46,113 +81,390 @@ impl<T> Connection<T> {
         Connection {
             transport: n_transport,
             buffer: self.buffer,
+            config: self.config,
+            state: self.state,
         }
     }
+
+    pub fn try_map_transport<F, T2, E>(self, func: F) -> Result<Connection<T2>, E>
+    where
+        F: FnOnce(T) -> Result<T2, E>,
+    {
+        let n_transport = func(self.transport)?;
+
+        Ok(Connection {
+            transport: n_transport,
+            buffer: self.buffer,
+            config: self.config,
+            state: self.state,
+        })
+    }
+
+    pub fn config(&self) -> &ConnectionConfig {
+        &self.config
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    /// Switches the state of the connection, which changes the read timeout for the following
+    /// packets
+    pub fn set_state(&mut self, state: ConnectionState) {
+        self.state = state;
+    }
+
+    /// Checks the length prefix of the next packet in the buffer against the maximum frame size
+    fn check_frame_size(&self) -> Result<(), NetworkError> {
+        use protocol::serialize::SerializeItem;
+
+        let max = self.config.max_frame_size;
+
+        match protocol::general::VarInt::parse(&self.buffer) {
+            Ok((_, size)) if size.0 < 0 => Err(NetworkError::Parse(
+                protocol::general::ParseError::NegativeLength,
+            )),
+            Ok((_, size)) if size.0 as usize > max => Err(NetworkError::FrameTooLarge {
+                size: size.0 as usize,
+                max,
+            }),
+            Ok(_) => Ok(()),
+            Err(_) => {
+                // An incomplete length prefix that is already longer than the one of the largest
+                // allowed frame can only encode a larger frame, a VarInt never has more than 5
+                // bytes
+                let max_prefix = (usize::BITS - max.leading_zeros()).div_ceil(7).clamp(1, 5);
+                if self.buffer.len() >= max_prefix as usize {
+                    return Err(NetworkError::FrameTooLarge {
+                        size: self.buffer.len(),
+                        max,
+                    });
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<T> Connection<T>
+where
+    T: SplitTransport,
+{
+    /// Splits the connection into a half for receiving and a half for sending, so they can be
+    /// used from different tasks. Both halves start with the config and state of the connection
+    /// and the already buffered data stays with the receiving half.
+    pub fn split(self) -> (Connection<T::Reader>, Connection<T::Writer>) {
+        let (reader, writer) = self.transport.split();
+
+        (
+            Connection {
+                transport: reader,
+                buffer: self.buffer,
+                config: self.config.clone(),
+                state: self.state,
+            },
+            Connection {
+                transport: writer,
+                buffer: bytes::BytesMut::new(),
+                config: self.config,
+                state: self.state,
+            },
+        )
+    }
 }
+
 impl<T> Connection<T>
 where
     T: TransportReceive,
 {
-    pub async fn recv_rawpacket(&mut self) -> Result<protocol::packet::RawPacket, ()> {
+    /// Reads from the transport until `parse` returns a complete packet or the read timeout of
+    /// the current state expires
+    async fn recv_with<P, R>(&mut self, mut parse: P) -> Result<R, NetworkError>
+    where
+        P: FnMut(&mut bytes::BytesMut) -> nom::IResult<(), R, protocol::general::ParseError>,
+    {
+        let deadline = tokio::time::Instant::now() + self.config.read_timeout(self.state);
+
         loop {
-            match protocol::packet::RawPacket::parse_bytes(&mut self.buffer) {
+            self.check_frame_size()?;
+
+            match parse(&mut self.buffer) {
                 Ok((_, v)) => return Ok(v),
                 Err(nom::Err::Incomplete(_)) => {}
-                Err(e) => {
-                    tracing::error!(?e);
-                    return Err(());
+                Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+                    return Err(NetworkError::Parse(e));
                 }
             };
 
-            match self.transport.recv(&mut self.buffer).await {
-                Ok(v) if v == 0 => {
-                    tracing::error!("EOF");
-                    return Err(());
-                }
-                Err(e) => {
-                    tracing::error!(?e);
-                    return Err(());
-                }
-                _ => {}
+            match tokio::time::timeout_at(deadline, self.transport.recv(&mut self.buffer)).await {
+                Ok(Ok(0)) => return Err(NetworkError::Eof),
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => return Err(e),
+                Err(_) => return Err(NetworkError::Timeout),
             };
         }
     }
 
+    pub async fn recv_rawpacket(&mut self) -> Result<protocol::packet::RawPacket, NetworkError> {
+        self.recv_with(protocol::packet::RawPacket::parse_bytes)
+            .await
+    }
+
     pub async fn recv_packet<D, F>(
         &mut self,
         mut parser: F,
-    ) -> Result<protocol::packet::Packet<D>, ()>
+    ) -> Result<protocol::packet::Packet<D>, NetworkError>
     where
         F: protocol::packet::PacketContentParser<D>,
     {
-        loop {
-            match protocol::packet::Packet::parse_bytes(&mut parser, &mut self.buffer) {
-                Ok((_, v)) => return Ok(v),
-                Err(nom::Err::Incomplete(_)) => {}
-                Err(e) => {
-                    tracing::error!(?e);
-                    return Err(());
-                }
-            };
-
-            match self.transport.recv(&mut self.buffer).await {
-                Ok(v) if v == 0 => {
-                    tracing::error!("EOF");
-                    return Err(());
-                }
-                Err(e) => {
-                    tracing::error!(?e);
-                    return Err(());
-                }
-                _ => {}
-            };
-        }
+        self.recv_with(|buffer| protocol::packet::Packet::parse_bytes(&mut parser, buffer))
+            .await
     }
 
     pub async fn recv_legacy_packet<D, F>(
         &mut self,
         mut parser: F,
-    ) -> Result<protocol::packet::LegacyPacket<D>, ()>
+    ) -> Result<protocol::packet::LegacyPacket<D>, NetworkError>
     where
         F: protocol::packet::PacketContentParser<D>,
     {
-        loop {
-            match protocol::packet::LegacyPacket::parse_bytes(&mut parser, &mut self.buffer) {
-                Ok((_, v)) => return Ok(v),
-                Err(nom::Err::Incomplete(_)) => {}
-                Err(e) => {
-                    tracing::error!(?e);
-                    return Err(());
-                }
-            };
-
-            match self.transport.recv(&mut self.buffer).await {
-                Ok(v) if v == 0 => {
-                    tracing::error!("EOF");
-                    return Err(());
-                }
-                Err(e) => {
-                    tracing::error!(?e);
-                    return Err(());
-                }
-                _ => {}
-            };
-        }
+        self.recv_with(|buffer| protocol::packet::LegacyPacket::parse_bytes(&mut parser, buffer))
+            .await
     }
 }
 
+/// Sending waits until the transport accepted the complete packet, so a client that reads slowly
+/// also slows down the sender instead of packets piling up in memory. The write timeout then
+/// catches clients that stopped reading entirely.
 impl<T> Connection<T>
 where
     T: TransportSend,
 {
-    pub async fn send_packet<D>(&mut self, packet: &protocol::packet::Packet<D>) -> Result<(), ()>
+    pub async fn send_packet<D>(
+        &mut self,
+        packet: &protocol::packet::Packet<D>,
+    ) -> Result<(), NetworkError>
     where
         D: protocol::packet::PacketContent,
     {
-        self.transport.send_packet(packet).await
+        match tokio::time::timeout(
+            self.config.write_timeout,
+            self.transport.send_packet(packet),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(_) => Err(NetworkError::Timeout),
+        }
     }
 
-    pub async fn send_rawpacket(&mut self, packet: &protocol::packet::RawPacket) -> Result<(), ()> {
-        self.transport.send_rawpacket(packet).await
+    pub async fn send_rawpacket(
+        &mut self,
+        packet: &protocol::packet::RawPacket,
+    ) -> Result<(), NetworkError> {
+        match tokio::time::timeout(
+            self.config.write_timeout,
+            self.transport.send_rawpacket(packet),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(_) => Err(NetworkError::Timeout),
+        }
+    }
+
+    /// Tells the client why it is disconnected, using the packet of the current state, and
+    /// closes the connection afterwards. There is no way to tell the client anything before the
+    /// login, so the connection is just closed in the handshake and status states.
+    pub async fn disconnect(
+        mut self,
+        reason: protocol::general::TextComponent,
+    ) -> Result<(), NetworkError> {
+        match self.state {
+            ConnectionState::Handshake | ConnectionState::Status => Ok(()),
+            ConnectionState::Login => {
+                self.send_packet(&protocol::packet::Packet {
+                    inner: protocol::login::client::Disconnect::new(&reason),
+                })
+                .await
+            }
+            ConnectionState::Configuration => {
+                self.send_packet(&protocol::packet::Packet {
+                    inner: protocol::configuration::client::Disconnect { reason },
+                })
+                .await
+            }
+            ConnectionState::Play => {
+                self.send_packet(&protocol::packet::Packet {
+                    inner: protocol::play::client::Disconnect { reason },
+                })
+                .await
+            }
+        }
+    }
+
+    /// Turns the connection into a writer for the packets queued using the returned
+    /// [`PacketSender`]s. The queue holds at most `capacity` packets, after that the senders wait
+    /// for the connection to catch up.
+    ///
+    /// The returned future does the actual sending and should be spawned as its own task, it
+    /// finishes once all the senders are dropped or a packet could not be send.
+    pub fn into_writer(
+        mut self,
+        capacity: usize,
+    ) -> (
+        PacketSender,
+        impl core::future::Future<Output = Result<(), NetworkError>>,
+    ) {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(capacity);
+
+        let writer = async move {
+            while let Some(packet) = rx.recv().await {
+                self.send_rawpacket(&packet).await?;
+            }
+            Ok(())
+        };
+
+        (PacketSender::new(tx), writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{collections::VecDeque, time::Duration};
+
+    /// Returns the chunks one by one and then waits forever, or returns EOF if `eof` is set
+    struct Chunks {
+        chunks: VecDeque<Vec<u8>>,
+        eof: bool,
+    }
+
+    impl TransportReceive for Chunks {
+        async fn recv(&mut self, buf: &mut bytes::BytesMut) -> Result<usize, NetworkError> {
+            match self.chunks.pop_front() {
+                Some(chunk) => {
+                    buf.extend_from_slice(&chunk);
+                    Ok(chunk.len())
+                }
+                None if self.eof => Ok(0),
+                None => core::future::pending().await,
+            }
+        }
+    }
+
+    fn connection(chunks: &[&[u8]], eof: bool) -> Connection<Chunks> {
+        Connection::with_config(
+            Chunks {
+                chunks: chunks.iter().map(|c| c.to_vec()).collect(),
+                eof,
+            },
+            bytes::BytesMut::new(),
+            ConnectionConfig {
+                handshake_timeout: Duration::from_millis(50),
+                max_frame_size: 1024,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn split_packet() {
+        let mut conn = connection(&[&[0x03, 0x01], &[0xaa], &[0xbb, 0x01, 0x00]], false);
+
+        let packet = conn.recv_rawpacket().await.unwrap();
+        assert_eq!(1, packet.id.0);
+        assert_eq!(vec![0xaa, 0xbb], packet.data);
+
+        let packet = conn.recv_rawpacket().await.unwrap();
+        assert_eq!(0, packet.id.0);
+    }
+
+    #[tokio::test]
+    async fn frame_too_large() {
+        // The length is checked before waiting for the rest of the packet
+        let mut conn = connection(&[&[0x81, 0x08]], false);
+        assert!(matches!(
+            conn.recv_rawpacket().await,
+            Err(NetworkError::FrameTooLarge {
+                size: 1025,
+                max: 1024
+            })
+        ));
+
+        let mut conn = connection(&[&[0xff, 0xff], &[0xff, 0xff]], false);
+        assert!(matches!(
+            conn.recv_rawpacket().await,
+            Err(NetworkError::FrameTooLarge { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn timeout_and_eof() {
+        let mut conn = connection(&[&[0x03, 0x01]], false);
+        assert!(matches!(
+            conn.recv_rawpacket().await,
+            Err(NetworkError::Timeout)
+        ));
+
+        let mut conn = connection(&[&[0x03, 0x01]], true);
+        assert!(matches!(
+            conn.recv_rawpacket().await,
+            Err(NetworkError::Eof)
+        ));
+    }
+
+    #[tokio::test]
+    async fn split_halves() {
+        let (local, remote) = tokio::io::duplex(64);
+        let (mut reader, writer) =
+            Connection::new(UnencryptedConnection::new(local), bytes::BytesMut::new()).split();
+        let mut remote =
+            Connection::new(UnencryptedConnection::new(remote), bytes::BytesMut::new());
+
+        let (sender, writer) = writer.into_writer(4);
+        let writer = tokio::spawn(writer);
+
+        let ping = protocol::packet::Packet {
+            inner: protocol::status::server::PingRequest { payload: 123 },
+        };
+        sender.send(&ping).await.unwrap();
+        remote.send_packet(&ping).await.unwrap();
+
+        let received = reader
+            .recv_packet(protocol::status::server::PingRequest::parse)
+            .await
+            .unwrap();
+        assert_eq!(ping, received);
+        let received = remote
+            .recv_packet(protocol::status::server::PingRequest::parse)
+            .await
+            .unwrap();
+        assert_eq!(ping, received);
+
+        drop(sender);
+        assert!(writer.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn disconnect() {
+        let (mut server, mut client) = memory::pair(1024, ConnectionConfig::default());
+        server.set_state(ConnectionState::Login);
+
+        server
+            .disconnect(protocol::general::TextComponent::text("bye"))
+            .await
+            .unwrap();
+
+        let packet = client
+            .recv_packet(protocol::login::client::Disconnect::parse)
+            .await
+            .unwrap();
+        assert_eq!(r#""bye""#, packet.inner.reason.0);
+        assert!(matches!(
+            client.recv_rawpacket().await,
+            Err(NetworkError::Eof)
+        ));
     }
 }
@@ -0,0 +1,28 @@
+//! An in-memory transport, which makes it possible to run both sides of a connection in the same
+//! process without any sockets, for example in tests
+
+use crate::{Connection, ConnectionConfig, UnencryptedConnection};
+
+pub type MemoryTransport = UnencryptedConnection<tokio::io::DuplexStream>;
+
+/// Creates two connections that are connected to each other, each direction buffers at most
+/// `max_buf_size` bytes before the sender has to wait
+pub fn pair(
+    max_buf_size: usize,
+    config: ConnectionConfig,
+) -> (Connection<MemoryTransport>, Connection<MemoryTransport>) {
+    let (first, second) = tokio::io::duplex(max_buf_size);
+
+    (
+        Connection::with_config(
+            UnencryptedConnection::new(first),
+            bytes::BytesMut::new(),
+            config.clone(),
+        ),
+        Connection::with_config(
+            UnencryptedConnection::new(second),
+            bytes::BytesMut::new(),
+            config,
+        ),
+    )
+}
@@ -0,0 +1,198 @@
+//! A scripted client for testing the handlers of a server
+//!
+//! The [`FakeClient`] talks to the connection returned by [`FakeClient::pair`] like a real client
+//! would. Every method panics with a description of what went wrong if the server does not send
+//! what is expected, so a test reads as the exact exchange of packets.
+//!
+//! ```
+//! use networking::{testing::FakeClient, Connection, ConnectionState, Transport};
+//! use protocol::{handshake::server::NextState, packet::Packet, status};
+//!
+//! // A handler that only answers a single ping
+//! async fn pong<T: Transport>(mut connection: Connection<T>) {
+//!     connection
+//!         .recv_packet(protocol::handshake::server::Handshaking::parse)
+//!         .await
+//!         .unwrap();
+//!     connection.set_state(ConnectionState::Status);
+//!
+//!     let ping = connection
+//!         .recv_packet(status::server::PingRequest::parse)
+//!         .await
+//!         .unwrap();
+//!     let pong = status::client::PingResponse {
+//!         payload: ping.inner.payload,
+//!     };
+//!     connection.send_packet(&Packet { inner: pong }).await.unwrap();
+//! }
+//!
+//! # tokio::runtime::Builder::new_current_thread()
+//! #     .enable_all()
+//! #     .build()
+//! #     .unwrap()
+//! #     .block_on(async {
+//! let (mut client, connection) = FakeClient::pair();
+//! let script = async move {
+//!     client.handshake(NextState::Status).await;
+//!     client.ping(123).await;
+//! };
+//! tokio::join!(pong(connection), script);
+//! # });
+//! ```
+
+use crate::{
+    cipher::{DefaultCipher, StreamCipher},
+    memory::MemoryTransport,
+    Connection, ConnectionConfig, ConnectionState, EncryptedConnection, NetworkError, Transport,
+    UnencryptedConnection,
+};
+
+/// The protocol version send in the handshake
+pub const PROTOCOL_VERSION: i32 = 766;
+
+pub struct FakeClient<T> {
+    connection: Connection<T>,
+}
+
+impl FakeClient<MemoryTransport> {
+    /// Creates a client and the connection for the server side, which uses short timeouts so
+    /// that a stuck test fails quickly
+    pub fn pair() -> (Self, Connection<MemoryTransport>) {
+        let timeout = std::time::Duration::from_secs(5);
+        let config = ConnectionConfig {
+            handshake_timeout: timeout,
+            status_timeout: timeout,
+            login_timeout: timeout,
+            configuration_timeout: timeout,
+            play_timeout: timeout,
+            write_timeout: timeout,
+            ..Default::default()
+        };
+
+        let (client, server) = crate::memory::pair(64 * 1024, config);
+        (Self { connection: client }, server)
+    }
+}
+
+impl<T> FakeClient<T> {
+    pub fn connection(&mut self) -> &mut Connection<T> {
+        &mut self.connection
+    }
+
+    pub fn set_state(&mut self, state: ConnectionState) {
+        self.connection.set_state(state);
+    }
+}
+
+impl<S> FakeClient<UnencryptedConnection<S>>
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + core::marker::Unpin,
+{
+    /// Enables the encryption after sending the encryption response, like the client does
+    pub fn encrypt(self, shared_secret: &[u8]) -> FakeClient<EncryptedConnection<S>> {
+        self.encrypt_with::<DefaultCipher>(shared_secret)
+    }
+
+    pub fn encrypt_with<C>(self, shared_secret: &[u8]) -> FakeClient<EncryptedConnection<S, C>>
+    where
+        C: StreamCipher,
+    {
+        FakeClient {
+            connection: self
+                .connection
+                .try_map_transport(|t| t.encrypt::<C>(shared_secret))
+                .expect("Enabling encryption"),
+        }
+    }
+}
+
+impl<T> FakeClient<T>
+where
+    T: Transport,
+{
+    pub async fn send<D>(&mut self, packet: D)
+    where
+        D: protocol::packet::PacketContent,
+    {
+        if let Err(e) = self
+            .connection
+            .send_packet(&protocol::packet::Packet { inner: packet })
+            .await
+        {
+            panic!("Sending packet 0x{:02x}: {:?}", D::ID, e);
+        }
+    }
+
+    /// Receives the next packet and parses it using the parser
+    pub async fn expect<D, F>(&mut self, parser: F) -> D
+    where
+        F: protocol::packet::PacketContentParser<D>,
+    {
+        match self.connection.recv_packet(parser).await {
+            Ok(packet) => packet.inner,
+            Err(e) => panic!("Expected packet in {:?}: {:?}", self.connection.state(), e),
+        }
+    }
+
+    pub async fn expect_raw(&mut self) -> protocol::packet::RawPacket {
+        match self.connection.recv_rawpacket().await {
+            Ok(packet) => packet,
+            Err(e) => panic!("Expected packet in {:?}: {:?}", self.connection.state(), e),
+        }
+    }
+
+    /// Expects the server to close the connection without sending anything else
+    pub async fn expect_closed(&mut self) {
+        match self.connection.recv_rawpacket().await {
+            Err(NetworkError::Eof) => {}
+            Ok(packet) => panic!(
+                "Expected the connection to be closed, received 0x{:02x}",
+                packet.id.0
+            ),
+            Err(e) => panic!("Expected the connection to be closed: {:?}", e),
+        }
+    }
+
+    /// Sends the handshake and switches to the next state
+    pub async fn handshake(&mut self, next_state: protocol::handshake::server::NextState) {
+        let state = match next_state {
+            protocol::handshake::server::NextState::Status => ConnectionState::Status,
+            protocol::handshake::server::NextState::Login
+            | protocol::handshake::server::NextState::Transfer => ConnectionState::Login,
+        };
+
+        self.send(protocol::handshake::server::Handshaking {
+            protocol_version: protocol::general::VarInt(PROTOCOL_VERSION),
+            server_addr: protocol::general::PString("localhost".into()),
+            server_port: 25565,
+            next_state,
+        })
+        .await;
+        self.set_state(state);
+    }
+
+    /// Requests the status of the server
+    pub async fn status(&mut self) -> protocol::status::client::StatusResponse {
+        self.send(protocol::status::server::StatusRequest {}).await;
+        self.expect(protocol::status::client::StatusResponse::parse)
+            .await
+    }
+
+    /// Sends a ping and checks that the server responds with the same payload
+    pub async fn ping(&mut self, payload: i64) {
+        self.send(protocol::status::server::PingRequest { payload })
+            .await;
+        let response = self
+            .expect(protocol::status::client::PingResponse::parse)
+            .await;
+        assert_eq!(payload, response.payload, "Payload of the ping response");
+    }
+
+    pub async fn login_start(&mut self, name: &str, uuid: u128) {
+        self.send(protocol::login::server::LoginStart {
+            name: protocol::general::PString(name.to_string().into()),
+            uuid,
+        })
+        .await;
+    }
+}
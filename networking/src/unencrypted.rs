@@ -1,4 +1,7 @@
-use crate::{EncryptedConnection, TransportReceive, TransportSend};
+use crate::{
+    cipher::{Direction, StreamCipher},
+    EncryptedConnection, NetworkError, SplitTransport, TransportReceive, TransportSend,
+};
 
 pub struct UnencryptedConnection<S> {
     stream: S,
@@ -9,12 +12,31 @@ impl<S> UnencryptedConnection<S> {
         Self { stream }
     }
 
-    pub fn encrypt(
-        self,
-        read: openssl::symm::Crypter,
-        write: openssl::symm::Crypter,
-    ) -> EncryptedConnection<S> {
-        EncryptedConnection::new(self.stream, read, write)
+    /// Enables the encryption using the shared secret from the login, with the cipher
+    /// backend `C`
+    pub fn encrypt<C>(self, shared_secret: &[u8]) -> Result<EncryptedConnection<S, C>, NetworkError>
+    where
+        C: StreamCipher,
+    {
+        let read = C::new(Direction::Decrypt, shared_secret)?;
+        let write = C::new(Direction::Encrypt, shared_secret)?;
+        Ok(EncryptedConnection::new(self.stream, read, write))
+    }
+}
+
+impl<S> SplitTransport for UnencryptedConnection<S>
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite,
+{
+    type Reader = UnencryptedConnection<tokio::io::ReadHalf<S>>;
+    type Writer = UnencryptedConnection<tokio::io::WriteHalf<S>>;
+
+    fn split(self) -> (Self::Reader, Self::Writer) {
+        let (read_half, write_half) = tokio::io::split(self.stream);
+        (
+            UnencryptedConnection::new(read_half),
+            UnencryptedConnection::new(write_half),
+        )
     }
 }
 
@@ -22,21 +44,21 @@ impl<S> TransportReceive for UnencryptedConnection<S>
 where
     S: tokio::io::AsyncRead + core::marker::Unpin,
 {
-    async fn recv(&mut self, buf: &mut bytes::BytesMut) -> Result<usize, ()> {
+    async fn recv(&mut self, buf: &mut bytes::BytesMut) -> Result<usize, NetworkError> {
         use tokio::io::AsyncReadExt;
 
-        self.stream.read_buf(buf).await.map_err(|e| {
-            dbg!(e);
-            ()
-        })
+        Ok(self.stream.read_buf(buf).await?)
     }
 }
 
 impl<S> TransportSend for UnencryptedConnection<S>
 where
-    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + core::marker::Unpin,
+    S: tokio::io::AsyncWrite + core::marker::Unpin,
 {
-    async fn send_packet<D>(&mut self, packet: &protocol::packet::Packet<D>) -> Result<(), ()>
+    async fn send_packet<D>(
+        &mut self,
+        packet: &protocol::packet::Packet<D>,
+    ) -> Result<(), NetworkError>
     where
         D: protocol::packet::PacketContent,
     {
@@ -44,28 +66,19 @@ where
 
         let bytes = packet.serialize();
 
-        self.stream
-            .write_all(&bytes)
-            .await
-            .map(|_| ())
-            .map_err(|e| {
-                dbg!(e);
-                ()
-            })
+        self.stream.write_all(&bytes).await?;
+        Ok(())
     }
 
-    async fn send_rawpacket(&mut self, packet: &protocol::packet::RawPacket) -> Result<(), ()> {
+    async fn send_rawpacket(
+        &mut self,
+        packet: &protocol::packet::RawPacket,
+    ) -> Result<(), NetworkError> {
         use tokio::io::AsyncWriteExt;
 
         let bytes = packet.serialize();
 
-        self.stream
-            .write_all(&bytes)
-            .await
-            .map(|_| ())
-            .map_err(|e| {
-                dbg!(e);
-                ()
-            })
+        self.stream.write_all(&bytes).await?;
+        Ok(())
     }
 }
@@ -0,0 +1,30 @@
+use crate::NetworkError;
+
+/// Queues packets for a connection writer running in its own task, see
+/// [`Connection::into_writer`](crate::Connection::into_writer)
+#[derive(Clone)]
+pub struct PacketSender {
+    tx: tokio::sync::mpsc::Sender<protocol::packet::RawPacket>,
+}
+
+impl PacketSender {
+    pub(crate) fn new(tx: tokio::sync::mpsc::Sender<protocol::packet::RawPacket>) -> Self {
+        Self { tx }
+    }
+
+    /// Waits until there is space in the queue of the writer and queues the packet, returns
+    /// [`NetworkError::Eof`] if the writer already stopped
+    pub async fn send<D>(&self, packet: &protocol::packet::Packet<D>) -> Result<(), NetworkError>
+    where
+        D: protocol::packet::PacketContent,
+    {
+        self.send_rawpacket(packet.into()).await
+    }
+
+    pub async fn send_rawpacket(
+        &self,
+        packet: protocol::packet::RawPacket,
+    ) -> Result<(), NetworkError> {
+        self.tx.send(packet).await.map_err(|_| NetworkError::Eof)
+    }
+}
@@ -0,0 +1,264 @@
+//! Queries the status of a server, like the server list of the client does
+//!
+//! [`status`] uses the current protocol, with the handshake, status request and ping on a
+//! [`Connection`]. Servers that do not answer it can still be queried using [`legacy_status`].
+
+use networking::{Connection, ConnectionState, NetworkError, Transport};
+use protocol::{
+    general::{PString, VarInt},
+    packet::Packet,
+};
+
+/// The protocol version send in the handshake
+pub const PROTOCOL_VERSION: i32 = 766;
+
+#[derive(Debug)]
+pub enum PingError {
+    Network(NetworkError),
+    /// The status send by the server is not valid JSON
+    InvalidStatus(serde_json::Error),
+    /// The server answered the ping with a different payload
+    PayloadMismatch {
+        expected: i64,
+        received: i64,
+    },
+}
+
+impl From<NetworkError> for PingError {
+    fn from(value: NetworkError) -> Self {
+        Self::Network(value)
+    }
+}
+
+impl From<std::io::Error> for PingError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Network(value.into())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Status {
+    pub version: String,
+    pub protocol: i64,
+    pub online: i64,
+    pub max: i64,
+    /// Some of the players that are online, servers may also use this for arbitrary text
+    pub sample: Vec<protocol::status::client::SamplePlayer>,
+    /// The description as plain text, without any formatting
+    pub motd: String,
+    pub favicon: bool,
+    /// The round trip time of the ping in milliseconds
+    pub latency: f64,
+    /// Whether the status was received using the legacy ping
+    pub legacy: bool,
+}
+
+/// Requests the status and measures the latency with a ping afterwards
+pub async fn status<T>(
+    connection: &mut Connection<T>,
+    host: &str,
+    port: u16,
+) -> Result<Status, PingError>
+where
+    T: Transport,
+{
+    connection.set_state(ConnectionState::Handshake);
+    connection
+        .send_packet(&Packet {
+            inner: protocol::handshake::server::Handshaking {
+                protocol_version: VarInt(PROTOCOL_VERSION),
+                server_addr: PString(host.to_string().into()),
+                server_port: port,
+                next_state: protocol::handshake::server::NextState::Status,
+            },
+        })
+        .await?;
+    connection.set_state(ConnectionState::Status);
+
+    connection
+        .send_packet(&Packet {
+            inner: protocol::status::server::StatusRequest {},
+        })
+        .await?;
+    let response = connection
+        .recv_packet(protocol::status::client::StatusResponse::parse)
+        .await?;
+    let content = response.inner.content().map_err(PingError::InvalidStatus)?;
+
+    let payload = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    let start = std::time::Instant::now();
+    connection
+        .send_packet(&Packet {
+            inner: protocol::status::server::PingRequest { payload },
+        })
+        .await?;
+    let pong = connection
+        .recv_packet(protocol::status::client::PingResponse::parse)
+        .await?;
+    let latency = start.elapsed();
+
+    if pong.inner.payload != payload {
+        return Err(PingError::PayloadMismatch {
+            expected: payload,
+            received: pong.inner.payload,
+        });
+    }
+
+    Ok(Status {
+        version: content.version.name,
+        protocol: content.version.protocol.into(),
+        online: content.players.online as i64,
+        max: content.players.max as i64,
+        sample: content.players.sample,
+        motd: content.description.plain_text(),
+        favicon: content.favicon.is_some(),
+        latency: latency.as_secs_f64() * 1000.0,
+        legacy: false,
+    })
+}
+
+/// Requests the status using the ping of clients before 1.7, the server closes the connection
+/// after responding
+pub async fn legacy_status<S>(
+    mut stream: S,
+    timeout: std::time::Duration,
+) -> Result<Status, PingError>
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + core::marker::Unpin,
+{
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let start = std::time::Instant::now();
+    let exchange = async {
+        stream.write_all(&protocol::status::legacy::REQUEST).await?;
+
+        let mut buffer = Vec::new();
+        stream.read_to_end(&mut buffer).await?;
+        Ok::<_, std::io::Error>(buffer)
+    };
+    let buffer = match tokio::time::timeout(timeout, exchange).await {
+        Ok(buffer) => buffer?,
+        Err(_) => return Err(NetworkError::Timeout.into()),
+    };
+    let latency = start.elapsed();
+
+    let status = match protocol::status::legacy::LegacyStatus::parse(&buffer) {
+        Ok((_, status)) => status,
+        Err(nom::Err::Incomplete(_)) => return Err(NetworkError::Eof.into()),
+        Err(nom::Err::Error(e) | nom::Err::Failure(e)) => return Err(NetworkError::Parse(e).into()),
+    };
+
+    Ok(Status {
+        version: status.version.unwrap_or_default(),
+        protocol: status.protocol.map(i64::from).unwrap_or(-1),
+        online: status.online.into(),
+        max: status.max.into(),
+        sample: Vec::new(),
+        motd: status.motd,
+        favicon: false,
+        latency: latency.as_secs_f64() * 1000.0,
+        legacy: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn status_and_ping() {
+        let (mut connection, mut server) = networking::memory::pair(64 * 1024, Default::default());
+
+        let script = async move {
+            server
+                .recv_packet(protocol::handshake::server::Handshaking::parse)
+                .await
+                .unwrap();
+            server
+                .recv_packet(protocol::status::server::StatusRequest::parse)
+                .await
+                .unwrap();
+
+            let content = protocol::status::client::StatusResponseContent {
+                version: protocol::status::client::StatusVersion {
+                    name: "1.20.6".into(),
+                    protocol: 766,
+                },
+                players: protocol::status::client::StatusPlayers {
+                    max: 20,
+                    online: 2,
+                    sample: Vec::new(),
+                },
+                description: protocol::status::client::StatusDescription::text("testing"),
+                favicon: None,
+                enforces_secure_chat: false,
+                previews_chat: false,
+            };
+            server
+                .send_packet(&Packet {
+                    inner: protocol::status::client::StatusResponse::new(&content),
+                })
+                .await
+                .unwrap();
+
+            let ping = server
+                .recv_packet(protocol::status::server::PingRequest::parse)
+                .await
+                .unwrap();
+            server
+                .send_packet(&Packet {
+                    inner: protocol::status::client::PingResponse {
+                        payload: ping.inner.payload,
+                    },
+                })
+                .await
+                .unwrap();
+        };
+
+        let (_, status) = tokio::join!(script, status(&mut connection, "localhost", 25565));
+        let status = status.unwrap();
+
+        assert_eq!("1.20.6", status.version);
+        assert_eq!(766, status.protocol);
+        assert_eq!((2, 20), (status.online, status.max));
+        assert_eq!("testing", status.motd);
+        assert!(!status.favicon);
+        assert!(!status.legacy);
+    }
+
+    #[tokio::test]
+    async fn legacy_ping() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (client, mut server) = tokio::io::duplex(1024);
+
+        let script = async move {
+            let mut request = [0; 2];
+            server.read_exact(&mut request).await.unwrap();
+            assert_eq!(protocol::status::legacy::REQUEST, request);
+
+            let status = protocol::status::legacy::LegacyStatus {
+                protocol: Some(127),
+                version: Some("1.20.6".into()),
+                motd: "old".into(),
+                online: 1,
+                max: 10,
+            };
+            server.write_all(&status.serialize()).await.unwrap();
+        };
+
+        let (_, status) = tokio::join!(
+            script,
+            legacy_status(client, std::time::Duration::from_secs(5))
+        );
+        let status = status.unwrap();
+
+        assert_eq!("old", status.motd);
+        assert_eq!(127, status.protocol);
+        assert_eq!((1, 10), (status.online, status.max));
+        assert!(status.legacy);
+    }
+}
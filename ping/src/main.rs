@@ -0,0 +1,197 @@
+//! Prints the status of a server
+//!
+//! ```text
+//! ping [--json] [--legacy] [--timeout <seconds>] <host>[:<port>]
+//! ```
+//!
+//! Servers that do not answer the current status request are queried using the legacy ping,
+//! `--legacy` skips straight to it. With `--json` the status is printed as a single JSON object,
+//! for use by monitoring. IPv6 addresses with a port have to be enclosed in brackets, like
+//! `[::1]:25565`.
+
+use std::time::Duration;
+
+use networking::{Connection, ConnectionConfig};
+
+struct Args {
+    host: String,
+    port: u16,
+    json: bool,
+    legacy: bool,
+    timeout: Duration,
+}
+
+const USAGE: &str = "Usage: ping [--json] [--legacy] [--timeout <seconds>] <host>[:<port>]";
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut address = None;
+    let mut json = false;
+    let mut legacy = false;
+    let mut timeout = Duration::from_secs(5);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--legacy" => legacy = true,
+            "--timeout" => {
+                let seconds = args
+                    .next()
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .ok_or("--timeout expects a number of seconds")?;
+                timeout = Duration::from_secs(seconds);
+            }
+            other if other.starts_with("--") => return Err(format!("Unknown option {}", other)),
+            _ if address.is_some() => return Err("Only one address can be given".into()),
+            _ => address = Some(arg),
+        };
+    }
+
+    let address = address.ok_or("Missing the address of the server")?;
+    let (host, port) = parse_address(&address)?;
+
+    Ok(Args {
+        host,
+        port,
+        json,
+        legacy,
+        timeout,
+    })
+}
+
+/// Splits the address into the host and port, using the default port if none is given
+fn parse_address(address: &str) -> Result<(String, u16), String> {
+    let parse_port = |port: &str| {
+        port.parse::<u16>()
+            .map_err(|_| format!("Invalid port {}", port))
+    };
+
+    if let Some(rest) = address.strip_prefix('[') {
+        let (host, rest) = rest
+            .split_once(']')
+            .ok_or_else(|| format!("Missing the closing bracket in {}", address))?;
+        let port = match rest {
+            "" => 25565,
+            _ => match rest.strip_prefix(':') {
+                Some(port) => parse_port(port)?,
+                None => return Err(format!("Invalid address {}", address)),
+            },
+        };
+        return Ok((host.to_string(), port));
+    }
+
+    match address.split_once(':') {
+        // More than one colon without brackets is an IPv6 address without a port
+        Some((_, port)) if port.contains(':') => Ok((address.to_string(), 25565)),
+        Some((host, port)) => Ok((host.to_string(), parse_port(port)?)),
+        None => Ok((address.to_string(), 25565)),
+    }
+}
+
+async fn query(args: &Args) -> Result<ping::Status, ping::PingError> {
+    let connect = || async {
+        match tokio::time::timeout(
+            args.timeout,
+            tokio::net::TcpStream::connect((args.host.as_str(), args.port)),
+        )
+        .await
+        {
+            Ok(stream) => Ok(stream?),
+            Err(_) => Err(ping::PingError::Network(networking::NetworkError::Timeout)),
+        }
+    };
+
+    if !args.legacy {
+        let config = ConnectionConfig {
+            handshake_timeout: args.timeout,
+            status_timeout: args.timeout,
+            write_timeout: args.timeout,
+            ..Default::default()
+        };
+
+        let stream = connect().await?;
+        config.configure_tcp(&stream)?;
+        let mut connection = Connection::with_config(
+            networking::UnencryptedConnection::new(stream),
+            bytes::BytesMut::with_capacity(4096),
+            config,
+        );
+
+        match ping::status(&mut connection, &args.host, args.port).await {
+            Ok(status) => return Ok(status),
+            Err(e) => eprintln!("Status request failed, trying the legacy ping: {:?}", e),
+        };
+    }
+
+    ping::legacy_status(connect().await?, args.timeout).await
+}
+
+fn print(status: &ping::Status) {
+    println!("Version: {} (protocol {})", status.version, status.protocol);
+    println!("Players: {}/{}", status.online, status.max);
+    for player in status.sample.iter() {
+        println!("  {} ({:032x})", player.name, player.id);
+    }
+    println!("MOTD: {}", status.motd);
+    println!("Favicon: {}", if status.favicon { "yes" } else { "no" });
+    println!("Latency: {:.2} ms", status.latency);
+    if status.legacy {
+        println!("Received using the legacy ping");
+    }
+}
+
+fn main() {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(a) => a,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let status = match runtime.block_on(query(&args)) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Querying {}:{}: {:?}", args.host, args.port, e);
+            std::process::exit(1);
+        }
+    };
+
+    if args.json {
+        println!("{}", serde_json::to_string(&status).unwrap());
+    } else {
+        print(&status);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn addresses() {
+        assert_eq!(
+            Ok(("localhost".to_string(), 25565)),
+            parse_address("localhost")
+        );
+        assert_eq!(
+            Ok(("127.0.0.1".to_string(), 25566)),
+            parse_address("127.0.0.1:25566")
+        );
+        assert_eq!(Ok(("::1".to_string(), 25565)), parse_address("::1"));
+        assert_eq!(
+            Ok(("2001:db8::1".to_string(), 25565)),
+            parse_address("2001:db8::1")
+        );
+        assert_eq!(Ok(("::1".to_string(), 25566)), parse_address("[::1]:25566"));
+        assert_eq!(Ok(("::1".to_string(), 25565)), parse_address("[::1]"));
+
+        assert!(parse_address("localhost:port").is_err());
+        assert!(parse_address("[::1").is_err());
+        assert!(parse_address("[::1]25566").is_err());
+    }
+}
@@ -0,0 +1,273 @@
+//! The types used by the signed chat
+//!
+//! Every player with a [`ChatSession`] signs their messages, and every message also includes the
+//! signatures of the last messages the player has seen, which are acknowledged using the
+//! [`LastSeenUpdate`].
+
+use crate::{
+    general::{BitSet, ParseError, VarInt},
+    serialize::{SerializeError, SerializeItem},
+};
+
+/// The number of previously seen messages included in every signed message
+pub const LAST_SEEN_MESSAGES: usize = 20;
+
+/// The length of a message signature in bytes
+pub const SIGNATURE_LENGTH: usize = 256;
+
+/// The chat session of a player, used to verify the signatures of their messages
+#[derive(Debug, PartialEq, Clone)]
+pub struct ChatSession {
+    pub session_id: u128,
+    /// The expiry time of the key, in milliseconds since the unix epoch
+    pub expires_at: i64,
+    /// The DER encoded public key
+    pub public_key: Vec<u8>,
+    /// The signature of the public key, created by Mojang
+    pub key_signature: Vec<u8>,
+}
+
+impl SerializeItem for ChatSession {
+    fn slen(&self) -> usize {
+        self.session_id.slen()
+            + self.expires_at.slen()
+            + self.public_key.slen()
+            + self.key_signature.slen()
+    }
+
+    fn serialize<'b>(&self, mut buf: &'b mut [u8]) -> Result<&'b mut [u8], SerializeError> {
+        buf = self.session_id.serialize(buf)?;
+        buf = self.expires_at.serialize(buf)?;
+        buf = self.public_key.serialize(buf)?;
+        self.key_signature.serialize(buf)
+    }
+
+    fn parse(i: &[u8]) -> nom::IResult<&[u8], Self, ParseError> {
+        let (i, session_id) = u128::parse(i)?;
+        let (i, expires_at) = i64::parse(i)?;
+        let (i, public_key) = Vec::<u8>::parse(i)?;
+        let (i, key_signature) = Vec::<u8>::parse(i)?;
+
+        Ok((
+            i,
+            Self {
+                session_id,
+                expires_at,
+                public_key,
+                key_signature,
+            },
+        ))
+    }
+}
+
+/// The RSA signature of a message, send without a length prefix
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MessageSignature(pub Box<[u8; SIGNATURE_LENGTH]>);
+
+impl SerializeItem for MessageSignature {
+    fn slen(&self) -> usize {
+        SIGNATURE_LENGTH
+    }
+
+    fn serialize<'b>(&self, buf: &'b mut [u8]) -> Result<&'b mut [u8], SerializeError> {
+        if buf.len() < SIGNATURE_LENGTH {
+            return Err(SerializeError::NotEnoughSpace {
+                missing: SIGNATURE_LENGTH - buf.len(),
+            });
+        }
+
+        buf[..SIGNATURE_LENGTH].copy_from_slice(self.0.as_slice());
+        Ok(&mut buf[SIGNATURE_LENGTH..])
+    }
+
+    fn parse(i: &[u8]) -> nom::IResult<&[u8], Self, ParseError> {
+        let (i, data) = nom::bytes::streaming::take(SIGNATURE_LENGTH)(i)?;
+
+        let mut signature = Box::new([0; SIGNATURE_LENGTH]);
+        signature.copy_from_slice(data);
+        Ok((i, Self(signature)))
+    }
+}
+
+/// Acknowledges the messages the client has seen since its last update
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LastSeenUpdate {
+    /// The number of messages received since the last update
+    pub offset: VarInt,
+    /// Bit `i` is set if the `i`-th message in the window of the last
+    /// [`LAST_SEEN_MESSAGES`] is acknowledged, send as a fixed number of bytes
+    pub acknowledged: u32,
+}
+
+impl LastSeenUpdate {
+    const BYTES: usize = LAST_SEEN_MESSAGES.div_ceil(8);
+
+    /// Whether the message at the index in the window is acknowledged
+    pub fn is_acknowledged(&self, index: usize) -> bool {
+        index < LAST_SEEN_MESSAGES && self.acknowledged & (1 << index) != 0
+    }
+}
+
+impl SerializeItem for LastSeenUpdate {
+    fn slen(&self) -> usize {
+        self.offset.slen() + Self::BYTES
+    }
+
+    fn serialize<'b>(&self, mut buf: &'b mut [u8]) -> Result<&'b mut [u8], SerializeError> {
+        buf = self.offset.serialize(buf)?;
+        for byte in &self.acknowledged.to_le_bytes()[..Self::BYTES] {
+            buf = byte.serialize(buf)?;
+        }
+        Ok(buf)
+    }
+
+    fn parse(i: &[u8]) -> nom::IResult<&[u8], Self, ParseError> {
+        let (i, offset) = VarInt::parse(i)?;
+        let (i, bytes) = nom::bytes::streaming::take(Self::BYTES)(i)?;
+
+        let acknowledged = bytes
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (idx, b)| acc | (*b as u32) << (idx * 8));
+        Ok((
+            i,
+            Self {
+                offset,
+                acknowledged,
+            },
+        ))
+    }
+}
+
+/// A previously send message signature, either by its ID in the cache of the client or the
+/// complete signature
+#[derive(Debug, PartialEq, Clone)]
+pub enum PackedSignature {
+    Id(i32),
+    Full(MessageSignature),
+}
+
+impl SerializeItem for PackedSignature {
+    fn slen(&self) -> usize {
+        match self {
+            Self::Id(id) => VarInt(*id + 1).slen(),
+            Self::Full(signature) => VarInt(0).slen() + signature.slen(),
+        }
+    }
+
+    fn serialize<'b>(&self, buf: &'b mut [u8]) -> Result<&'b mut [u8], SerializeError> {
+        // The ID is offset by one, as 0 marks a full signature
+        match self {
+            Self::Id(id) => VarInt(*id + 1).serialize(buf),
+            Self::Full(signature) => {
+                let buf = VarInt(0).serialize(buf)?;
+                signature.serialize(buf)
+            }
+        }
+    }
+
+    fn parse(i: &[u8]) -> nom::IResult<&[u8], Self, ParseError> {
+        let (i, id) = VarInt::parse(i)?;
+        match id.0 {
+            0 => {
+                let (i, signature) = MessageSignature::parse(i)?;
+                Ok((i, Self::Full(signature)))
+            }
+            id => Ok((i, Self::Id(id - 1))),
+        }
+    }
+}
+
+/// Which parts of a message were filtered by the server
+#[derive(Debug, PartialEq, Clone)]
+pub enum FilterMask {
+    PassThrough,
+    FullyFiltered,
+    /// The set bits mark the filtered characters
+    PartiallyFiltered(BitSet),
+}
+
+impl SerializeItem for FilterMask {
+    fn slen(&self) -> usize {
+        match self {
+            Self::PassThrough | Self::FullyFiltered => VarInt(0).slen(),
+            Self::PartiallyFiltered(mask) => VarInt(2).slen() + mask.slen(),
+        }
+    }
+
+    fn serialize<'b>(&self, buf: &'b mut [u8]) -> Result<&'b mut [u8], SerializeError> {
+        match self {
+            Self::PassThrough => VarInt(0).serialize(buf),
+            Self::FullyFiltered => VarInt(1).serialize(buf),
+            Self::PartiallyFiltered(mask) => {
+                let buf = VarInt(2).serialize(buf)?;
+                mask.serialize(buf)
+            }
+        }
+    }
+
+    fn parse(i: &[u8]) -> nom::IResult<&[u8], Self, ParseError> {
+        let (i, kind) = VarInt::parse(i)?;
+        match kind.0 {
+            0 => Ok((i, Self::PassThrough)),
+            1 => Ok((i, Self::FullyFiltered)),
+            2 => {
+                let (i, mask) = BitSet::parse(i)?;
+                Ok((i, Self::PartiallyFiltered(mask)))
+            }
+            _ => Err(nom::Err::Error(ParseError::Other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip<T>(value: T) -> Vec<u8>
+    where
+        T: SerializeItem + PartialEq + std::fmt::Debug,
+    {
+        let mut buffer = vec![0; value.slen()];
+        let rem = value.serialize(&mut buffer).unwrap().len();
+        let written = buffer.len() - rem;
+        buffer.truncate(written);
+
+        let (remaining, parsed) = T::parse(&buffer).unwrap();
+        assert!(remaining.is_empty());
+        assert_eq!(value, parsed);
+        buffer
+    }
+
+    #[test]
+    fn last_seen_update() {
+        let update = LastSeenUpdate {
+            offset: VarInt(3),
+            acknowledged: 0b1000_0000_0000_0000_0101,
+        };
+
+        assert_eq!(vec![0x03, 0x05, 0x00, 0x08], roundtrip(update));
+        assert!(update.is_acknowledged(0));
+        assert!(!update.is_acknowledged(1));
+        assert!(update.is_acknowledged(19));
+        assert!(!update.is_acknowledged(20));
+    }
+
+    #[test]
+    fn packed_signatures() {
+        assert_eq!(vec![0x03], roundtrip(PackedSignature::Id(2)));
+
+        let data = roundtrip(PackedSignature::Full(MessageSignature(Box::new(
+            [7; SIGNATURE_LENGTH],
+        ))));
+        assert_eq!(1 + SIGNATURE_LENGTH, data.len());
+        assert_eq!(0, data[0]);
+    }
+
+    #[test]
+    fn filter_mask() {
+        roundtrip(FilterMask::PassThrough);
+        roundtrip(FilterMask::FullyFiltered);
+        roundtrip(FilterMask::PartiallyFiltered(BitSet::from_iter([1, 5])));
+    }
+}
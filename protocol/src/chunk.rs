@@ -0,0 +1,883 @@
+//! The format of the chunk data send to the client
+//!
+//! A chunk column is split into sections of `16x16x16` blocks, every section stores its block
+//! states and biomes in [`PalettedContainer`]s.
+//!
+//! [Docs](https://wiki.vg/Chunk_Format)
+
+use std::collections::HashMap;
+
+use crate::{
+    general::{BitSet, VarInt},
+    serialize::SerializeItem,
+};
+
+/// The block state of `minecraft:air`, which is not counted as a block in a section
+pub const AIR: i32 = 0;
+
+/// Describes the size and the encodings of a kind of [`PalettedContainer`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ContainerKind {
+    /// The number of entries in the container
+    pub entries: usize,
+    /// The smallest number of bits used with an indirect palette
+    pub min_indirect_bits: u8,
+    /// The largest number of bits used with an indirect palette, anything larger uses the
+    /// direct encoding
+    pub max_indirect_bits: u8,
+    /// The number of bits used for the direct encoding, depends on the size of the registry
+    pub direct_bits: u8,
+}
+
+impl ContainerKind {
+    /// The block states of a section, stored in `16x16x16` entries
+    pub const BLOCK_STATES: Self = Self {
+        entries: 4096,
+        min_indirect_bits: 4,
+        max_indirect_bits: 8,
+        direct_bits: 15,
+    };
+
+    /// The biomes of a section, stored in `4x4x4` entries
+    pub const BIOMES: Self = Self {
+        entries: 64,
+        min_indirect_bits: 1,
+        max_indirect_bits: 3,
+        direct_bits: 6,
+    };
+
+    /// The biomes with the direct encoding based on the given number of biomes in the registry
+    pub fn biomes(registry_size: usize) -> Self {
+        Self {
+            direct_bits: bits_needed(registry_size).max(1),
+            ..Self::BIOMES
+        }
+    }
+}
+
+/// The number of bits needed to represent `count` different values
+fn bits_needed(count: usize) -> u8 {
+    (usize::BITS - count.saturating_sub(1).leading_zeros()) as u8
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ChunkError {
+    /// The number of values does not match the entries of the container
+    WrongLength { expected: usize, received: usize },
+    /// The value can not be stored using the direct encoding
+    ValueTooLarge(i32),
+    /// The data does not contain enough entries or references an entry outside of the palette
+    InvalidData,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Palette {
+    /// All the entries have the same value
+    Single(i32),
+    /// The entries are indices into the list of values
+    Indirect(Vec<i32>),
+    /// The entries are the values themselves
+    Direct,
+}
+
+/// A fixed number of entries, bit-packed into longs using a palette
+#[derive(Debug, PartialEq, Clone)]
+pub struct PalettedContainer {
+    pub bits_per_entry: u8,
+    pub palette: Palette,
+    /// The packed entries, entries never span across two longs
+    pub data: Vec<u64>,
+}
+
+impl PalettedContainer {
+    /// A container with every entry set to the value
+    pub fn single(value: i32) -> Self {
+        Self {
+            bits_per_entry: 0,
+            palette: Palette::Single(value),
+            data: Vec::new(),
+        }
+    }
+
+    /// Encodes the dense values using the smallest encoding
+    pub fn encode(kind: &ContainerKind, values: &[i32]) -> Result<Self, ChunkError> {
+        if values.len() != kind.entries {
+            return Err(ChunkError::WrongLength {
+                expected: kind.entries,
+                received: values.len(),
+            });
+        }
+
+        // The index of every value in the palette, which is in the order the values first appear
+        let mut palette: Vec<i32> = Vec::new();
+        let mut palette_indices: HashMap<i32, usize> = HashMap::new();
+        for value in values {
+            palette_indices.entry(*value).or_insert_with(|| {
+                palette.push(*value);
+                palette.len() - 1
+            });
+        }
+
+        if palette.len() == 1 {
+            return Ok(Self::single(palette[0]));
+        }
+
+        let indirect_bits = bits_needed(palette.len()).max(kind.min_indirect_bits);
+        if indirect_bits <= kind.max_indirect_bits {
+            let indices = values.iter().map(|v| palette_indices[v] as u64);
+
+            return Ok(Self {
+                bits_per_entry: indirect_bits,
+                data: pack(indirect_bits, kind.entries, indices),
+                palette: Palette::Indirect(palette),
+            });
+        }
+
+        if let Some(value) = values
+            .iter()
+            .find(|v| **v < 0 || **v as u64 >= 1 << kind.direct_bits)
+        {
+            return Err(ChunkError::ValueTooLarge(*value));
+        }
+
+        Ok(Self {
+            bits_per_entry: kind.direct_bits,
+            data: pack(
+                kind.direct_bits,
+                kind.entries,
+                values.iter().map(|v| *v as u64),
+            ),
+            palette: Palette::Direct,
+        })
+    }
+
+    /// Decodes all the entries into their values
+    pub fn decode(&self, kind: &ContainerKind) -> Result<Vec<i32>, ChunkError> {
+        match &self.palette {
+            Palette::Single(value) => Ok(vec![*value; kind.entries]),
+            Palette::Indirect(palette) => unpack(self.bits_per_entry, kind.entries, &self.data)?
+                .into_iter()
+                .map(|idx| palette.get(idx as usize).copied())
+                .collect::<Option<Vec<_>>>()
+                .ok_or(ChunkError::InvalidData),
+            Palette::Direct => Ok(unpack(self.bits_per_entry, kind.entries, &self.data)?
+                .into_iter()
+                .map(|v| v as i32)
+                .collect()),
+        }
+    }
+
+    pub fn slen(&self) -> usize {
+        let palette_len = match &self.palette {
+            Palette::Single(value) => VarInt(*value).slen(),
+            Palette::Indirect(values) => {
+                VarInt(values.len() as i32).slen()
+                    + values.iter().map(|v| VarInt(*v).slen()).sum::<usize>()
+            }
+            Palette::Direct => 0,
+        };
+
+        1 + palette_len + VarInt(self.data.len() as i32).slen() + self.data.len() * 8
+    }
+
+    pub fn serialize<'b>(
+        &self,
+        mut buf: &'b mut [u8],
+    ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
+        buf = self.bits_per_entry.serialize(buf)?;
+        buf = match &self.palette {
+            Palette::Single(value) => VarInt(*value).serialize(buf)?,
+            Palette::Indirect(values) => values
+                .iter()
+                .map(|v| VarInt(*v))
+                .collect::<Vec<_>>()
+                .serialize(buf)?,
+            Palette::Direct => buf,
+        };
+        self.data
+            .iter()
+            .map(|v| *v as i64)
+            .collect::<Vec<_>>()
+            .serialize(buf)
+    }
+
+    /// Parses a container of the given kind, which determines the encoding used for the bits
+    /// per entry
+    pub fn parse<'i>(
+        kind: &ContainerKind,
+        i: &'i [u8],
+    ) -> nom::IResult<&'i [u8], Self, crate::general::ParseError> {
+        let (i, bits_per_entry) = u8::parse(i)?;
+
+        let (i, palette) = if bits_per_entry == 0 {
+            let (i, value) = VarInt::parse(i)?;
+            (i, Palette::Single(value.0))
+        } else if bits_per_entry <= kind.max_indirect_bits {
+            let (i, values) = Vec::<VarInt>::parse(i)?;
+            (
+                i,
+                Palette::Indirect(values.into_iter().map(|v| v.0).collect()),
+            )
+        } else {
+            (i, Palette::Direct)
+        };
+
+        let (i, data) = Vec::<i64>::parse(i)?;
+
+        Ok((
+            i,
+            Self {
+                bits_per_entry,
+                palette,
+                data: data.into_iter().map(|v| v as u64).collect(),
+            },
+        ))
+    }
+}
+
+fn pack(bits: u8, entries: usize, values: impl Iterator<Item = u64>) -> Vec<u64> {
+    let per_long = 64 / bits as usize;
+    let mut data = vec![0; entries.div_ceil(per_long)];
+
+    for (idx, value) in values.enumerate() {
+        let offset = (idx % per_long) * bits as usize;
+        data[idx / per_long] |= value << offset;
+    }
+
+    data
+}
+
+fn unpack(bits: u8, entries: usize, data: &[u64]) -> Result<Vec<u64>, ChunkError> {
+    if bits == 0 || bits > 64 {
+        return Err(ChunkError::InvalidData);
+    }
+
+    let per_long = 64 / bits as usize;
+    if data.len() < entries.div_ceil(per_long) {
+        return Err(ChunkError::InvalidData);
+    }
+
+    let mask = u64::MAX >> (64 - bits as u32);
+    Ok((0..entries)
+        .map(|idx| {
+            let offset = (idx % per_long) * bits as usize;
+            (data[idx / per_long] >> offset) & mask
+        })
+        .collect())
+}
+
+/// A `16x16x16` section of a chunk column
+#[derive(Debug, PartialEq, Clone)]
+pub struct ChunkSection {
+    /// The number of non-air blocks, used by the client to skip empty sections
+    pub block_count: i16,
+    pub block_states: PalettedContainer,
+    pub biomes: PalettedContainer,
+}
+
+impl ChunkSection {
+    /// A section only containing air in the given biome
+    pub fn empty(biome: i32) -> Self {
+        Self {
+            block_count: 0,
+            block_states: PalettedContainer::single(AIR),
+            biomes: PalettedContainer::single(biome),
+        }
+    }
+
+    /// The index of a block in the dense representation
+    pub fn block_index(x: usize, y: usize, z: usize) -> usize {
+        (y * 16 + z) * 16 + x
+    }
+
+    /// The index of a biome in the dense representation, every biome covers `4x4x4` blocks
+    pub fn biome_index(x: usize, y: usize, z: usize) -> usize {
+        (y * 4 + z) * 4 + x
+    }
+
+    /// Creates a section from the dense block states and biomes, see
+    /// [`block_index`](Self::block_index) and [`biome_index`](Self::biome_index) for their
+    /// layout
+    pub fn from_dense(blocks: &[i32], biomes: &[i32]) -> Result<Self, ChunkError> {
+        Self::from_dense_with(blocks, biomes, &ContainerKind::BIOMES)
+    }
+
+    /// Like [`from_dense`](Self::from_dense), but with a custom encoding for the biomes
+    pub fn from_dense_with(
+        blocks: &[i32],
+        biomes: &[i32],
+        biome_kind: &ContainerKind,
+    ) -> Result<Self, ChunkError> {
+        let block_states = PalettedContainer::encode(&ContainerKind::BLOCK_STATES, blocks)?;
+        let biomes = PalettedContainer::encode(biome_kind, biomes)?;
+
+        Ok(Self {
+            block_count: blocks.iter().filter(|b| **b != AIR).count() as i16,
+            block_states,
+            biomes,
+        })
+    }
+
+    /// The dense block states of the section
+    pub fn blocks(&self) -> Result<Vec<i32>, ChunkError> {
+        self.block_states.decode(&ContainerKind::BLOCK_STATES)
+    }
+
+    /// The dense biomes of the section
+    pub fn biomes(&self) -> Result<Vec<i32>, ChunkError> {
+        self.biomes.decode(&ContainerKind::BIOMES)
+    }
+}
+
+impl SerializeItem for ChunkSection {
+    fn slen(&self) -> usize {
+        self.block_count.slen() + self.block_states.slen() + self.biomes.slen()
+    }
+
+    fn serialize<'b>(
+        &self,
+        mut buf: &'b mut [u8],
+    ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
+        buf = self.block_count.serialize(buf)?;
+        buf = self.block_states.serialize(buf)?;
+        self.biomes.serialize(buf)
+    }
+
+    fn parse(i: &[u8]) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
+        let (i, block_count) = i16::parse(i)?;
+        let (i, block_states) = PalettedContainer::parse(&ContainerKind::BLOCK_STATES, i)?;
+        let (i, biomes) = PalettedContainer::parse(&ContainerKind::BIOMES, i)?;
+
+        Ok((
+            i,
+            Self {
+                block_count,
+                block_states,
+                biomes,
+            },
+        ))
+    }
+}
+
+/// The sections of a chunk column, from the bottom of the world to the top
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ChunkData {
+    pub sections: Vec<ChunkSection>,
+}
+
+impl SerializeItem for ChunkData {
+    fn slen(&self) -> usize {
+        let size = self.sections.iter().map(|s| s.slen()).sum::<usize>();
+        VarInt(size as i32).slen() + size
+    }
+
+    fn serialize<'b>(
+        &self,
+        buf: &'b mut [u8],
+    ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
+        // The size is prefixed, so the sections need to be serialized first
+        let mut content = vec![0; self.sections.iter().map(|s| s.slen()).sum::<usize>()];
+        let mut remaining = content.as_mut_slice();
+        for section in self.sections.iter() {
+            remaining = section.serialize(remaining)?;
+        }
+        let remaining = remaining.len();
+        content.truncate(content.len() - remaining);
+
+        let buf = VarInt(content.len() as i32).serialize(buf)?;
+        if buf.len() < content.len() {
+            return Err(crate::serialize::SerializeError::NotEnoughSpace {
+                missing: content.len() - buf.len(),
+            });
+        }
+        (buf[..content.len()]).copy_from_slice(&content);
+        Ok(&mut buf[content.len()..])
+    }
+
+    fn parse(i: &[u8]) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
+        let (i, size) = VarInt::parse(i)?;
+        if size.0 < 0 {
+            return Err(nom::Err::Error(crate::general::ParseError::NegativeLength));
+        }
+        let (i, mut content) = nom::bytes::streaming::take(size.0 as usize)(i)?;
+
+        let mut sections = Vec::new();
+        while !content.is_empty() {
+            let (rem, section) = ChunkSection::parse(content)?;
+            sections.push(section);
+            content = rem;
+        }
+
+        Ok((i, Self { sections }))
+    }
+}
+
+/// The heightmaps used by the client
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum HeightmapKind {
+    /// The highest block that blocks motion or contains a fluid, used for rain
+    MotionBlocking,
+    /// The highest non-air block
+    WorldSurface,
+}
+
+impl HeightmapKind {
+    /// The name used as the key in the NBT compound
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::MotionBlocking => "MOTION_BLOCKING",
+            Self::WorldSurface => "WORLD_SURFACE",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "MOTION_BLOCKING" => Some(Self::MotionBlocking),
+            "WORLD_SURFACE" => Some(Self::WorldSurface),
+            _ => None,
+        }
+    }
+}
+
+/// The height of every column in a chunk, relative to the bottom of the world.
+///
+/// A height of `0` means the column contains no matching block, otherwise it is one above the
+/// highest matching block
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Heightmap {
+    heights: Box<[u16; 256]>,
+}
+
+impl Default for Heightmap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Heightmap {
+    pub fn new() -> Self {
+        Self {
+            heights: Box::new([0; 256]),
+        }
+    }
+
+    pub fn get(&self, x: usize, z: usize) -> u16 {
+        self.heights[z * 16 + x]
+    }
+
+    pub fn set(&mut self, x: usize, z: usize, height: u16) {
+        self.heights[z * 16 + x] = height;
+    }
+
+    /// The number of bits used for every column, based on the height of the world
+    fn bits(world_height: u16) -> u8 {
+        bits_needed(world_height as usize + 1)
+    }
+
+    /// Packs the heights into longs, like the entries of a [`PalettedContainer`]
+    pub fn pack(&self, world_height: u16) -> Vec<i64> {
+        pack(
+            Self::bits(world_height),
+            256,
+            self.heights.iter().map(|h| *h as u64),
+        )
+        .into_iter()
+        .map(|v| v as i64)
+        .collect()
+    }
+
+    pub fn unpack(world_height: u16, data: &[i64]) -> Result<Self, ChunkError> {
+        let data: Vec<u64> = data.iter().map(|v| *v as u64).collect();
+        let values = unpack(Self::bits(world_height), 256, &data)?;
+
+        let mut heights = Box::new([0; 256]);
+        for (height, value) in heights.iter_mut().zip(values) {
+            if value > world_height as u64 {
+                return Err(ChunkError::InvalidData);
+            }
+            *height = value as u16;
+        }
+
+        Ok(Self { heights })
+    }
+}
+
+/// The light levels of a section, stored as one nibble per block
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LightArray(pub Box<[u8; 2048]>);
+
+impl LightArray {
+    /// A section where every block has the given light level
+    pub fn filled(level: u8) -> Self {
+        let level = level & 0x0f;
+        Self(Box::new([level | (level << 4); 2048]))
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> u8 {
+        let idx = ChunkSection::block_index(x, y, z);
+        (self.0[idx / 2] >> ((idx % 2) * 4)) & 0x0f
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, level: u8) {
+        let idx = ChunkSection::block_index(x, y, z);
+        let shift = (idx % 2) * 4;
+        self.0[idx / 2] = (self.0[idx / 2] & !(0x0f << shift)) | ((level & 0x0f) << shift);
+    }
+
+    /// Whether all the blocks have a light level of 0
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|v| *v == 0)
+    }
+}
+
+impl Default for LightArray {
+    fn default() -> Self {
+        Self::filled(0)
+    }
+}
+
+impl SerializeItem for LightArray {
+    fn slen(&self) -> usize {
+        VarInt(2048).slen() + 2048
+    }
+
+    fn serialize<'b>(
+        &self,
+        buf: &'b mut [u8],
+    ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
+        let buf = VarInt(2048).serialize(buf)?;
+        if buf.len() < 2048 {
+            return Err(crate::serialize::SerializeError::NotEnoughSpace {
+                missing: 2048 - buf.len(),
+            });
+        }
+        (buf[..2048]).copy_from_slice(self.0.as_slice());
+        Ok(&mut buf[2048..])
+    }
+
+    fn parse(i: &[u8]) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
+        let (i, length) = VarInt::parse(i)?;
+        if length.0 != 2048 {
+            return Err(nom::Err::Error(crate::general::ParseError::Other));
+        }
+        let (i, data) = nom::bytes::streaming::take(2048usize)(i)?;
+
+        let mut array = Box::new([0; 2048]);
+        array.copy_from_slice(data);
+        Ok((i, Self(array)))
+    }
+}
+
+/// The light of a chunk column, as send in the Chunk Data and Update Light packets.
+///
+/// There is one more section below and above the world, so the index of a light section is one
+/// larger than the index of the corresponding chunk section
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct LightData {
+    /// The sections with sky light contained in `sky_light_arrays`
+    pub sky_light_mask: BitSet,
+    pub block_light_mask: BitSet,
+    /// The sections where all the sky light levels are 0
+    pub empty_sky_light_mask: BitSet,
+    pub empty_block_light_mask: BitSet,
+    pub sky_light_arrays: Vec<LightArray>,
+    pub block_light_arrays: Vec<LightArray>,
+}
+
+impl LightData {
+    /// Creates the light data from the light sections, empty sections are only marked in the
+    /// masks and sections without a value are not send at all
+    pub fn new(sky: &[Option<LightArray>], block: &[Option<LightArray>]) -> Self {
+        fn split(sections: &[Option<LightArray>]) -> (BitSet, BitSet, Vec<LightArray>) {
+            let mut mask = BitSet::new();
+            let mut empty = BitSet::new();
+            let mut arrays = Vec::new();
+
+            for (idx, section) in sections.iter().enumerate() {
+                match section {
+                    Some(s) if s.is_empty() => empty.set(idx, true),
+                    Some(s) => {
+                        mask.set(idx, true);
+                        arrays.push(s.clone());
+                    }
+                    None => {}
+                };
+            }
+
+            (mask, empty, arrays)
+        }
+
+        let (sky_light_mask, empty_sky_light_mask, sky_light_arrays) = split(sky);
+        let (block_light_mask, empty_block_light_mask, block_light_arrays) = split(block);
+
+        Self {
+            sky_light_mask,
+            block_light_mask,
+            empty_sky_light_mask,
+            empty_block_light_mask,
+            sky_light_arrays,
+            block_light_arrays,
+        }
+    }
+
+    /// The sky light of the section at the light index, if it was send
+    pub fn sky_light(&self, index: usize) -> Option<LightArray> {
+        Self::section(
+            &self.sky_light_mask,
+            &self.empty_sky_light_mask,
+            &self.sky_light_arrays,
+            index,
+        )
+    }
+
+    /// The block light of the section at the light index, if it was send
+    pub fn block_light(&self, index: usize) -> Option<LightArray> {
+        Self::section(
+            &self.block_light_mask,
+            &self.empty_block_light_mask,
+            &self.block_light_arrays,
+            index,
+        )
+    }
+
+    fn section(
+        mask: &BitSet,
+        empty: &BitSet,
+        arrays: &[LightArray],
+        index: usize,
+    ) -> Option<LightArray> {
+        if empty.get(index) {
+            return Some(LightArray::default());
+        }
+        if !mask.get(index) {
+            return None;
+        }
+
+        let position = mask.iter().take_while(|i| *i < index).count();
+        arrays.get(position).cloned()
+    }
+}
+
+impl SerializeItem for LightData {
+    fn slen(&self) -> usize {
+        self.sky_light_mask.slen()
+            + self.block_light_mask.slen()
+            + self.empty_sky_light_mask.slen()
+            + self.empty_block_light_mask.slen()
+            + self.sky_light_arrays.slen()
+            + self.block_light_arrays.slen()
+    }
+
+    fn serialize<'b>(
+        &self,
+        mut buf: &'b mut [u8],
+    ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
+        buf = self.sky_light_mask.serialize(buf)?;
+        buf = self.block_light_mask.serialize(buf)?;
+        buf = self.empty_sky_light_mask.serialize(buf)?;
+        buf = self.empty_block_light_mask.serialize(buf)?;
+        buf = self.sky_light_arrays.serialize(buf)?;
+        self.block_light_arrays.serialize(buf)
+    }
+
+    fn parse(i: &[u8]) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
+        let (i, sky_light_mask) = BitSet::parse(i)?;
+        let (i, block_light_mask) = BitSet::parse(i)?;
+        let (i, empty_sky_light_mask) = BitSet::parse(i)?;
+        let (i, empty_block_light_mask) = BitSet::parse(i)?;
+        let (i, sky_light_arrays) = Vec::<LightArray>::parse(i)?;
+        let (i, block_light_arrays) = Vec::<LightArray>::parse(i)?;
+
+        Ok((
+            i,
+            Self {
+                sky_light_mask,
+                block_light_mask,
+                empty_sky_light_mask,
+                empty_block_light_mask,
+                sky_light_arrays,
+                block_light_arrays,
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(section: &ChunkSection) -> ChunkSection {
+        let mut buffer = vec![0; section.slen()];
+        let rem = section.serialize(&mut buffer).unwrap().len();
+        let written = buffer.len() - rem;
+
+        let (rem, parsed) = ChunkSection::parse(&buffer[..written]).unwrap();
+        assert!(rem.is_empty());
+        parsed
+    }
+
+    #[test]
+    fn single_valued() {
+        let section = ChunkSection::from_dense(&[1; 4096], &[39; 64]).unwrap();
+
+        assert_eq!(4096, section.block_count);
+        assert_eq!(PalettedContainer::single(1), section.block_states);
+        assert_eq!(PalettedContainer::single(39), section.biomes);
+        assert_eq!(section, roundtrip(&section));
+        assert_eq!(ChunkSection::empty(39), roundtrip(&ChunkSection::empty(39)));
+    }
+
+    #[test]
+    fn indirect() {
+        let blocks: Vec<i32> = (0..4096)
+            .map(|idx| if idx < 256 { 1 } else { AIR })
+            .collect();
+        let biomes: Vec<i32> = (0..64).map(|idx| idx % 3).collect();
+
+        let section = ChunkSection::from_dense(&blocks, &biomes).unwrap();
+        assert_eq!(256, section.block_count);
+        assert_eq!(4, section.block_states.bits_per_entry);
+        assert_eq!(
+            Palette::Indirect(vec![1, AIR]),
+            section.block_states.palette
+        );
+        assert_eq!(256, section.block_states.data.len());
+        assert_eq!(2, section.biomes.bits_per_entry);
+
+        let parsed = roundtrip(&section);
+        assert_eq!(section, parsed);
+        assert_eq!(Ok(blocks), parsed.blocks());
+        assert_eq!(Ok(biomes), parsed.biomes());
+    }
+
+    #[test]
+    fn direct() {
+        // 300 different values need 9 bits, which is too large for an indirect palette
+        let blocks: Vec<i32> = (0..4096).map(|idx| idx % 300).collect();
+
+        let section = ChunkSection::from_dense(&blocks, &[0; 64]).unwrap();
+        assert_eq!(15, section.block_states.bits_per_entry);
+        assert_eq!(Palette::Direct, section.block_states.palette);
+        // 4 entries per long, as entries dont span multiple longs
+        assert_eq!(1024, section.block_states.data.len());
+
+        let parsed = roundtrip(&section);
+        assert_eq!(Ok(blocks), parsed.blocks());
+    }
+
+    #[test]
+    fn packing() {
+        // 5 bits only fit 12 entries into a long, leaving the upper 4 bits unused
+        let data = pack(5, 13, (0..13).map(|v| v as u64));
+        assert_eq!(2, data.len());
+        assert_eq!(12, data[1]);
+        assert_eq!(11, data[0] >> 55);
+        assert_eq!(Ok((0..13).collect::<Vec<u64>>()), unpack(5, 13, &data));
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            Err(ChunkError::WrongLength {
+                expected: 64,
+                received: 1
+            }),
+            PalettedContainer::encode(&ContainerKind::BIOMES, &[0])
+        );
+        assert_eq!(
+            Err(ChunkError::ValueTooLarge(64)),
+            PalettedContainer::encode(&ContainerKind::BIOMES, &(1..=64).collect::<Vec<_>>())
+        );
+
+        let container = PalettedContainer {
+            bits_per_entry: 1,
+            palette: Palette::Indirect(vec![0]),
+            data: vec![u64::MAX],
+        };
+        assert_eq!(
+            Err(ChunkError::InvalidData),
+            container.decode(&ContainerKind::BIOMES)
+        );
+    }
+
+    #[test]
+    fn chunk_data() {
+        let data = ChunkData {
+            sections: vec![
+                ChunkSection::from_dense(&[1; 4096], &[0; 64]).unwrap(),
+                ChunkSection::empty(0),
+            ],
+        };
+
+        let mut buffer = vec![0; data.slen()];
+        data.serialize(&mut buffer).unwrap();
+
+        let (_, parsed) = ChunkData::parse(&buffer).unwrap();
+        assert_eq!(data, parsed);
+    }
+
+    #[test]
+    fn biome_kind() {
+        assert_eq!(6, ContainerKind::biomes(64).direct_bits);
+        assert_eq!(7, ContainerKind::biomes(65).direct_bits);
+        assert_eq!(1, ContainerKind::biomes(1).direct_bits);
+    }
+
+    #[test]
+    fn light_array() {
+        let mut array = LightArray::default();
+        array.set(1, 0, 0, 15);
+        array.set(0, 1, 0, 7);
+
+        assert_eq!(0xf0, array.0[0]);
+        assert_eq!(15, array.get(1, 0, 0));
+        assert_eq!(7, array.get(0, 1, 0));
+        assert_eq!(0, array.get(0, 0, 0));
+        assert_eq!(15, LightArray::filled(15).get(3, 4, 5));
+    }
+
+    #[test]
+    fn light_data() {
+        let mut lit = LightArray::default();
+        lit.set(0, 0, 0, 12);
+
+        let light = LightData::new(
+            &[Some(LightArray::default()), None, Some(lit.clone())],
+            &[None, Some(LightArray::filled(3))],
+        );
+        assert_eq!(BitSet::from_iter([2]), light.sky_light_mask);
+        assert_eq!(BitSet::from_iter([0]), light.empty_sky_light_mask);
+        assert_eq!(BitSet::from_iter([1]), light.block_light_mask);
+
+        let mut buffer = vec![0; light.slen()];
+        light.serialize(&mut buffer).unwrap();
+        let (_, parsed) = LightData::parse(&buffer).unwrap();
+        assert_eq!(light, parsed);
+
+        assert_eq!(Some(LightArray::default()), parsed.sky_light(0));
+        assert_eq!(None, parsed.sky_light(1));
+        assert_eq!(Some(lit), parsed.sky_light(2));
+        assert_eq!(Some(LightArray::filled(3)), parsed.block_light(1));
+    }
+
+    #[test]
+    fn heightmap() {
+        let mut heightmap = Heightmap::new();
+        heightmap.set(0, 0, 384);
+        heightmap.set(15, 15, 5);
+        heightmap.set(6, 0, 7);
+
+        // 9 bits per entry, so 7 entries per long
+        let packed = heightmap.pack(384);
+        assert_eq!(37, packed.len());
+        assert_eq!(384, packed[0] & 0x1ff);
+        assert_eq!(7, packed[0] >> 54);
+        assert_eq!(5, packed[36] >> 27);
+
+        assert_eq!(Ok(heightmap), Heightmap::unpack(384, &packed));
+        assert_eq!(
+            Err(ChunkError::InvalidData),
+            Heightmap::unpack(256, &Heightmap::new().pack(384)[..10])
+        );
+    }
+}
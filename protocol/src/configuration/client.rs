@@ -1,20 +1,57 @@
 use crate::{
     combined_packet, declare_packet,
-    general::{PString, VarInt},
+    general::{PString, TextComponent, VarInt},
+    plugin_message,
 };
 
 combined_packet!(
     Configuration,
+    CookieRequest,
     PluginMessage,
+    Disconnect,
     RegistryData,
+    RemoveResourcePack,
+    AddResourcePack,
     FeatureFlags,
     Finish,
+    KeepAlive,
     UpdateTags,
+    StoreCookie,
+    Transfer,
     KnownPacks
 );
 
-// declare_packet!(PluginMessage, 0x01, false, (channel, PString<'static>), ); // TODO
+declare_packet!(CookieRequest, 0x00, false, (key, PString<'static>));
+plugin_message!(PluginMessage, 0x01);
+declare_packet!(Disconnect, 0x02, false, (reason, TextComponent));
 declare_packet!(Finish, 0x03, false,);
+declare_packet!(KeepAlive, 0x04, false, (id, i64));
+// Removes the pack with the given UUID or all of them
+declare_packet!(RemoveResourcePack, 0x08, false, (uuid, Option<u128>));
+declare_packet!(
+    AddResourcePack,
+    0x09,
+    false,
+    (uuid, u128),
+    (url, PString<'static>),
+    (hash, PString<'static>),
+    (forced, bool),
+    (prompt, Option<TextComponent>)
+);
+declare_packet!(
+    StoreCookie,
+    0x0a,
+    false,
+    (key, PString<'static>),
+    (payload, Vec<u8>)
+);
+declare_packet!(
+    Transfer,
+    0x0b,
+    false,
+    (host, PString<'static>),
+    (port, VarInt)
+);
 declare_packet!(FeatureFlags, 0x0c, false, (flags, Vec<PString<'static>>));
 declare_packet!(
     KnownPacks,
@@ -26,60 +63,6 @@ declare_packet!(
     )
 );
 
-#[derive(Debug, PartialEq)]
-pub struct PluginMessage {
-    pub channel: PString<'static>,
-    pub data: Vec<u8>,
-}
-
-impl PluginMessage {
-    pub fn parse(id: VarInt, buf: &[u8]) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
-        use crate::serialize::SerializeItem;
-
-        if id.0 != 0x01 {
-            return Err(nom::Err::Error(crate::general::ParseError::WrongPacketId {
-                expected: 0x01,
-                received: id.0,
-            }));
-        }
-
-        let (buf, channel) = PString::parse(buf)?;
-
-        Ok((
-            &[],
-            Self {
-                channel,
-                data: buf.to_vec(),
-            },
-        ))
-    }
-}
-
-impl crate::packet::PacketContent for PluginMessage {
-    const ID: i32 = 0x01;
-    const PACKETTRAIL: bool = false;
-
-    fn length(&self) -> usize {
-        use crate::serialize::SerializeItem;
-
-        self.channel.slen() + self.data.len()
-    }
-
-    fn serialize<'b>(
-        &self,
-        buffer: &'b mut [u8],
-    ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
-        use crate::serialize::SerializeItem;
-
-        let buffer = self.channel.serialize(buffer)?;
-
-        let content_len = self.data.len();
-        (buffer[..content_len]).copy_from_slice(&self.data);
-
-        Ok(&mut buffer[content_len..])
-    }
-}
-
 #[derive(Debug, PartialEq)]
 pub struct RegistryEntry {
     pub id: PString<'static>,
@@ -94,9 +77,25 @@ declare_packet!(
     (entries, Vec<RegistryEntry>)
 );
 
+impl RegistryEntry {
+    fn serialized_data(&self) -> Option<Result<Vec<u8>, crate::serialize::SerializeError>> {
+        self.data.as_ref().map(|data| {
+            let mut buffer = Vec::new();
+            data.serialize(false, true, None, &mut buffer)
+                .map_err(|_| crate::serialize::SerializeError::Other("Serializing NBT"))?;
+            Ok(buffer)
+        })
+    }
+}
+
 impl crate::serialize::SerializeItem for RegistryEntry {
     fn slen(&self) -> usize {
-        self.id.slen() + self.data.as_ref().map(|t| todo!()).unwrap_or(1)
+        let data_len = match self.data.as_ref() {
+            Some(data) => data.serialized_len(None),
+            None => 0,
+        };
+
+        self.id.slen() + 1 + data_len
     }
 
     fn serialize<'b>(
@@ -104,10 +103,19 @@ impl crate::serialize::SerializeItem for RegistryEntry {
         buf: &'b mut [u8],
     ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
         let buf = self.id.serialize(buf)?;
-        match self.data.as_ref() {
-            None => 0x00u8.serialize(buf),
+        match self.serialized_data() {
+            None => false.serialize(buf),
             Some(data) => {
-                todo!()
+                let data = data?;
+
+                let buf = true.serialize(buf)?;
+                if buf.len() < data.len() {
+                    return Err(crate::serialize::SerializeError::NotEnoughSpace {
+                        missing: data.len() - buf.len(),
+                    });
+                }
+                (buf[..data.len()]).copy_from_slice(&data);
+                Ok(&mut buf[data.len()..])
             }
         }
     }
@@ -115,18 +123,13 @@ impl crate::serialize::SerializeItem for RegistryEntry {
     fn parse(i: &[u8]) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
         let (i, id) = PString::parse(i)?;
 
-        let (i, has_data) = nom::number::streaming::be_u8(i)?;
-        let data = match has_data {
-            0x00 => None,
-            0x01 => {
-                return Err(nom::Err::Error(crate::general::ParseError::NotImplemented(
-                    "Parsing Registry Data",
-                )));
-            }
-            other => {
-                dbg!(other);
-                return Err(nom::Err::Error(crate::general::ParseError::Other));
-            }
+        let (i, has_data) = bool::parse(i)?;
+        let (i, data) = if has_data {
+            let (i, (_, tag)) = nbt::Tag::parse(false, true)(i)
+                .map_err(|_| nom::Err::Error(crate::general::ParseError::Other))?;
+            (i, Some(tag))
+        } else {
+            (i, None)
         };
 
         Ok((i, Self { id, data }))
@@ -239,4 +242,97 @@ mod tests {
 
         // todo!()
     }
+
+    #[test]
+    fn registry_data_with_nbt() {
+        let expected = RegistryData {
+            id: PString("minecraft:dimension_type".into()),
+            entries: vec![
+                RegistryEntry {
+                    id: PString("minecraft:overworld".into()),
+                    data: Some(nbt::Tag::Compound(
+                        [
+                            ("height".to_string(), nbt::Tag::Int(384)),
+                            ("natural".to_string(), nbt::Tag::Byte(1)),
+                        ]
+                        .into_iter()
+                        .collect(),
+                    )),
+                },
+                RegistryEntry {
+                    id: PString("minecraft:the_end".into()),
+                    data: None,
+                },
+            ],
+        };
+
+        let buffer = Packet { inner: expected }.serialize();
+
+        let (rem, parsed) = Packet::parse(RegistryData::parse)(&buffer).unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(
+            Some(nbt::Tag::Int(384)),
+            match parsed.inner.entries[0].data.as_ref() {
+                Some(nbt::Tag::Compound(values)) => values.get("height").cloned(),
+                _ => None,
+            }
+        );
+        assert_eq!(None, parsed.inner.entries[1].data);
+    }
+
+    #[test]
+    fn update_tags() {
+        let expected = UpdateTags {
+            tags: vec![(
+                PString("minecraft:fluid".into()),
+                vec![(
+                    PString("minecraft:water".into()),
+                    vec![VarInt(1), VarInt(2)],
+                )],
+            )],
+        };
+
+        let buffer = Packet { inner: expected }.serialize();
+
+        let (rem, parsed) = Packet::parse(Configuration::parse)(&buffer).unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(
+            Configuration::UpdateTags(UpdateTags {
+                tags: vec![(
+                    PString("minecraft:fluid".into()),
+                    vec![(
+                        PString("minecraft:water".into()),
+                        vec![VarInt(1), VarInt(2)]
+                    )],
+                )],
+            }),
+            parsed.inner
+        );
+    }
+
+    #[test]
+    fn add_resource_pack() {
+        let expected = AddResourcePack {
+            uuid: 0x1234,
+            url: PString("https://example.com/pack.zip".into()),
+            hash: PString("0123456789abcdef0123456789abcdef01234567".into()),
+            forced: true,
+            prompt: Some(TextComponent::text("Please accept")),
+        };
+
+        let buffer = Packet { inner: expected }.serialize();
+
+        let (rem, parsed) = Packet::parse(Configuration::parse)(&buffer).unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(
+            Configuration::AddResourcePack(AddResourcePack {
+                uuid: 0x1234,
+                url: PString("https://example.com/pack.zip".into()),
+                hash: PString("0123456789abcdef0123456789abcdef01234567".into()),
+                forced: true,
+                prompt: Some(TextComponent::text("Please accept")),
+            }),
+            parsed.inner
+        );
+    }
 }
@@ -1,17 +1,28 @@
 use crate::{
     combined_packet, declare_packet,
-    general::{PString, VarInt},
+    general::{PString, ResourcePackResult, VarInt},
+    plugin_message,
     serialize::SerializeItem,
 };
 
 combined_packet!(
     ConfigurationMessage,
     ClientInformation,
+    CookieResponse,
     PluginMessage,
     AckFinish,
+    KeepAlive,
+    ResourcePackResponse,
     KnownPacks
 );
 
+declare_packet!(
+    ResourcePackResponse,
+    0x06,
+    false,
+    (uuid, u128),
+    (result, ResourcePackResult)
+);
 declare_packet!(
     KnownPacks,
     0x07,
@@ -97,43 +108,16 @@ impl crate::packet::PacketContent for ClientInformation {
     }
 }
 
-#[derive(Debug, PartialEq)]
-pub struct PluginMessage {
-    pub channel: PString<'static>,
-    pub data: Vec<u8>,
-}
-
-impl PluginMessage {
-    pub fn parse(id: VarInt, i: &[u8]) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
-        if id.0 != 0x02 {
-            return Err(nom::Err::Error(crate::general::ParseError::Other));
-        }
-
-        let (i, channel) = PString::parse(i)?;
-        let (i, length) = VarInt::parse(i)?;
-
-        let data = i[..length.0 as usize].to_vec();
-        let i = &i[length.0 as usize..];
-
-        Ok((i, Self { channel, data }))
-    }
-}
-
-impl crate::packet::PacketContent for PluginMessage {
-    const ID: i32 = 0x02;
-    const PACKETTRAIL: bool = false;
-
-    fn length(&self) -> usize {
-        self.channel.slen() + self.data.len()
-    }
+declare_packet!(
+    CookieResponse,
+    0x01,
+    false,
+    (key, PString<'static>),
+    (payload, Option<Vec<u8>>)
+);
 
-    fn serialize<'b>(
-        &self,
-        buffer: &'b mut [u8],
-    ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
-        todo!()
-    }
-}
+plugin_message!(PluginMessage, 0x02);
+declare_packet!(KeepAlive, 0x04, false, (id, i64));
 
 #[derive(Debug, PartialEq)]
 pub struct AckFinish {}
@@ -19,6 +19,9 @@ pub use slot::Slot;
 mod text;
 pub use text::TextComponent;
 
+mod resource_pack;
+pub use resource_pack::ResourcePackResult;
+
 #[derive(Debug, PartialEq)]
 pub enum ParseError {
     WrongPacketId { expected: i32, received: i32 },
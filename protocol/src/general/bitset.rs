@@ -1,4 +1,7 @@
-#[derive(Debug, PartialEq)]
+/// A set of bits, send as the longs containing them
+///
+/// Bit `i` is stored in the long `i / 64` at the position `i % 64`
+#[derive(Debug, PartialEq, Clone, Default)]
 pub struct BitSet {
     parts: Vec<i64>,
 }
@@ -27,4 +30,104 @@ impl BitSet {
     pub fn new() -> Self {
         Self { parts: Vec::new() }
     }
+
+    /// Sets or clears the bit at the index
+    pub fn set(&mut self, index: usize, value: bool) {
+        let (part, bit) = (index / 64, index % 64);
+
+        if value {
+            if self.parts.len() <= part {
+                self.parts.resize(part + 1, 0);
+            }
+            self.parts[part] |= 1 << bit;
+        } else if let Some(p) = self.parts.get_mut(part) {
+            *p &= !(1 << bit);
+
+            // Like the vanilla implementation, no trailing empty longs are send
+            while self.parts.last() == Some(&0) {
+                self.parts.pop();
+            }
+        }
+    }
+
+    /// Checks if the bit at the index is set
+    pub fn get(&self, index: usize) -> bool {
+        self.parts
+            .get(index / 64)
+            .map(|p| p & (1 << (index % 64)) != 0)
+            .unwrap_or(false)
+    }
+
+    /// The indices of all the set bits in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.parts.iter().enumerate().flat_map(|(idx, part)| {
+            (0..64)
+                .filter(move |bit| part & (1 << bit) != 0)
+                .map(move |bit| idx * 64 + bit)
+        })
+    }
+
+    /// The number of set bits
+    pub fn count(&self) -> usize {
+        self.parts.iter().map(|p| p.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parts.iter().all(|p| *p == 0)
+    }
+}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<T: IntoIterator<Item = usize>>(iter: T) -> Self {
+        let mut set = Self::new();
+        for index in iter {
+            set.set(index, true);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialize::SerializeItem;
+
+    #[test]
+    fn set_get() {
+        let mut set = BitSet::new();
+        set.set(0, true);
+        set.set(65, true);
+        set.set(63, true);
+
+        assert!(set.get(0));
+        assert!(!set.get(1));
+        assert!(set.get(63));
+        assert!(set.get(65));
+        assert!(!set.get(1000));
+        assert_eq!(vec![0, 63, 65], set.iter().collect::<Vec<_>>());
+        assert_eq!(3, set.count());
+
+        set.set(65, false);
+        assert_eq!(BitSet::from_iter([0, 63]), set);
+
+        set.set(0, false);
+        set.set(63, false);
+        assert!(set.is_empty());
+        assert_eq!(BitSet::new(), set);
+    }
+
+    #[test]
+    fn serialize() {
+        let set = BitSet::from_iter([1, 64]);
+
+        let mut buffer = vec![0; set.slen()];
+        let rem = set.serialize(&mut buffer).unwrap().len();
+        let written = buffer.len() - rem;
+        assert_eq!(
+            &[2, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 1],
+            &buffer[..written]
+        );
+
+        assert_eq!(Ok((&[] as &[u8], set)), BitSet::parse(&buffer[..written]));
+    }
 }
@@ -0,0 +1,65 @@
+use crate::{general::VarInt, serialize::SerializeItem};
+
+/// The result reported by the client in a Resource Pack Response
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ResourcePackResult {
+    SuccessfullyLoaded,
+    Declined,
+    FailedDownload,
+    Accepted,
+    Downloaded,
+    InvalidUrl,
+    FailedReload,
+    Discarded,
+}
+
+impl ResourcePackResult {
+    pub fn id(&self) -> i32 {
+        match self {
+            Self::SuccessfullyLoaded => 0,
+            Self::Declined => 1,
+            Self::FailedDownload => 2,
+            Self::Accepted => 3,
+            Self::Downloaded => 4,
+            Self::InvalidUrl => 5,
+            Self::FailedReload => 6,
+            Self::Discarded => 7,
+        }
+    }
+
+    /// Whether this is the last response the client sends for the pack
+    pub fn is_final(&self) -> bool {
+        !matches!(self, Self::Accepted | Self::Downloaded)
+    }
+}
+
+impl SerializeItem for ResourcePackResult {
+    fn slen(&self) -> usize {
+        VarInt(self.id()).slen()
+    }
+
+    fn serialize<'b>(
+        &self,
+        buf: &'b mut [u8],
+    ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
+        VarInt(self.id()).serialize(buf)
+    }
+
+    fn parse(i: &[u8]) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
+        let (i, raw) = VarInt::parse(i)?;
+
+        let result = match raw.0 {
+            0 => Self::SuccessfullyLoaded,
+            1 => Self::Declined,
+            2 => Self::FailedDownload,
+            3 => Self::Accepted,
+            4 => Self::Downloaded,
+            5 => Self::InvalidUrl,
+            6 => Self::FailedReload,
+            7 => Self::Discarded,
+            _ => return Err(nom::Err::Error(crate::general::ParseError::Other)),
+        };
+
+        Ok((i, result))
+    }
+}
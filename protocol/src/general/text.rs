@@ -1,24 +1,196 @@
-#[derive(Debug, PartialEq)]
+use std::collections::HashMap;
+
+/// A text component, send as NBT since 1.20.3
+///
+/// The root tag is either a plain String or a Compound, both without a name
+#[derive(Debug, PartialEq, Clone)]
 pub struct TextComponent {
     pub content: nbt::Tag,
 }
 
+impl TextComponent {
+    /// A component only consisting of the plain text
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            content: nbt::Tag::String_(text.into()),
+        }
+    }
+
+    /// A component translated by the client, with the arguments inserted into the translation
+    pub fn translate(key: impl Into<String>, args: Vec<TextComponent>) -> Self {
+        let mut content = HashMap::new();
+        content.insert("translate".to_string(), nbt::Tag::String_(key.into()));
+        if !args.is_empty() {
+            // All the elements of a NBT list need to have the same type
+            let args = args.into_iter().map(|a| a.into_compound()).collect();
+            content.insert("with".to_string(), nbt::Tag::List(args));
+        }
+
+        Self {
+            content: nbt::Tag::Compound(content),
+        }
+    }
+
+    /// Adds the style entries, like `color` or `italic`, to the component
+    pub fn styled(self, style: impl IntoIterator<Item = (String, nbt::Tag)>) -> Self {
+        let mut content = match self.into_compound() {
+            nbt::Tag::Compound(c) => c,
+            _ => unreachable!("Always converted to a compound"),
+        };
+        content.extend(style);
+
+        Self {
+            content: nbt::Tag::Compound(content),
+        }
+    }
+
+    /// The component as JSON, which is still used by the disconnect during the login
+    pub fn to_json(&self) -> String {
+        fn convert(tag: &nbt::Tag) -> serde_json::Value {
+            match tag {
+                nbt::Tag::End => serde_json::Value::Null,
+                // Text components only use bytes for their boolean style fields
+                nbt::Tag::Byte(b) => serde_json::Value::Bool(*b != 0),
+                nbt::Tag::Short(v) => (*v).into(),
+                nbt::Tag::Int(v) => (*v).into(),
+                nbt::Tag::Long(v) => (*v).into(),
+                nbt::Tag::Float(v) => (*v).into(),
+                nbt::Tag::Double(v) => (*v).into(),
+                nbt::Tag::String_(s) => s.as_str().into(),
+                nbt::Tag::ByteArray(v) => v.iter().copied().collect(),
+                nbt::Tag::IntArray(v) => v.iter().copied().collect(),
+                nbt::Tag::LongArray(v) => v.iter().copied().collect(),
+                nbt::Tag::List(items) => items.iter().map(convert).collect(),
+                nbt::Tag::Compound(entries) => serde_json::Value::Object(
+                    entries
+                        .iter()
+                        .map(|(k, v)| (k.clone(), convert(v)))
+                        .collect(),
+                ),
+            }
+        }
+
+        convert(&self.content).to_string()
+    }
+
+    fn into_compound(self) -> nbt::Tag {
+        match self.content {
+            nbt::Tag::Compound(c) => nbt::Tag::Compound(c),
+            other => nbt::Tag::Compound([("text".to_string(), other)].into_iter().collect()),
+        }
+    }
+
+    fn serialized(&self) -> Result<Vec<u8>, crate::serialize::SerializeError> {
+        let mut buffer = Vec::new();
+        self.content
+            .serialize(false, true, None, &mut buffer)
+            .map_err(|_| crate::serialize::SerializeError::Other("Serializing NBT"))?;
+        Ok(buffer)
+    }
+}
+
 impl crate::serialize::SerializeItem for TextComponent {
     fn slen(&self) -> usize {
-        todo!()
+        self.serialized().map(|d| d.len()).unwrap_or(0)
     }
 
     fn serialize<'b>(
         &self,
         buf: &'b mut [u8],
     ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
-        todo!()
+        let data = self.serialized()?;
+        if buf.len() < data.len() {
+            return Err(crate::serialize::SerializeError::NotEnoughSpace {
+                missing: data.len() - buf.len(),
+            });
+        }
+
+        (buf[..data.len()]).copy_from_slice(&data);
+        Ok(&mut buf[data.len()..])
     }
 
     fn parse(i: &[u8]) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
-        let (i, (_, tag)) = nbt::Tag::parse(false, true)(i)
-            .map_err(|e| nom::Err::Error(crate::general::ParseError::Other))?;
+        let (_, id) = nom::number::streaming::u8(i)?;
+
+        // Only the root compound is handled without a name by the nbt crate itself
+        let result = if id == 0x0a {
+            nbt::Tag::parse(false, true)(i).map(|(i, (_, tag))| (i, tag))
+        } else {
+            nbt::Tag::parse_payload(id)(&i[1..])
+        };
+        let (i, tag) = result.map_err(|_| nom::Err::Error(crate::general::ParseError::Other))?;
 
         Ok((i, Self { content: tag }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialize::SerializeItem;
+
+    #[test]
+    fn plain_text() {
+        let text = TextComponent::text("hi");
+
+        let mut buffer = vec![0; text.slen()];
+        let rem = text.serialize(&mut buffer).unwrap().len();
+        assert_eq!(0, rem);
+        assert_eq!(&[0x08, 0x00, 0x02, b'h', b'i'], buffer.as_slice());
+
+        assert_eq!(Ok((&[] as &[u8], text)), TextComponent::parse(&buffer));
+    }
+
+    #[test]
+    fn translate() {
+        let text = TextComponent::translate(
+            "chat.type.text",
+            vec![
+                TextComponent::text("sender"),
+                TextComponent::text("content").styled([("italic".to_string(), nbt::Tag::Byte(1))]),
+            ],
+        );
+
+        let nbt::Tag::Compound(content) = &text.content else {
+            panic!("Expected a compound")
+        };
+        let Some(nbt::Tag::List(args)) = content.get("with") else {
+            panic!("Expected the arguments")
+        };
+        assert!(args.iter().all(|a| matches!(a, nbt::Tag::Compound(_))));
+
+        let mut buffer = vec![0; text.slen()];
+        text.serialize(&mut buffer).unwrap();
+        assert_eq!(Ok((&[] as &[u8], text)), TextComponent::parse(&buffer));
+    }
+
+    #[test]
+    fn compound() {
+        let text = TextComponent {
+            content: nbt::Tag::Compound(
+                vec![("text".to_string(), nbt::Tag::String_("hi".into()))]
+                    .into_iter()
+                    .collect(),
+            ),
+        };
+
+        let mut buffer = vec![0; text.slen()];
+        text.serialize(&mut buffer).unwrap();
+        assert_eq!(Ok((&[] as &[u8], text)), TextComponent::parse(&buffer));
+    }
+
+    #[test]
+    fn json() {
+        assert_eq!(r#""hi""#, TextComponent::text("hi").to_json());
+
+        let text = TextComponent::translate(
+            "multiplayer.disconnect.incompatible",
+            vec![TextComponent::text("1.20.6")],
+        )
+        .styled([("bold".to_string(), nbt::Tag::Byte(1))]);
+        assert_eq!(
+            r#"{"bold":true,"translate":"multiplayer.disconnect.incompatible","with":[{"text":"1.20.6"}]}"#,
+            text.to_json()
+        );
+    }
+}
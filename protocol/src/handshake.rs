@@ -26,6 +26,9 @@ pub mod server {
         Status,
         /// Moves into the `Login` state, corresponding messages can be found at [`crate::login`]
         Login,
+        /// Moves into the `Login` state, but the client was transferred from another server using
+        /// a `Transfer` packet
+        Transfer,
     }
 
     impl Handshaking {
@@ -45,6 +48,7 @@ pub mod server {
             let next_state = match raw_next_state.0 {
                 1 => NextState::Status,
                 2 => NextState::Login,
+                3 => NextState::Transfer,
                 _ => return Err(nom::Err::Error(crate::general::ParseError::Other)),
             };
 
@@ -81,6 +85,7 @@ pub mod server {
             buffer = match &self.next_state {
                 NextState::Status => VarInt(1).serialize(buffer)?,
                 NextState::Login => VarInt(2).serialize(buffer)?,
+                NextState::Transfer => VarInt(3).serialize(buffer)?,
             };
             Ok(buffer)
         }
@@ -123,5 +128,14 @@ pub mod server {
                 hs
             );
         }
+
+        #[test]
+        fn handshake_transfer() {
+            let (rem, hs) =
+                Handshaking::parse(VarInt(0x00), &[0x06, 0x01, b'a', 0xff, 0x00, 0x03, 0xff])
+                    .unwrap();
+            assert_eq!(&[0xff], rem);
+            assert_eq!(NextState::Transfer, hs.next_state);
+        }
     }
 }
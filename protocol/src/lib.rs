@@ -1,6 +1,8 @@
 //! # Protocol
 //! Entirely based on [these docs](https://wiki.vg/index.php)
 
+pub mod chat;
+pub mod chunk;
 pub mod configuration;
 pub mod general;
 pub mod handshake;
@@ -8,6 +10,7 @@ pub mod login;
 pub mod metadata;
 pub mod packet;
 pub mod play;
+pub mod plugin;
 pub mod status;
 
 pub mod serialize;
@@ -90,3 +93,93 @@ macro_rules! combined_packet {
     }
 }
 pub(crate) use combined_packet;
+
+macro_rules! plugin_message {
+    ($name:ident, $pid:literal) => {
+        /// A Plugin Message, the data is the remainder of the packet and not length prefixed
+        ///
+        /// Use [`PluginChannel`](crate::plugin::PluginChannel) implementations to construct and
+        /// decode the data in a typed way
+        #[derive(Debug, PartialEq)]
+        pub struct $name {
+            pub channel: crate::general::PString<'static>,
+            pub data: Vec<u8>,
+        }
+
+        impl $name {
+            pub fn parse(
+                id: crate::general::VarInt,
+                i: &[u8],
+            ) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
+                if id.0 != $pid {
+                    return Err(nom::Err::Error(crate::general::ParseError::WrongPacketId {
+                        expected: $pid,
+                        received: id.0,
+                    }));
+                }
+
+                let (i, channel) =
+                    <crate::general::PString as crate::serialize::SerializeItem>::parse(i)?;
+
+                Ok((
+                    &[],
+                    Self {
+                        channel,
+                        data: i.to_vec(),
+                    },
+                ))
+            }
+
+            /// Creates the Plugin Message for the given channel payload
+            pub fn new<P>(payload: &P) -> Self
+            where
+                P: crate::plugin::PluginChannel,
+            {
+                Self {
+                    channel: crate::general::PString(P::CHANNEL.into()),
+                    data: payload.encode(),
+                }
+            }
+
+            /// Decodes the data as the given channel payload, returns `None` if the message was
+            /// send on a different channel
+            pub fn decode<P>(&self) -> Option<Result<P, crate::general::ParseError>>
+            where
+                P: crate::plugin::PluginChannel,
+            {
+                if self.channel.0 != P::CHANNEL {
+                    return None;
+                }
+
+                Some(P::decode(&self.data))
+            }
+        }
+
+        impl crate::packet::PacketContent for $name {
+            const ID: i32 = $pid;
+            const PACKETTRAIL: bool = false;
+
+            fn length(&self) -> usize {
+                crate::serialize::SerializeItem::slen(&self.channel) + self.data.len()
+            }
+
+            fn serialize<'b>(
+                &self,
+                buffer: &'b mut [u8],
+            ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
+                let buffer = crate::serialize::SerializeItem::serialize(&self.channel, buffer)?;
+
+                let content_len = self.data.len();
+                if buffer.len() < content_len {
+                    return Err(crate::serialize::SerializeError::NotEnoughSpace {
+                        missing: content_len - buffer.len(),
+                    });
+                }
+                (buffer[..content_len]).copy_from_slice(&self.data);
+
+                Ok(&mut buffer[content_len..])
+            }
+        }
+    };
+}
+pub(crate) use plugin_message;
@@ -4,3 +4,29 @@ pub mod client;
 pub mod server;
 
 pub mod encryption;
+
+/// The UUID of a player on a server in offline mode, which is the name based UUID of
+/// `OfflinePlayer:<name>` like Java's `UUID.nameUUIDFromBytes` creates it
+pub fn offline_uuid(name: &str) -> u128 {
+    use md5::Digest;
+
+    let mut hash: [u8; 16] = md5::Md5::digest(format!("OfflinePlayer:{}", name).as_bytes()).into();
+    // Version 3 and the IETF variant
+    hash[6] = (hash[6] & 0x0f) | 0x30;
+    hash[8] = (hash[8] & 0x3f) | 0x80;
+
+    u128::from_be_bytes(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offline_uuids() {
+        assert_eq!(
+            0xb50ad385_829d_3141_a216_7e7d7539ba7f,
+            offline_uuid("Notch")
+        );
+    }
+}
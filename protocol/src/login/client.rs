@@ -1,8 +1,92 @@
 use crate::{
+    combined_packet, declare_packet,
     general::{PString, VarInt},
     serialize::SerializeItem,
 };
 
+combined_packet!(
+    Login,
+    Disconnect,
+    EncryptionRequest,
+    LoginSuccess,
+    SetCompression,
+    LoginPluginRequest,
+    CookieRequest
+);
+
+// The reason is a JSON Text Component
+declare_packet!(Disconnect, 0x00, false, (reason, PString<'static>));
+declare_packet!(SetCompression, 0x03, false, (threshold, VarInt));
+declare_packet!(CookieRequest, 0x05, false, (key, PString<'static>));
+
+impl Disconnect {
+    pub fn new(reason: &crate::general::TextComponent) -> Self {
+        Self {
+            reason: PString(reason.to_json().into()),
+        }
+    }
+}
+
+/// Used to implement custom login steps, the client responds with a
+/// [`LoginPluginResponse`](super::server::LoginPluginResponse) with the same `message_id`
+#[derive(Debug, PartialEq)]
+pub struct LoginPluginRequest {
+    pub message_id: VarInt,
+    pub channel: PString<'static>,
+    /// The data is the remainder of the packet and not length prefixed
+    pub data: Vec<u8>,
+}
+
+impl LoginPluginRequest {
+    pub fn parse(id: VarInt, i: &[u8]) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
+        if id.0 != 0x04 {
+            return Err(nom::Err::Error(crate::general::ParseError::WrongPacketId {
+                expected: 0x04,
+                received: id.0,
+            }));
+        }
+
+        let (i, message_id) = VarInt::parse(i)?;
+        let (i, channel) = PString::parse(i)?;
+
+        Ok((
+            &[],
+            Self {
+                message_id,
+                channel,
+                data: i.to_vec(),
+            },
+        ))
+    }
+}
+
+impl crate::packet::PacketContent for LoginPluginRequest {
+    const ID: i32 = 0x04;
+    const PACKETTRAIL: bool = false;
+
+    fn length(&self) -> usize {
+        self.message_id.slen() + self.channel.slen() + self.data.len()
+    }
+
+    fn serialize<'b>(
+        &self,
+        mut buffer: &'b mut [u8],
+    ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
+        buffer = self.message_id.serialize(buffer)?;
+        buffer = self.channel.serialize(buffer)?;
+
+        let content_len = self.data.len();
+        if buffer.len() < content_len {
+            return Err(crate::serialize::SerializeError::NotEnoughSpace {
+                missing: content_len - buffer.len(),
+            });
+        }
+        (buffer[..content_len]).copy_from_slice(&self.data);
+
+        Ok(&mut buffer[content_len..])
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct EncryptionRequest {
     pub server_id: PString<'static>,
@@ -129,7 +213,18 @@ impl crate::serialize::SerializeItem for Property {
     }
 
     fn parse(i: &[u8]) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
-        todo!()
+        let (i, name) = PString::parse(i)?;
+        let (i, value) = PString::parse(i)?;
+        let (i, signature) = Option::<PString>::parse(i)?;
+
+        Ok((
+            i,
+            Self {
+                name,
+                value,
+                signature,
+            },
+        ))
     }
 }
 
@@ -141,16 +236,16 @@ impl LoginSuccess {
 
         let (i, uuid) = nom::number::streaming::be_u128(i)?;
         let (i, name) = PString::parse(i)?;
-        let (i, prop_count) = VarInt::parse(i)?;
-
-        let i = &i[1..];
+        let (i, properites) = Vec::<Property>::parse(i)?;
+        // The strict error handling flag, which is send as the packet trail
+        let (i, _) = bool::parse(i)?;
 
         Ok((
             i,
             Self {
                 uuid,
                 name,
-                properites: Vec::new(),
+                properites,
             },
         ))
     }
@@ -164,7 +259,40 @@ mod tests {
     use crate::packet::Packet;
 
     #[test]
-    #[ignore = "How do we deal with the trailing 1 at the end?"]
+    fn property_roundtrip() {
+        let property = Property {
+            name: PString("textures".into()),
+            value: PString("value".into()),
+            signature: Some(PString("signature".into())),
+        };
+
+        let mut buffer = vec![0; property.slen()];
+        let rem = property.serialize(&mut buffer).unwrap().len();
+        let written = buffer.len() - rem;
+
+        let (rem, parsed) = Property::parse(&buffer[..written]).unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(property, parsed);
+    }
+
+    #[test]
+    fn login_plugin_request() {
+        let packet = Packet {
+            inner: LoginPluginRequest {
+                message_id: VarInt(3),
+                channel: PString("velocity:player_info".into()),
+                data: vec![1],
+            },
+        };
+
+        let serialized = packet.serialize();
+        let (rem, parsed) = Packet::parse(Login::parse)(&serialized).unwrap();
+        assert!(rem.is_empty());
+
+        assert_eq!(Login::LoginPluginRequest(packet.inner), parsed.inner);
+    }
+
+    #[test]
     fn serialize_parse() {
         let msg = LoginSuccess {
             uuid: 123456789,
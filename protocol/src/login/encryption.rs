@@ -1,3 +1,37 @@
+/// The hash the client and the server send to the session server to identify the login, the
+/// SHA-1 of the session as a signed number in hex like Java's `new BigInteger(digest).toString(16)`
+///
+/// The public key is the DER encoded key of the encryption request.
+pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key: &[u8]) -> String {
+    use sha1::Digest;
+
+    let mut hasher = sha1::Sha1::new();
+    hasher.update(server_id.as_bytes());
+    hasher.update(shared_secret);
+    hasher.update(public_key);
+    let mut digest: [u8; 20] = hasher.finalize().into();
+
+    // Negative digests are printed as the magnitude of their two's complement
+    let negative = digest[0] & 0x80 != 0;
+    if negative {
+        let mut carry = true;
+        for byte in digest.iter_mut().rev() {
+            let (value, overflow) = (!*byte).overflowing_add(carry as u8);
+            *byte = value;
+            carry = overflow;
+        }
+    }
+
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    let hex = hex.trim_start_matches('0');
+
+    match (negative, hex.is_empty()) {
+        (_, true) => "0".to_string(),
+        (true, false) => format!("-{}", hex),
+        (false, false) => hex.to_string(),
+    }
+}
+
 /// https://gist.github.com/Lazersmoke/9947ada8acdd74a8b2e37d77cf1e0fdc
 pub fn asn1_encode_key<M, E>(modulo: M, exponent: E) -> Vec<u8>
 where
@@ -114,4 +148,26 @@ mod tests {
             result.as_slice()
         );
     }
+
+    #[test]
+    fn server_hashes() {
+        assert_eq!(
+            "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48",
+            server_hash("Notch", &[], &[])
+        );
+        assert_eq!(
+            "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1",
+            server_hash("jeb_", &[], &[])
+        );
+        assert_eq!(
+            "88e16a1019277b15d58faf0541e11910eb756f6",
+            server_hash("simon", &[], &[])
+        );
+
+        // The parts are hashed as one continuous input
+        assert_eq!(
+            server_hash("Notch", &[], &[]),
+            server_hash("No", b"tc", b"h")
+        );
+    }
 }
@@ -1,8 +1,98 @@
 use crate::{
+    combined_packet, declare_packet,
     general::{PString, VarInt},
     serialize::SerializeItem,
 };
 
+combined_packet!(
+    LoginMessage,
+    LoginStart,
+    EncryptionResponse,
+    LoginPluginResponse,
+    LoginAck,
+    CookieResponse
+);
+
+declare_packet!(
+    CookieResponse,
+    0x04,
+    false,
+    (key, PString<'static>),
+    (payload, Option<Vec<u8>>)
+);
+
+/// The response to a [`LoginPluginRequest`](super::client::LoginPluginRequest), `data` is `None`
+/// if the client did not understand the request
+#[derive(Debug, PartialEq)]
+pub struct LoginPluginResponse {
+    pub message_id: VarInt,
+    /// The data is the remainder of the packet and not length prefixed
+    pub data: Option<Vec<u8>>,
+}
+
+impl LoginPluginResponse {
+    pub fn parse(id: VarInt, i: &[u8]) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
+        if id.0 != 0x02 {
+            return Err(nom::Err::Error(crate::general::ParseError::WrongPacketId {
+                expected: 0x02,
+                received: id.0,
+            }));
+        }
+
+        let (i, message_id) = VarInt::parse(i)?;
+        let (i, successful) = bool::parse(i)?;
+
+        if successful {
+            Ok((
+                &[],
+                Self {
+                    message_id,
+                    data: Some(i.to_vec()),
+                },
+            ))
+        } else {
+            Ok((
+                i,
+                Self {
+                    message_id,
+                    data: None,
+                },
+            ))
+        }
+    }
+}
+
+impl crate::packet::PacketContent for LoginPluginResponse {
+    const ID: i32 = 0x02;
+    const PACKETTRAIL: bool = false;
+
+    fn length(&self) -> usize {
+        self.message_id.slen() + 1 + self.data.as_ref().map(|d| d.len()).unwrap_or(0)
+    }
+
+    fn serialize<'b>(
+        &self,
+        mut buffer: &'b mut [u8],
+    ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
+        buffer = self.message_id.serialize(buffer)?;
+        buffer = self.data.is_some().serialize(buffer)?;
+
+        let data = match self.data.as_ref() {
+            Some(d) => d,
+            None => return Ok(buffer),
+        };
+
+        if buffer.len() < data.len() {
+            return Err(crate::serialize::SerializeError::NotEnoughSpace {
+                missing: data.len() - buffer.len(),
+            });
+        }
+        (buffer[..data.len()]).copy_from_slice(data);
+
+        Ok(&mut buffer[data.len()..])
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct LoginStart {
     pub name: PString<'static>,
@@ -72,6 +162,24 @@ impl EncryptionResponse {
     }
 }
 
+impl crate::packet::PacketContent for EncryptionResponse {
+    const ID: i32 = 0x01;
+    const PACKETTRAIL: bool = false;
+
+    fn length(&self) -> usize {
+        self.shared_secret.slen() + self.verify_token.slen()
+    }
+
+    fn serialize<'b>(
+        &self,
+        mut buffer: &'b mut [u8],
+    ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
+        buffer = self.shared_secret.serialize(buffer)?;
+        buffer = self.verify_token.serialize(buffer)?;
+        Ok(buffer)
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct LoginAck {}
 
@@ -105,6 +213,41 @@ mod tests {
     use super::*;
     use crate::packet::Packet;
 
+    #[test]
+    fn login_plugin_response() {
+        for data in [None, Some(vec![1, 2, 3])] {
+            let packet = Packet {
+                inner: LoginPluginResponse {
+                    message_id: VarInt(1),
+                    data,
+                },
+            };
+
+            let serialized = packet.serialize();
+            let (rem, parsed) = Packet::parse(LoginMessage::parse)(&serialized).unwrap();
+            assert!(rem.is_empty());
+            assert_eq!(
+                LoginMessage::LoginPluginResponse(packet.inner),
+                parsed.inner
+            );
+        }
+    }
+
+    #[test]
+    fn cookie_response() {
+        let packet = Packet {
+            inner: CookieResponse {
+                key: PString("test:cookie".into()),
+                payload: Some(vec![0xde, 0xad]),
+            },
+        };
+
+        let serialized = packet.serialize();
+        let (rem, parsed) = Packet::parse(LoginMessage::parse)(&serialized).unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(LoginMessage::CookieResponse(packet.inner), parsed.inner);
+    }
+
     #[test]
     fn serialize_loginstart() {
         let packet = Packet {
@@ -128,6 +128,7 @@ impl<D> Packet<D> {
     }
 }
 
+#[derive(Debug, PartialEq)]
 pub struct RawPacket {
     pub id: VarInt,
     pub data: Vec<u8>,
@@ -213,6 +214,28 @@ impl RawPacket {
     }
 }
 
+impl<D> From<&Packet<D>> for RawPacket
+where
+    D: PacketContent,
+{
+    fn from(value: &Packet<D>) -> Self {
+        let mut data = vec![0; value.inner.length() + usize::from(D::PACKETTRAIL)];
+
+        let remaining = value.inner.serialize(&mut data).unwrap().len();
+        let mut written = data.len() - remaining;
+        if D::PACKETTRAIL {
+            data[written] = 0x01;
+            written += 1;
+        }
+        data.truncate(written);
+
+        Self {
+            id: VarInt(D::ID),
+            data,
+        }
+    }
+}
+
 impl<D> LegacyPacket<D> {
     pub fn parse<'i, F>(
         mut parser: F,
@@ -292,4 +315,14 @@ mod tests {
         assert_eq!(&[0xff], rem);
         assert_eq!(Packet { inner: () }, pack);
     }
+
+    #[test]
+    fn packet_to_raw() {
+        let packet = Packet {
+            inner: crate::status::server::PingRequest { payload: 0x0102 },
+        };
+
+        let raw = RawPacket::from(&packet);
+        assert_eq!(packet.serialize(), raw.serialize());
+    }
 }
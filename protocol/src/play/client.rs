@@ -1,6 +1,10 @@
+pub use crate::chat::ChatSession;
 use crate::{
+    chat::{FilterMask, MessageSignature, PackedSignature},
+    chunk::{Heightmap, HeightmapKind},
     combined_packet, declare_packet,
-    general::{BitSet, PString, Position, TextComponent, VarInt, VarLong},
+    general::{PString, Position, TextComponent, VarInt, VarLong},
+    plugin_message,
     serialize::SerializeItem,
 };
 
@@ -16,7 +20,11 @@ combined_packet!(
     Commands,
     SetContainerContent,
     SetContainerSlot,
+    CookieRequest,
+    PluginMessage,
     DamageEvent,
+    Disconnect,
+    DisguisedChatMessage,
     EntityEvent,
     UnloadChunk,
     GameEvent,
@@ -32,10 +40,14 @@ combined_packet!(
     UpdateEntityPositionAndRotation,
     UpdateEntityRotation,
     PlayerAbilities,
+    PlayerChatMessage,
+    PlayerInfoRemove,
     PlayerInfoUpdate,
     SynchronizePlayerPosition,
     UpdateRecipeBook,
     RemoveEntities,
+    RemoveResourcePack,
+    AddResourcePack,
     SetHeadRotation,
     UpdateSectionBlocks,
     ServerData,
@@ -49,10 +61,13 @@ combined_packet!(
     SetHealth,
     UpdateTime,
     SoundEffect,
+    SystemChatMessage,
     TeleportEntity,
     PickupItem,
     SetTickingState,
     StepTick,
+    StoreCookie,
+    Transfer,
     UpdateAdvancements,
     UpdateAttributes,
     UpdateRecipes
@@ -119,6 +134,8 @@ declare_packet!(
     (slot, i16),
     (slot_data, crate::general::Slot)
 );
+declare_packet!(CookieRequest, 0x16, false, (key, PString<'static>));
+plugin_message!(PluginMessage, 0x19);
 declare_packet!(
     DamageEvent,
     0x1a,
@@ -129,6 +146,17 @@ declare_packet!(
     (source_direct_id, VarInt),
     (source_position, Option<(f64, f64, f64)>)
 );
+declare_packet!(Disconnect, 0x1d, false, (reason, TextComponent));
+// A chat message without a signature, formatted by the client using the chat type
+declare_packet!(
+    DisguisedChatMessage,
+    0x1e,
+    false,
+    (message, TextComponent),
+    (chat_type, VarInt),
+    (sender_name, TextComponent),
+    (target_name, Option<TextComponent>)
+);
 declare_packet!(
     EntityEvent,
     0x1f,
@@ -138,6 +166,25 @@ declare_packet!(
 );
 declare_packet!(UnloadChunk, 0x21, false, (chunk_z, i32), (chunk_x, i32));
 declare_packet!(GameEvent, 0x22, false, (event, u8), (value, f32));
+
+impl GameEvent {
+    pub const NO_RESPAWN_BLOCK: u8 = 0;
+    pub const BEGIN_RAINING: u8 = 1;
+    pub const END_RAINING: u8 = 2;
+    pub const CHANGE_GAME_MODE: u8 = 3;
+    pub const WIN_GAME: u8 = 4;
+    pub const DEMO_EVENT: u8 = 5;
+    pub const ARROW_HIT_PLAYER: u8 = 6;
+    pub const RAIN_LEVEL_CHANGE: u8 = 7;
+    pub const THUNDER_LEVEL_CHANGE: u8 = 8;
+    pub const PUFFERFISH_STING: u8 = 9;
+    pub const ELDER_GUARDIAN_APPEARANCE: u8 = 10;
+    pub const ENABLE_RESPAWN_SCREEN: u8 = 11;
+    pub const LIMITED_CRAFTING: u8 = 12;
+    /// Sent after the login, the client waits for the chunk around it before closing the
+    /// loading screen
+    pub const START_WAITING_FOR_CHUNKS: u8 = 13;
+}
 declare_packet!(HurtAnimation, 0x24, false, (entity_id, VarInt), (yaw, f32));
 declare_packet!(
     InitializeWorldBorder,
@@ -152,7 +199,8 @@ declare_packet!(
     (warning_blocks, VarInt),
     (warning_time, VarInt)
 );
-declare_packet!(KeepAlive, 0x26, false,); // TODO
+// The client responds with the same id, the time until then is the latency of the player
+declare_packet!(KeepAlive, 0x26, false, (id, i64));
 declare_packet!(WorldEvent, 0x28, false,); // TODO
 declare_packet!(Particle, 0x29, false,); // TODO
 declare_packet!(
@@ -194,8 +242,28 @@ declare_packet!(
     (flying_speed, f32),
     (fov_modifier, f32)
 );
-// declare_packet!(PlayerInfoUpdate, 0x3e, false, (actions, i8)); // TODO
+// A message signed by the sending player, the index counts the messages of the sender
+declare_packet!(
+    PlayerChatMessage,
+    0x39,
+    false,
+    (sender, u128),
+    (index, VarInt),
+    (signature, Option<MessageSignature>),
+    (message, PString<'static>),
+    (timestamp, i64),
+    (salt, i64),
+    (previous_messages, Vec<PackedSignature>),
+    (unsigned_content, Option<TextComponent>),
+    (filter, FilterMask),
+    (chat_type, VarInt),
+    (sender_name, TextComponent),
+    (target_name, Option<TextComponent>)
+);
+declare_packet!(PlayerInfoRemove, 0x3d, false, (players, Vec<u128>));
 
+/// Updates the entries of players in the tab list, the `actions` bitmask defines which
+/// [`PlayerAction`]s are included for every player
 #[derive(Debug, PartialEq)]
 pub struct PlayerInfoUpdate {
     pub actions: i8,
@@ -203,6 +271,17 @@ pub struct PlayerInfoUpdate {
 }
 
 impl PlayerInfoUpdate {
+    /// Creates the packet and computes the bitmask, every player needs to have the same actions
+    pub fn new(mut players: Vec<(u128, Vec<PlayerAction>)>) -> Self {
+        let mut actions = 0;
+        for (_, player_actions) in players.iter_mut() {
+            player_actions.sort_by_key(|a| a.mask() as u8);
+            actions |= player_actions.iter().fold(0, |acc, a| acc | a.mask());
+        }
+
+        Self { actions, players }
+    }
+
     pub fn parse(id: VarInt, i: &[u8]) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
         if id.0 != <Self as crate::packet::PacketContent>::ID {
             return Err(nom::Err::Error(crate::general::ParseError::WrongPacketId {
@@ -250,65 +329,159 @@ impl crate::packet::PacketContent for PlayerInfoUpdate {
     const PACKETTRAIL: bool = false;
 
     fn length(&self) -> usize {
-        todo!()
+        self.actions.slen()
+            + VarInt(self.players.len() as i32).slen()
+            + self
+                .players
+                .iter()
+                .map(|(uuid, actions)| {
+                    uuid.slen() + actions.iter().map(|a| a.slen()).sum::<usize>()
+                })
+                .sum::<usize>()
     }
 
     fn serialize<'b>(
         &self,
-        buffer: &'b mut [u8],
+        mut buffer: &'b mut [u8],
     ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
-        todo!()
+        buffer = self.actions.serialize(buffer)?;
+        buffer = VarInt(self.players.len() as i32).serialize(buffer)?;
+
+        for (uuid, actions) in self.players.iter() {
+            // The client reads the actions in the order of their bits, so they need to match
+            // the bitmask exactly
+            let mut remaining = actions.iter();
+            let mut expected = (0..8)
+                .map(|idx| (0x01 << idx) as i8)
+                .filter(|mask| self.actions & mask != 0);
+            let in_order = loop {
+                match (remaining.next(), expected.next()) {
+                    (Some(action), Some(mask)) if action.mask() == mask => continue,
+                    (None, None) => break true,
+                    _ => break false,
+                }
+            };
+            if !in_order {
+                return Err(crate::serialize::SerializeError::Other(
+                    "Player Actions dont match the bitmask",
+                ));
+            }
+
+            buffer = uuid.serialize(buffer)?;
+            for action in actions.iter() {
+                buffer = action.serialize(buffer)?;
+            }
+        }
+
+        Ok(buffer)
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum PlayerAction {
-    AddPlayer {},
-    InitializeChat {},
-    UpdateGamemode {},
-    UpdateListed {},
-    UpdateLatency {},
-    UpdateDisplayName {},
+    AddPlayer {
+        name: PString<'static>,
+        properties: Vec<crate::login::client::Property>,
+    },
+    InitializeChat {
+        session: Option<ChatSession>,
+    },
+    UpdateGamemode {
+        gamemode: VarInt,
+    },
+    UpdateListed {
+        listed: bool,
+    },
+    UpdateLatency {
+        ping: VarInt,
+    },
+    UpdateDisplayName {
+        display_name: Option<TextComponent>,
+    },
 }
 
 impl PlayerAction {
+    pub const ADD_PLAYER: i8 = 0x01;
+    pub const INITIALIZE_CHAT: i8 = 0x02;
+    pub const UPDATE_GAMEMODE: i8 = 0x04;
+    pub const UPDATE_LISTED: i8 = 0x08;
+    pub const UPDATE_LATENCY: i8 = 0x10;
+    pub const UPDATE_DISPLAY_NAME: i8 = 0x20;
+
+    /// The bit of the action in the bitmask of the [`PlayerInfoUpdate`]
+    pub fn mask(&self) -> i8 {
+        match self {
+            Self::AddPlayer { .. } => Self::ADD_PLAYER,
+            Self::InitializeChat { .. } => Self::INITIALIZE_CHAT,
+            Self::UpdateGamemode { .. } => Self::UPDATE_GAMEMODE,
+            Self::UpdateListed { .. } => Self::UPDATE_LISTED,
+            Self::UpdateLatency { .. } => Self::UPDATE_LATENCY,
+            Self::UpdateDisplayName { .. } => Self::UPDATE_DISPLAY_NAME,
+        }
+    }
+
     pub fn parse(mask: i8, i: &[u8]) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
         match mask {
-            0x01 => {
+            Self::ADD_PLAYER => {
                 let (i, name) = PString::<'static>::parse(i)?;
-                let (i, properties) = Vec::<(PString, PString, Option<PString>)>::parse(i)?;
+                let (i, properties) = Vec::<crate::login::client::Property>::parse(i)?;
 
-                Ok((i, Self::AddPlayer {}))
+                Ok((i, Self::AddPlayer { name, properties }))
             }
-            0x02 => {
-                let (i, data) = Option::<(u128, i64, Vec<i8>, Vec<i8>)>::parse(i)?;
+            Self::INITIALIZE_CHAT => {
+                let (i, session) = Option::<ChatSession>::parse(i)?;
 
-                Ok((i, Self::InitializeChat {}))
+                Ok((i, Self::InitializeChat { session }))
             }
-            0x04 => {
+            Self::UPDATE_GAMEMODE => {
                 let (i, gamemode) = VarInt::parse(i)?;
 
-                Ok((i, Self::UpdateGamemode {}))
+                Ok((i, Self::UpdateGamemode { gamemode }))
             }
-            0x08 => {
+            Self::UPDATE_LISTED => {
                 let (i, listed) = bool::parse(i)?;
 
-                Ok((i, Self::UpdateListed {}))
+                Ok((i, Self::UpdateListed { listed }))
             }
-            0x10 => {
+            Self::UPDATE_LATENCY => {
                 let (i, ping) = VarInt::parse(i)?;
 
-                Ok((i, Self::UpdateLatency {}))
+                Ok((i, Self::UpdateLatency { ping }))
             }
-            0x20 => {
-                let (i, display_name) = Option::<PString<'static>>::parse(i)?;
+            Self::UPDATE_DISPLAY_NAME => {
+                let (i, display_name) = Option::<TextComponent>::parse(i)?;
 
-                Ok((i, Self::UpdateDisplayName {}))
+                Ok((i, Self::UpdateDisplayName { display_name }))
             }
-            other => {
-                dbg!(other);
-                return Err(nom::Err::Error(crate::general::ParseError::Other));
+            _ => Err(nom::Err::Error(crate::general::ParseError::Other)),
+        }
+    }
+
+    fn slen(&self) -> usize {
+        match self {
+            Self::AddPlayer { name, properties } => name.slen() + properties.slen(),
+            Self::InitializeChat { session } => session.slen(),
+            Self::UpdateGamemode { gamemode } => gamemode.slen(),
+            Self::UpdateListed { listed } => listed.slen(),
+            Self::UpdateLatency { ping } => ping.slen(),
+            Self::UpdateDisplayName { display_name } => display_name.slen(),
+        }
+    }
+
+    fn serialize<'b>(
+        &self,
+        buf: &'b mut [u8],
+    ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
+        match self {
+            Self::AddPlayer { name, properties } => {
+                let buf = name.serialize(buf)?;
+                properties.serialize(buf)
             }
+            Self::InitializeChat { session } => session.serialize(buf),
+            Self::UpdateGamemode { gamemode } => gamemode.serialize(buf),
+            Self::UpdateListed { listed } => listed.serialize(buf),
+            Self::UpdateLatency { ping } => ping.serialize(buf),
+            Self::UpdateDisplayName { display_name } => display_name.serialize(buf),
         }
     }
 }
@@ -341,6 +514,18 @@ declare_packet!(
     (recipe_ids, Vec<PString<'static>>) // TODO
 );
 declare_packet!(RemoveEntities, 0x42, false, (entity_ids, Vec<VarInt>));
+// Removes the pack with the given UUID or all of them
+declare_packet!(RemoveResourcePack, 0x45, false, (uuid, Option<u128>));
+declare_packet!(
+    AddResourcePack,
+    0x46,
+    false,
+    (uuid, u128),
+    (url, PString<'static>),
+    (hash, PString<'static>),
+    (forced, bool),
+    (prompt, Option<TextComponent>)
+);
 declare_packet!(
     SetHeadRotation,
     0x48,
@@ -470,7 +655,7 @@ declare_packet!(
     (reduced_debug_info, bool),
     (enable_respawn_rule, bool),
     (do_limited_crafting, bool),
-    (dimension_type, PString<'static>),
+    (dimension_type, VarInt),
     (dimension_name, PString<'static>),
     (hashed_seed, i64),
     (game_mode, u8),
@@ -587,16 +772,43 @@ pub struct BlockEntity {
     pub data: nbt::Tag,
 }
 
+/// Serializes the NBT as send over the network, without the name of the root tag
+fn serialize_nbt<'b>(
+    tag: &nbt::Tag,
+    buf: &'b mut [u8],
+) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
+    let mut data = Vec::new();
+    tag.serialize(false, true, None, &mut data)
+        .map_err(|_| crate::serialize::SerializeError::Other("Serializing NBT"))?;
+
+    if buf.len() < data.len() {
+        return Err(crate::serialize::SerializeError::NotEnoughSpace {
+            missing: data.len() - buf.len(),
+        });
+    }
+    (buf[..data.len()]).copy_from_slice(&data);
+    Ok(&mut buf[data.len()..])
+}
+
+fn nbt_len(tag: &nbt::Tag) -> usize {
+    let mut data = Vec::new();
+    let _ = tag.serialize(false, true, None, &mut data);
+    data.len()
+}
+
 impl SerializeItem for BlockEntity {
     fn slen(&self) -> usize {
-        todo!()
+        1 + self.y.slen() + self.ty.slen() + nbt_len(&self.data)
     }
 
     fn serialize<'b>(
         &self,
-        buf: &'b mut [u8],
+        mut buf: &'b mut [u8],
     ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
-        todo!()
+        buf = ((self.x << 4) | (self.z & 0x0f)).serialize(buf)?;
+        buf = self.y.serialize(buf)?;
+        buf = self.ty.serialize(buf)?;
+        serialize_nbt(&self.data, buf)
     }
 
     fn parse(i: &[u8]) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
@@ -620,25 +832,78 @@ impl SerializeItem for BlockEntity {
     }
 }
 
-#[derive(Debug, PartialEq)]
-pub struct HeightMap(pub nbt::Tag);
+/// The packed heightmaps of a chunk, send as a NBT compound containing a long array for
+/// every [`HeightmapKind`]
+#[derive(Debug, PartialEq, Default)]
+pub struct HeightMap {
+    pub maps: Vec<(HeightmapKind, Vec<i64>)>,
+}
+
+impl HeightMap {
+    /// Packs the heightmaps for a world with the given height
+    pub fn new(world_height: u16, maps: &[(HeightmapKind, &Heightmap)]) -> Self {
+        Self {
+            maps: maps
+                .iter()
+                .map(|(kind, map)| (*kind, map.pack(world_height)))
+                .collect(),
+        }
+    }
+
+    /// Unpacks the heightmap of the given kind, if it was send
+    pub fn get(
+        &self,
+        kind: HeightmapKind,
+        world_height: u16,
+    ) -> Option<Result<Heightmap, crate::chunk::ChunkError>> {
+        self.maps
+            .iter()
+            .find(|(k, _)| *k == kind)
+            .map(|(_, data)| Heightmap::unpack(world_height, data))
+    }
+
+    fn tag(&self) -> nbt::Tag {
+        nbt::Tag::Compound(
+            self.maps
+                .iter()
+                .map(|(kind, data)| (kind.name().to_string(), nbt::Tag::LongArray(data.clone())))
+                .collect(),
+        )
+    }
+}
 
 impl SerializeItem for HeightMap {
     fn slen(&self) -> usize {
-        todo!()
+        nbt_len(&self.tag())
     }
 
     fn serialize<'b>(
         &self,
         buf: &'b mut [u8],
     ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
-        todo!()
+        serialize_nbt(&self.tag(), buf)
     }
 
     fn parse(i: &[u8]) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
         let (i, (_, value)) = nbt::Tag::parse(false, true)(i)
-            .map_err(|e| nom::Err::Error(crate::general::ParseError::Other))?;
-        Ok((i, Self(value)))
+            .map_err(|_| nom::Err::Error(crate::general::ParseError::Other))?;
+
+        let entries = match value {
+            nbt::Tag::Compound(entries) => entries,
+            _ => return Err(nom::Err::Error(crate::general::ParseError::Other)),
+        };
+
+        // Unknown heightmaps are not used by the client, so they are skipped
+        let mut maps: Vec<(HeightmapKind, Vec<i64>)> = entries
+            .into_iter()
+            .filter_map(|(name, tag)| match (HeightmapKind::from_name(&name), tag) {
+                (Some(kind), nbt::Tag::LongArray(data)) => Some((kind, data)),
+                _ => None,
+            })
+            .collect();
+        maps.sort_by_key(|(kind, _)| *kind);
+
+        Ok((i, Self { maps }))
     }
 }
 
@@ -649,14 +914,9 @@ declare_packet!(
     (chunk_x, i32),
     (chunk_z, i32),
     (height_maps, HeightMap),
-    (data, Vec<i8>),
+    (data, crate::chunk::ChunkData),
     (block_entities, Vec<BlockEntity>),
-    (sky_light_mask, BitSet),
-    (block_light_mask, BitSet),
-    (empty_sky_light_mask, BitSet),
-    (empty_block_light_mask, BitSet),
-    (sky_light_arrays, Vec<Vec<i8>>),
-    (block_light_arrays, Vec<Vec<i8>>)
+    (light, crate::chunk::LightData)
 );
 
 declare_packet!(
@@ -665,12 +925,7 @@ declare_packet!(
     false,
     (chunk_x, VarInt),
     (chunk_z, VarInt),
-    (sky_light_mask, BitSet),
-    (block_light_mask, BitSet),
-    (empty_sky_light_mask, BitSet),
-    (empty_block_light_mask, BitSet),
-    (sky_light_arrays, Vec<Vec<i8>>),
-    (block_light_arrays, Vec<Vec<i8>>)
+    (light, crate::chunk::LightData)
 );
 
 #[derive(Debug, PartialEq)]
@@ -782,6 +1037,27 @@ declare_packet!(
     (is_frozen, bool)
 );
 declare_packet!(StepTick, 0x72, false, (steps, VarInt));
+declare_packet!(
+    SystemChatMessage,
+    0x6c,
+    false,
+    (content, TextComponent),
+    (overlay, bool)
+);
+declare_packet!(
+    StoreCookie,
+    0x6b,
+    false,
+    (key, PString<'static>),
+    (payload, Vec<u8>)
+);
+declare_packet!(
+    Transfer,
+    0x73,
+    false,
+    (host, PString<'static>),
+    (port, VarInt)
+);
 declare_packet!(UpdateAdvancements, 0x74, false, (reset_clear, bool)); // TODO
 declare_packet!(
     UpdateAttributes,
@@ -792,7 +1068,10 @@ declare_packet!(
 );
 declare_packet!(UpdateRecipes, 0x77, false,); // TODO
 
-#[derive(Debug, PartialEq)]
+/// A single Node in the Command Graph send using the [`Commands`] Packet
+///
+/// [Docs](https://wiki.vg/Command_Data)
+#[derive(Debug, PartialEq, Clone)]
 pub struct CommandNode {
     pub flags: i8,
     pub children: Vec<VarInt>,
@@ -802,6 +1081,24 @@ pub struct CommandNode {
     pub suggestions_type: Option<PString<'static>>,
 }
 
+impl CommandNode {
+    /// Mask for the Node-Type in the flags (0 = Root, 1 = Literal, 2 = Argument)
+    pub const TYPE_MASK: i8 = 0x03;
+    pub const TYPE_ROOT: i8 = 0x00;
+    pub const TYPE_LITERAL: i8 = 0x01;
+    pub const TYPE_ARGUMENT: i8 = 0x02;
+    /// Set if the Command is valid when ending at this Node
+    pub const EXECUTABLE: i8 = 0x04;
+    /// Set if the Node has a redirect
+    pub const HAS_REDIRECT: i8 = 0x08;
+    /// Set if the Node has a suggestions type
+    pub const HAS_SUGGESTIONS_TYPE: i8 = 0x10;
+
+    pub fn is_executable(&self) -> bool {
+        self.flags & Self::EXECUTABLE != 0
+    }
+}
+
 impl SerializeItem for CommandNode {
     fn slen(&self) -> usize {
         self.flags.slen()
@@ -821,7 +1118,7 @@ impl SerializeItem for CommandNode {
 
         let (mut i, children) = Vec::<VarInt>::parse(i)?;
 
-        let redirect = if flags & 0x08 > 0 {
+        let redirect = if flags & Self::HAS_REDIRECT > 0 {
             let (tmp_i, index) = VarInt::parse(i)?;
             i = tmp_i;
             Some(index)
@@ -829,7 +1126,8 @@ impl SerializeItem for CommandNode {
             None
         };
 
-        let name = if flags & 0x03 == 1 || flags & 0x03 == 2 {
+        let node_type = flags & Self::TYPE_MASK;
+        let name = if node_type == Self::TYPE_LITERAL || node_type == Self::TYPE_ARGUMENT {
             let (tmp_i, name) = PString::parse(i)?;
             i = tmp_i;
             Some(name)
@@ -837,7 +1135,7 @@ impl SerializeItem for CommandNode {
             None
         };
 
-        let parser = if flags & 0x03 == 2 {
+        let parser = if node_type == Self::TYPE_ARGUMENT {
             let (tmp_i, parser) = CommandParser::parse(i)?;
             i = tmp_i;
             Some(parser)
@@ -845,7 +1143,7 @@ impl SerializeItem for CommandNode {
             None
         };
 
-        let suggestions_type = if flags & 0x10 > 0 {
+        let suggestions_type = if flags & Self::HAS_SUGGESTIONS_TYPE > 0 {
             let (tmp_i, ident) = PString::parse(i)?;
             i = tmp_i;
             Some(ident)
@@ -866,53 +1164,429 @@ impl SerializeItem for CommandNode {
         ))
     }
 
+    fn serialize<'b>(
+        &self,
+        mut buf: &'b mut [u8],
+    ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
+        buf = self.flags.serialize(buf)?;
+        buf = self.children.serialize(buf)?;
+
+        if let Some(redirect) = self.redirect.as_ref() {
+            buf = redirect.serialize(buf)?;
+        }
+        if let Some(name) = self.name.as_ref() {
+            buf = name.serialize(buf)?;
+        }
+        if let Some(parser) = self.parser.as_ref() {
+            buf = parser.serialize(buf)?;
+        }
+        if let Some(suggestions) = self.suggestions_type.as_ref() {
+            buf = suggestions.serialize(buf)?;
+        }
+
+        Ok(buf)
+    }
+}
+
+/// The optional min and max values of the numeric brigadier parsers
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct NumberRange<T> {
+    pub min: Option<T>,
+    pub max: Option<T>,
+}
+
+impl<T> NumberRange<T> {
+    pub fn new(min: Option<T>, max: Option<T>) -> Self {
+        Self { min, max }
+    }
+
+    fn flags(&self) -> i8 {
+        let mut flags = 0;
+        if self.min.is_some() {
+            flags |= 0x01;
+        }
+        if self.max.is_some() {
+            flags |= 0x02;
+        }
+        flags
+    }
+}
+
+impl<T> SerializeItem for NumberRange<T>
+where
+    T: SerializeItem,
+{
+    fn slen(&self) -> usize {
+        1 + self.min.as_ref().map(|v| v.slen()).unwrap_or(0)
+            + self.max.as_ref().map(|v| v.slen()).unwrap_or(0)
+    }
+
+    fn serialize<'b>(
+        &self,
+        mut buf: &'b mut [u8],
+    ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
+        buf = self.flags().serialize(buf)?;
+        if let Some(min) = self.min.as_ref() {
+            buf = min.serialize(buf)?;
+        }
+        if let Some(max) = self.max.as_ref() {
+            buf = max.serialize(buf)?;
+        }
+        Ok(buf)
+    }
+
+    fn parse(i: &[u8]) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
+        let (i, flags) = i8::parse(i)?;
+        let (i, min) = nom::combinator::cond(flags & 0x01 != 0, T::parse)(i)?;
+        let (i, max) = nom::combinator::cond(flags & 0x02 != 0, T::parse)(i)?;
+
+        Ok((i, Self { min, max }))
+    }
+}
+
+/// The different Modes for the `brigadier:string` parser
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum StringMode {
+    /// Reads a single word
+    SingleWord,
+    /// Reads a single word or a quoted phrase
+    QuotablePhrase,
+    /// Reads the rest of the input
+    GreedyPhrase,
+}
+
+impl SerializeItem for StringMode {
+    fn slen(&self) -> usize {
+        1
+    }
+
     fn serialize<'b>(
         &self,
         buf: &'b mut [u8],
     ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
-        todo!()
+        let raw = match self {
+            Self::SingleWord => 0,
+            Self::QuotablePhrase => 1,
+            Self::GreedyPhrase => 2,
+        };
+        VarInt(raw).serialize(buf)
+    }
+
+    fn parse(i: &[u8]) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
+        let (i, raw) = VarInt::parse(i)?;
+        match raw.0 {
+            0 => Ok((i, Self::SingleWord)),
+            1 => Ok((i, Self::QuotablePhrase)),
+            2 => Ok((i, Self::GreedyPhrase)),
+            _ => Err(nom::Err::Error(crate::general::ParseError::Other)),
+        }
     }
 }
 
-#[derive(Debug, PartialEq)]
+/// The Parser used by an Argument Node, the order of the variants matches the numeric IDs used
+/// in the protocol
+///
+/// [Docs](https://wiki.vg/Command_Data#Parsers)
+#[derive(Debug, PartialEq, Clone)]
 pub enum CommandParser {
-    BrigadierString(VarInt),
-    Entity(i8),
+    BrigadierBool,
+    BrigadierFloat(NumberRange<f32>),
+    BrigadierDouble(NumberRange<f64>),
+    BrigadierInteger(NumberRange<i32>),
+    BrigadierLong(NumberRange<i64>),
+    BrigadierString(StringMode),
+    Entity { single: bool, only_players: bool },
+    GameProfile,
+    BlockPos,
+    ColumnPos,
+    Vec3,
+    Vec2,
+    BlockState,
+    BlockPredicate,
+    ItemStack,
+    ItemPredicate,
+    Color,
+    Component,
+    Style,
     Message,
+    Nbt,
+    NbtTag,
+    NbtPath,
+    Objective,
+    ObjectiveCriteria,
+    Operation,
+    Particle,
+    Angle,
+    Rotation,
+    ScoreboardSlot,
+    ScoreHolder { allow_multiple: bool },
+    Swizzle,
+    Team,
+    ItemSlot,
+    ItemSlots,
+    ResourceLocation,
+    Function,
+    EntityAnchor,
+    IntRange,
+    FloatRange,
+    Dimension,
+    Gamemode,
+    Time { min: i32 },
+    ResourceOrTag { registry: PString<'static> },
+    ResourceOrTagKey { registry: PString<'static> },
+    Resource { registry: PString<'static> },
+    ResourceKey { registry: PString<'static> },
+    TemplateMirror,
+    TemplateRotation,
+    Heightmap,
+    LootTable,
+    LootPredicate,
+    LootModifier,
+    Uuid,
+}
+
+impl CommandParser {
+    /// The numeric ID of the parser in the protocol
+    pub fn id(&self) -> i32 {
+        match self {
+            Self::BrigadierBool => 0,
+            Self::BrigadierFloat(_) => 1,
+            Self::BrigadierDouble(_) => 2,
+            Self::BrigadierInteger(_) => 3,
+            Self::BrigadierLong(_) => 4,
+            Self::BrigadierString(_) => 5,
+            Self::Entity { .. } => 6,
+            Self::GameProfile => 7,
+            Self::BlockPos => 8,
+            Self::ColumnPos => 9,
+            Self::Vec3 => 10,
+            Self::Vec2 => 11,
+            Self::BlockState => 12,
+            Self::BlockPredicate => 13,
+            Self::ItemStack => 14,
+            Self::ItemPredicate => 15,
+            Self::Color => 16,
+            Self::Component => 17,
+            Self::Style => 18,
+            Self::Message => 19,
+            Self::Nbt => 20,
+            Self::NbtTag => 21,
+            Self::NbtPath => 22,
+            Self::Objective => 23,
+            Self::ObjectiveCriteria => 24,
+            Self::Operation => 25,
+            Self::Particle => 26,
+            Self::Angle => 27,
+            Self::Rotation => 28,
+            Self::ScoreboardSlot => 29,
+            Self::ScoreHolder { .. } => 30,
+            Self::Swizzle => 31,
+            Self::Team => 32,
+            Self::ItemSlot => 33,
+            Self::ItemSlots => 34,
+            Self::ResourceLocation => 35,
+            Self::Function => 36,
+            Self::EntityAnchor => 37,
+            Self::IntRange => 38,
+            Self::FloatRange => 39,
+            Self::Dimension => 40,
+            Self::Gamemode => 41,
+            Self::Time { .. } => 42,
+            Self::ResourceOrTag { .. } => 43,
+            Self::ResourceOrTagKey { .. } => 44,
+            Self::Resource { .. } => 45,
+            Self::ResourceKey { .. } => 46,
+            Self::TemplateMirror => 47,
+            Self::TemplateRotation => 48,
+            Self::Heightmap => 49,
+            Self::LootTable => 50,
+            Self::LootPredicate => 51,
+            Self::LootModifier => 52,
+            Self::Uuid => 53,
+        }
+    }
+
+    /// Returns the parser for a numeric ID, if the parser has no properties
+    fn without_properties(id: i32) -> Option<Self> {
+        let parser = match id {
+            0 => Self::BrigadierBool,
+            7 => Self::GameProfile,
+            8 => Self::BlockPos,
+            9 => Self::ColumnPos,
+            10 => Self::Vec3,
+            11 => Self::Vec2,
+            12 => Self::BlockState,
+            13 => Self::BlockPredicate,
+            14 => Self::ItemStack,
+            15 => Self::ItemPredicate,
+            16 => Self::Color,
+            17 => Self::Component,
+            18 => Self::Style,
+            19 => Self::Message,
+            20 => Self::Nbt,
+            21 => Self::NbtTag,
+            22 => Self::NbtPath,
+            23 => Self::Objective,
+            24 => Self::ObjectiveCriteria,
+            25 => Self::Operation,
+            26 => Self::Particle,
+            27 => Self::Angle,
+            28 => Self::Rotation,
+            29 => Self::ScoreboardSlot,
+            31 => Self::Swizzle,
+            32 => Self::Team,
+            33 => Self::ItemSlot,
+            34 => Self::ItemSlots,
+            35 => Self::ResourceLocation,
+            36 => Self::Function,
+            37 => Self::EntityAnchor,
+            38 => Self::IntRange,
+            39 => Self::FloatRange,
+            40 => Self::Dimension,
+            41 => Self::Gamemode,
+            47 => Self::TemplateMirror,
+            48 => Self::TemplateRotation,
+            49 => Self::Heightmap,
+            50 => Self::LootTable,
+            51 => Self::LootPredicate,
+            52 => Self::LootModifier,
+            53 => Self::Uuid,
+            _ => return None,
+        };
+        Some(parser)
+    }
 }
 
 impl SerializeItem for CommandParser {
     fn slen(&self) -> usize {
-        todo!()
+        let properties = match self {
+            Self::BrigadierFloat(range) => range.slen(),
+            Self::BrigadierDouble(range) => range.slen(),
+            Self::BrigadierInteger(range) => range.slen(),
+            Self::BrigadierLong(range) => range.slen(),
+            Self::BrigadierString(mode) => mode.slen(),
+            Self::Entity { .. } => 1,
+            Self::ScoreHolder { .. } => 1,
+            Self::Time { min } => min.slen(),
+            Self::ResourceOrTag { registry }
+            | Self::ResourceOrTagKey { registry }
+            | Self::Resource { registry }
+            | Self::ResourceKey { registry } => registry.slen(),
+            _ => 0,
+        };
+
+        VarInt(self.id()).slen() + properties
     }
 
     fn parse(i: &[u8]) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
         let (i, id) = VarInt::parse(i)?;
 
+        if let Some(parser) = Self::without_properties(id.0) {
+            return Ok((i, parser));
+        }
+
         match id.0 {
+            1 => {
+                let (i, range) = NumberRange::parse(i)?;
+                Ok((i, Self::BrigadierFloat(range)))
+            }
+            2 => {
+                let (i, range) = NumberRange::parse(i)?;
+                Ok((i, Self::BrigadierDouble(range)))
+            }
+            3 => {
+                let (i, range) = NumberRange::parse(i)?;
+                Ok((i, Self::BrigadierInteger(range)))
+            }
+            4 => {
+                let (i, range) = NumberRange::parse(i)?;
+                Ok((i, Self::BrigadierLong(range)))
+            }
             5 => {
-                let (i, id) = VarInt::parse(i)?;
-                Ok((i, Self::BrigadierString(id)))
+                let (i, mode) = StringMode::parse(i)?;
+                Ok((i, Self::BrigadierString(mode)))
             }
             6 => {
                 let (i, flags) = i8::parse(i)?;
-                Ok((i, Self::Entity(flags)))
+                Ok((
+                    i,
+                    Self::Entity {
+                        single: flags & 0x01 != 0,
+                        only_players: flags & 0x02 != 0,
+                    },
+                ))
+            }
+            30 => {
+                let (i, flags) = i8::parse(i)?;
+                Ok((
+                    i,
+                    Self::ScoreHolder {
+                        allow_multiple: flags & 0x01 != 0,
+                    },
+                ))
+            }
+            42 => {
+                let (i, min) = i32::parse(i)?;
+                Ok((i, Self::Time { min }))
+            }
+            43 => {
+                let (i, registry) = PString::parse(i)?;
+                Ok((i, Self::ResourceOrTag { registry }))
+            }
+            44 => {
+                let (i, registry) = PString::parse(i)?;
+                Ok((i, Self::ResourceOrTagKey { registry }))
+            }
+            45 => {
+                let (i, registry) = PString::parse(i)?;
+                Ok((i, Self::Resource { registry }))
             }
-            19 => Ok((i, Self::Message)),
-            other => {
-                dbg!(other);
-                Err(nom::Err::Error(crate::general::ParseError::NotImplemented(
-                    "Unknown Command Parser ID",
-                )))
+            46 => {
+                let (i, registry) = PString::parse(i)?;
+                Ok((i, Self::ResourceKey { registry }))
             }
+            _ => Err(nom::Err::Error(crate::general::ParseError::NotImplemented(
+                "Unknown Command Parser ID",
+            ))),
         }
     }
 
     fn serialize<'b>(
         &self,
-        buf: &'b mut [u8],
+        mut buf: &'b mut [u8],
     ) -> Result<&'b mut [u8], crate::serialize::SerializeError> {
-        todo!()
+        buf = VarInt(self.id()).serialize(buf)?;
+
+        match self {
+            Self::BrigadierFloat(range) => range.serialize(buf),
+            Self::BrigadierDouble(range) => range.serialize(buf),
+            Self::BrigadierInteger(range) => range.serialize(buf),
+            Self::BrigadierLong(range) => range.serialize(buf),
+            Self::BrigadierString(mode) => mode.serialize(buf),
+            Self::Entity {
+                single,
+                only_players,
+            } => {
+                let mut flags: i8 = 0;
+                if *single {
+                    flags |= 0x01;
+                }
+                if *only_players {
+                    flags |= 0x02;
+                }
+                flags.serialize(buf)
+            }
+            Self::ScoreHolder { allow_multiple } => {
+                let flags: i8 = if *allow_multiple { 0x01 } else { 0x00 };
+                flags.serialize(buf)
+            }
+            Self::Time { min } => min.serialize(buf),
+            Self::ResourceOrTag { registry }
+            | Self::ResourceOrTagKey { registry }
+            | Self::Resource { registry }
+            | Self::ResourceKey { registry } => registry.serialize(buf),
+            _ => Ok(buf),
+        }
     }
 }
 
@@ -942,6 +1616,7 @@ mod tests {
     test_packet_impled!(id_0x26, 0x26);
     test_packet_impled!(id_0x28, 0x28);
     test_packet_impled!(id_0x29, 0x29);
+    test_packet_impled!(id_0x3d, 0x3d);
     test_packet_impled!(id_0x3e, 0x3e);
     test_packet_impled!(id_0x54, 0x54);
     test_packet_impled!(id_0x56, 0x56);
@@ -951,4 +1626,235 @@ mod tests {
     test_packet_impled!(id_0x6f, 0x6f);
     test_packet_impled!(id_0x71, 0x71);
     test_packet_impled!(id_0x72, 0x72);
+    test_packet_impled!(id_0x16, 0x16);
+    test_packet_impled!(id_0x19, 0x19);
+    test_packet_impled!(id_0x6b, 0x6b);
+    test_packet_impled!(id_0x73, 0x73);
+    test_packet_impled!(id_0x1d, 0x1d);
+    test_packet_impled!(id_0x45, 0x45);
+    test_packet_impled!(id_0x46, 0x46);
+    test_packet_impled!(id_0x1e, 0x1e);
+    test_packet_impled!(id_0x39, 0x39);
+    test_packet_impled!(id_0x6c, 0x6c);
+
+    fn roundtrip<T>(value: T)
+    where
+        T: SerializeItem + PartialEq + std::fmt::Debug,
+    {
+        let mut buffer = vec![0; value.slen()];
+        let rem = value.serialize(&mut buffer).unwrap().len();
+        let written = buffer.len() - rem;
+
+        let (remaining, parsed) = T::parse(&buffer[..written]).unwrap();
+        assert!(remaining.is_empty());
+        assert_eq!(value, parsed);
+    }
+
+    #[test]
+    fn command_parser_properties() {
+        roundtrip(CommandParser::BrigadierBool);
+        roundtrip(CommandParser::BrigadierFloat(NumberRange::new(
+            Some(0.5),
+            None,
+        )));
+        roundtrip(CommandParser::BrigadierDouble(NumberRange::new(
+            None,
+            Some(10.0),
+        )));
+        roundtrip(CommandParser::BrigadierInteger(NumberRange::new(
+            Some(-5),
+            Some(5),
+        )));
+        roundtrip(CommandParser::BrigadierLong(NumberRange::default()));
+        roundtrip(CommandParser::BrigadierString(StringMode::GreedyPhrase));
+        roundtrip(CommandParser::Entity {
+            single: true,
+            only_players: false,
+        });
+        roundtrip(CommandParser::ScoreHolder {
+            allow_multiple: true,
+        });
+        roundtrip(CommandParser::Time { min: 1 });
+        roundtrip(CommandParser::ResourceKey {
+            registry: PString("minecraft:worldgen/biome".into()),
+        });
+        roundtrip(CommandParser::Uuid);
+    }
+
+    #[test]
+    fn integer_range_encoding() {
+        let parser = CommandParser::BrigadierInteger(NumberRange::new(None, Some(3)));
+
+        let mut buffer = vec![0; parser.slen()];
+        let rem = parser.serialize(&mut buffer).unwrap().len();
+        let written = buffer.len() - rem;
+
+        assert_eq!(&[0x03, 0x02, 0x00, 0x00, 0x00, 0x03], &buffer[..written]);
+    }
+
+    #[test]
+    fn command_node_roundtrip() {
+        roundtrip(CommandNode {
+            flags: CommandNode::TYPE_ROOT,
+            children: vec![VarInt(1)],
+            redirect: None,
+            name: None,
+            parser: None,
+            suggestions_type: None,
+        });
+        roundtrip(CommandNode {
+            flags: CommandNode::TYPE_LITERAL | CommandNode::HAS_REDIRECT,
+            children: vec![],
+            redirect: Some(VarInt(1)),
+            name: Some(PString("tp".into())),
+            parser: None,
+            suggestions_type: None,
+        });
+        roundtrip(CommandNode {
+            flags: CommandNode::TYPE_ARGUMENT
+                | CommandNode::EXECUTABLE
+                | CommandNode::HAS_SUGGESTIONS_TYPE,
+            children: vec![],
+            redirect: None,
+            name: Some(PString("target".into())),
+            parser: Some(CommandParser::BrigadierString(StringMode::SingleWord)),
+            suggestions_type: Some(PString("minecraft:ask_server".into())),
+        });
+    }
+
+    #[test]
+    fn player_info_update() {
+        let expected = PlayerInfoUpdate::new(vec![(
+            0x1234,
+            vec![
+                PlayerAction::UpdateListed { listed: true },
+                PlayerAction::AddPlayer {
+                    name: PString("Notch".into()),
+                    properties: vec![crate::login::client::Property {
+                        name: PString("textures".into()),
+                        value: PString("value".into()),
+                        signature: Some(PString("signature".into())),
+                    }],
+                },
+                PlayerAction::InitializeChat {
+                    session: Some(ChatSession {
+                        session_id: 1,
+                        expires_at: 2,
+                        public_key: vec![3, 4],
+                        key_signature: vec![5],
+                    }),
+                },
+                PlayerAction::UpdateGamemode {
+                    gamemode: VarInt(1),
+                },
+                PlayerAction::UpdateLatency { ping: VarInt(42) },
+                PlayerAction::UpdateDisplayName {
+                    display_name: Some(TextComponent::text("Notch")),
+                },
+            ],
+        )]);
+        assert_eq!(0x3f, expected.actions);
+
+        let buffer = crate::packet::Packet { inner: expected }.serialize();
+        let (rem, parsed) = crate::packet::Packet::parse(Play::parse)(&buffer).unwrap();
+        assert!(rem.is_empty());
+
+        match parsed.inner {
+            Play::PlayerInfoUpdate(update) => {
+                assert_eq!(0x3f, update.actions);
+                assert_eq!(0x1234, update.players[0].0);
+                assert_eq!(
+                    PlayerAction::AddPlayer {
+                        name: PString("Notch".into()),
+                        properties: vec![crate::login::client::Property {
+                            name: PString("textures".into()),
+                            value: PString("value".into()),
+                            signature: Some(PString("signature".into())),
+                        }],
+                    },
+                    update.players[0].1[0]
+                );
+                assert_eq!(
+                    PlayerAction::UpdateListed { listed: true },
+                    update.players[0].1[3]
+                );
+                assert_eq!(
+                    PlayerAction::UpdateDisplayName {
+                        display_name: Some(TextComponent::text("Notch")),
+                    },
+                    update.players[0].1[5]
+                );
+            }
+            other => panic!("Unexpected Packet {:?}", other),
+        };
+    }
+
+    #[test]
+    fn player_info_update_mismatched_actions() {
+        let update = PlayerInfoUpdate {
+            actions: PlayerAction::UPDATE_LISTED | PlayerAction::UPDATE_LATENCY,
+            players: vec![(1, vec![PlayerAction::UpdateListed { listed: true }])],
+        };
+
+        let mut buffer = vec![0; crate::packet::PacketContent::length(&update)];
+        assert!(crate::packet::PacketContent::serialize(&update, &mut buffer).is_err());
+    }
+
+    #[test]
+    fn keep_alive() {
+        let buffer = crate::packet::Packet {
+            inner: KeepAlive { id: -2 },
+        }
+        .serialize();
+
+        let (rem, parsed) = crate::packet::Packet::parse(Play::parse)(&buffer).unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(Play::KeepAlive(KeepAlive { id: -2 }), parsed.inner);
+    }
+
+    #[test]
+    fn player_info_remove() {
+        let buffer = crate::packet::Packet {
+            inner: PlayerInfoRemove {
+                players: vec![1, 2],
+            },
+        }
+        .serialize();
+        let (_, parsed) = crate::packet::Packet::parse(Play::parse)(&buffer).unwrap();
+
+        assert_eq!(
+            Play::PlayerInfoRemove(PlayerInfoRemove {
+                players: vec![1, 2]
+            }),
+            parsed.inner
+        );
+    }
+
+    #[test]
+    fn height_map() {
+        let mut heights = Heightmap::new();
+        heights.set(3, 4, 100);
+
+        let expected = HeightMap::new(
+            384,
+            &[
+                (HeightmapKind::MotionBlocking, &heights),
+                (HeightmapKind::WorldSurface, &Heightmap::new()),
+            ],
+        );
+
+        let mut buffer = vec![0; expected.slen()];
+        expected.serialize(&mut buffer).unwrap();
+        let (_, parsed) = HeightMap::parse(&buffer).unwrap();
+
+        assert_eq!(
+            Some(Ok(heights)),
+            parsed.get(HeightmapKind::MotionBlocking, 384)
+        );
+        assert_eq!(
+            Some(Ok(Heightmap::new())),
+            parsed.get(HeightmapKind::WorldSurface, 384)
+        );
+        assert_eq!(expected, parsed);
+    }
 }
@@ -1,12 +1,20 @@
 use crate::{
+    chat::{ChatSession, LastSeenUpdate, MessageSignature},
     combined_packet, declare_packet,
-    general::{Position, VarInt},
+    general::{PString, Position, ResourcePackResult, VarInt},
+    plugin_message,
 };
 
 combined_packet!(
     Play,
     ConfirmTeleportation,
+    MessageAcknowledgment,
+    ChatCommand,
+    ChatMessage,
+    PlayerSession,
     ChunkBatchReceived,
+    CookieResponse,
+    PluginMessage,
     Interact,
     KeepAlive,
     SetPlayerPosition,
@@ -15,15 +23,38 @@ combined_packet!(
     SetPlayerOnGround,
     PlayerAction,
     PlayerCommand,
+    ResourcePackResponse,
     SetHeldItem,
     SwingArm
 );
 
 declare_packet!(ConfirmTeleportation, 0x00, false, (teleport_id, VarInt));
+declare_packet!(MessageAcknowledgment, 0x03, false, (message_count, VarInt));
+declare_packet!(ChatCommand, 0x04, false, (command, PString<'static>));
+declare_packet!(
+    ChatMessage,
+    0x06,
+    false,
+    (message, PString<'static>),
+    (timestamp, i64),
+    (salt, i64),
+    (signature, Option<MessageSignature>),
+    (last_seen, LastSeenUpdate)
+);
+declare_packet!(PlayerSession, 0x07, false, (session, ChatSession));
 declare_packet!(ChunkBatchReceived, 0x08, false, (chunks_per_tick, f32));
 
+declare_packet!(
+    CookieResponse,
+    0x11,
+    false,
+    (key, PString<'static>),
+    (payload, Option<Vec<u8>>)
+);
+plugin_message!(PluginMessage, 0x12);
+
 declare_packet!(Interact, 0x16, false,); // TODO
-declare_packet!(KeepAlive, 0x18, false,); // TODO
+declare_packet!(KeepAlive, 0x18, false, (id, i64));
 declare_packet!(
     SetPlayerPosition,
     0x1a,
@@ -71,6 +102,13 @@ declare_packet!(
     (action_id, VarInt),
     (jump_boost, VarInt)
 );
+declare_packet!(
+    ResourcePackResponse,
+    0x2b,
+    false,
+    (uuid, u128),
+    (result, ResourcePackResult)
+);
 declare_packet!(SetHeldItem, 0x2f, false,); // TODO
 
 declare_packet!(SwingArm, 0x36, false, (hand, VarInt));
@@ -90,6 +128,39 @@ mod tests {
         };
     }
 
+    #[test]
+    fn implemented_x04() {
+        let result = Play::parse(VarInt(0x04), &[]);
+        match result {
+            Err(nom::Err::Error(crate::general::ParseError::UnknownPacketId(_))) => {
+                panic!("Unimplemented Packet ID")
+            }
+            _ => {}
+        };
+    }
+
+    #[test]
+    fn implemented_x06() {
+        let result = Play::parse(VarInt(0x06), &[]);
+        match result {
+            Err(nom::Err::Error(crate::general::ParseError::UnknownPacketId(_))) => {
+                panic!("Unimplemented Packet ID")
+            }
+            _ => {}
+        };
+    }
+
+    #[test]
+    fn implemented_x07() {
+        let result = Play::parse(VarInt(0x07), &[]);
+        match result {
+            Err(nom::Err::Error(crate::general::ParseError::UnknownPacketId(_))) => {
+                panic!("Unimplemented Packet ID")
+            }
+            _ => {}
+        };
+    }
+
     #[test]
     fn implemented_x16() {
         let result = Play::parse(VarInt(0x16), &[]);
@@ -0,0 +1,179 @@
+//! Typed payloads for Plugin Messages
+//!
+//! [Docs](https://wiki.vg/Plugin_channels)
+
+use crate::{
+    general::{PString, ParseError},
+    serialize::SerializeItem,
+};
+
+/// A Plugin Channel with a typed payload, which can be send using any of the `PluginMessage`
+/// packets in the configuration and play state
+pub trait PluginChannel: Sized {
+    /// The identifier of the channel, like `minecraft:brand`
+    const CHANNEL: &'static str;
+
+    fn encode(&self) -> Vec<u8>;
+    fn decode(data: &[u8]) -> Result<Self, ParseError>;
+}
+
+/// The `minecraft:brand` channel, containing the name of the client/server implementation
+#[derive(Debug, PartialEq, Clone)]
+pub struct Brand {
+    pub brand: String,
+}
+
+impl PluginChannel for Brand {
+    const CHANNEL: &'static str = "minecraft:brand";
+
+    fn encode(&self) -> Vec<u8> {
+        let content = PString(self.brand.as_str().into());
+
+        let mut buffer = vec![0; content.slen()];
+        let remaining = content
+            .serialize(&mut buffer)
+            .expect("The buffer has the correct size")
+            .len();
+        buffer.truncate(buffer.len() - remaining);
+        buffer
+    }
+
+    fn decode(data: &[u8]) -> Result<Self, ParseError> {
+        let (_, brand) = PString::parse(data).map_err(|e| match e {
+            nom::Err::Error(e) | nom::Err::Failure(e) => e,
+            nom::Err::Incomplete(_) => ParseError::Other,
+        })?;
+
+        Ok(Self {
+            brand: brand.0.into_owned(),
+        })
+    }
+}
+
+fn encode_channels(channels: &[String]) -> Vec<u8> {
+    channels.join("\0").into_bytes()
+}
+
+fn decode_channels(data: &[u8]) -> Result<Vec<String>, ParseError> {
+    let content = core::str::from_utf8(data).map_err(|_| ParseError::ParseString)?;
+
+    Ok(content
+        .split('\0')
+        .filter(|c| !c.is_empty())
+        .map(|c| c.to_string())
+        .collect())
+}
+
+/// The `minecraft:register` channel, used to announce the channels the sender can handle
+#[derive(Debug, PartialEq, Clone)]
+pub struct Register {
+    pub channels: Vec<String>,
+}
+
+impl PluginChannel for Register {
+    const CHANNEL: &'static str = "minecraft:register";
+
+    fn encode(&self) -> Vec<u8> {
+        encode_channels(&self.channels)
+    }
+
+    fn decode(data: &[u8]) -> Result<Self, ParseError> {
+        decode_channels(data).map(|channels| Self { channels })
+    }
+}
+
+/// The `minecraft:unregister` channel, used to announce channels the sender no longer handles
+#[derive(Debug, PartialEq, Clone)]
+pub struct Unregister {
+    pub channels: Vec<String>,
+}
+
+impl PluginChannel for Unregister {
+    const CHANNEL: &'static str = "minecraft:unregister";
+
+    fn encode(&self) -> Vec<u8> {
+        encode_channels(&self.channels)
+    }
+
+    fn decode(data: &[u8]) -> Result<Self, ParseError> {
+        decode_channels(data).map(|channels| Self { channels })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::Packet;
+
+    #[test]
+    fn brand() {
+        let brand = Brand {
+            brand: "vanilla".to_string(),
+        };
+
+        let encoded = brand.encode();
+        assert_eq!(b"\x07vanilla".as_slice(), encoded.as_slice());
+        assert_eq!(Ok(brand), Brand::decode(&encoded));
+    }
+
+    #[test]
+    fn register() {
+        let register = Register {
+            channels: vec!["first:a".to_string(), "second:b".to_string()],
+        };
+
+        let encoded = register.encode();
+        assert_eq!(b"first:a\0second:b".as_slice(), encoded.as_slice());
+        assert_eq!(Ok(register), Register::decode(&encoded));
+
+        assert_eq!(
+            Ok(Unregister {
+                channels: vec!["first:a".to_string()]
+            }),
+            Unregister::decode(b"first:a\0")
+        );
+    }
+
+    #[test]
+    fn configuration_serverbound() {
+        let expected = crate::configuration::server::PluginMessage::new(&Brand {
+            brand: "vanilla".to_string(),
+        });
+
+        let buffer = Packet { inner: expected }.serialize();
+        let (rem, parsed) =
+            Packet::parse(crate::configuration::server::PluginMessage::parse)(&buffer).unwrap();
+        assert!(rem.is_empty());
+
+        assert_eq!(
+            Some(Ok(Brand {
+                brand: "vanilla".to_string()
+            })),
+            parsed.inner.decode::<Brand>()
+        );
+        assert_eq!(None, parsed.inner.decode::<Register>());
+    }
+
+    #[test]
+    fn play_clientbound() {
+        let expected = crate::play::client::PluginMessage::new(&Register {
+            channels: vec!["custom:channel".to_string()],
+        });
+
+        let buffer = Packet { inner: expected }.serialize();
+        let (rem, parsed) = Packet::parse(crate::play::client::Play::parse)(&buffer).unwrap();
+        assert!(rem.is_empty());
+
+        match parsed.inner {
+            crate::play::client::Play::PluginMessage(pm) => {
+                assert_eq!(
+                    Some(Ok(Register {
+                        channels: vec!["custom:channel".to_string()]
+                    })),
+                    pm.decode::<Register>()
+                );
+            }
+            other => panic!("Unexpected Packet {:?}", other),
+        };
+    }
+}
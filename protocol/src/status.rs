@@ -1,4 +1,5 @@
 //! All the Messages relating to the `Status` state of a connection
 
 pub mod client;
+pub mod legacy;
 pub mod server;
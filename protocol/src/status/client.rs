@@ -2,7 +2,7 @@ use crate::{
     general::{PString, VarInt},
     serialize::SerializeItem,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug)]
 pub struct StatusResponse {
@@ -18,6 +18,16 @@ impl StatusResponse {
         }
     }
 
+    /// The status as the JSON send by the server
+    pub fn json(&self) -> &str {
+        &self.content.0
+    }
+
+    /// Parses the JSON of the status, which allows changing it before creating a new response
+    pub fn content(&self) -> Result<StatusResponseContent, serde_json::Error> {
+        serde_json::from_str(&self.content.0)
+    }
+
     pub fn parse(id: VarInt, i: &[u8]) -> nom::IResult<&[u8], Self, crate::general::ParseError> {
         if id.0 != 0x00 {
             return Err(nom::Err::Error(crate::general::ParseError::Other));
@@ -45,33 +55,207 @@ impl crate::packet::PacketContent for StatusResponse {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StatusResponseContent {
     pub version: StatusVersion,
     pub players: StatusPlayers,
     pub description: StatusDescription,
-    #[serde(rename = "enforcesSecureChat")]
+    /// A 64x64 PNG as a data URI, see [`favicon_data_uri`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub favicon: Option<String>,
+    #[serde(rename = "enforcesSecureChat", default)]
     pub enforces_secure_chat: bool,
-    #[serde(rename = "previewsChat")]
+    #[serde(rename = "previewsChat", default)]
     pub previews_chat: bool,
 }
 
-#[derive(Debug, Serialize)]
+impl StatusResponseContent {
+    /// The PNG of the favicon, if there is one and it is a valid base64 PNG data URI
+    pub fn favicon_png(&self) -> Option<Vec<u8>> {
+        use base64::prelude::*;
+
+        let data = self.favicon.as_ref()?.strip_prefix(FAVICON_PREFIX)?;
+        BASE64_STANDARD.decode(data).ok()
+    }
+}
+
+const FAVICON_PREFIX: &str = "data:image/png;base64,";
+
+/// Encodes the PNG as the data URI used for the favicon
+pub fn favicon_data_uri(png: &[u8]) -> String {
+    use base64::prelude::*;
+
+    format!("{}{}", FAVICON_PREFIX, BASE64_STANDARD.encode(png))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StatusVersion {
     pub name: String,
-    pub protocol: u16,
+    /// Servers send a protocol no client has, like -1, to always show the version as
+    /// incompatible
+    pub protocol: i32,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StatusPlayers {
     pub max: usize,
     pub online: usize,
-    pub sample: Vec<()>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sample: Vec<SamplePlayer>,
+}
+
+/// A player shown when hovering over the player count, servers also use this for arbitrary
+/// lines of text with a zero UUID
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SamplePlayer {
+    pub name: String,
+    #[serde(with = "hyphenated_uuid")]
+    pub id: u128,
+}
+
+/// The UUIDs in the JSON are hyphenated strings
+mod hyphenated_uuid {
+    pub fn serialize<S>(uuid: &u128, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let hex = format!("{:032x}", uuid);
+        serializer.serialize_str(&format!(
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        ))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<u128, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let raw: std::borrow::Cow<'de, str> = serde::Deserialize::deserialize(deserializer)?;
+        let hex = raw.replace('-', "");
+        if hex.len() != 32 {
+            return Err(serde::de::Error::custom("UUID does not have 32 hex digits"));
+        }
+        u128::from_str_radix(&hex, 16).map_err(serde::de::Error::custom)
+    }
 }
 
-#[derive(Debug, Serialize)]
+/// The MOTD, as a JSON text component with formatting and children
+///
+/// Plain strings are also accepted when deserializing, as some servers send the description that
+/// way.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(from = "DescriptionRepr")]
 pub struct StatusDescription {
     pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub italic: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub underlined: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strikethrough: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub obfuscated: Option<bool>,
+    /// The components following this one, which inherit its formatting
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra: Vec<StatusDescription>,
+}
+
+impl StatusDescription {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the color, either one of the named colors like `gold` or a `#RRGGBB` value
+    pub fn color(mut self, color: impl Into<String>) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    pub fn bold(mut self) -> Self {
+        self.bold = Some(true);
+        self
+    }
+
+    pub fn italic(mut self) -> Self {
+        self.italic = Some(true);
+        self
+    }
+
+    pub fn append(mut self, child: StatusDescription) -> Self {
+        self.extra.push(child);
+        self
+    }
+
+    /// The text of the component and all its children, without any formatting
+    pub fn plain_text(&self) -> String {
+        let mut result = self.text.clone();
+        for child in self.extra.iter() {
+            result.push_str(&child.plain_text());
+        }
+        result
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DescriptionRepr {
+    Text(String),
+    Component {
+        #[serde(default)]
+        text: String,
+        #[serde(default)]
+        color: Option<String>,
+        #[serde(default)]
+        bold: Option<bool>,
+        #[serde(default)]
+        italic: Option<bool>,
+        #[serde(default)]
+        underlined: Option<bool>,
+        #[serde(default)]
+        strikethrough: Option<bool>,
+        #[serde(default)]
+        obfuscated: Option<bool>,
+        #[serde(default)]
+        extra: Vec<StatusDescription>,
+    },
+}
+
+impl From<DescriptionRepr> for StatusDescription {
+    fn from(value: DescriptionRepr) -> Self {
+        match value {
+            DescriptionRepr::Text(text) => Self::text(text),
+            DescriptionRepr::Component {
+                text,
+                color,
+                bold,
+                italic,
+                underlined,
+                strikethrough,
+                obfuscated,
+                extra,
+            } => Self {
+                text,
+                color,
+                bold,
+                italic,
+                underlined,
+                strikethrough,
+                obfuscated,
+                extra,
+            },
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -123,9 +307,8 @@ mod tests {
                 online: 0,
                 sample: Vec::new(),
             },
-            description: StatusDescription {
-                text: "testing".into(),
-            },
+            description: StatusDescription::text("testing"),
+            favicon: None,
             enforces_secure_chat: false,
             previews_chat: false,
         };
@@ -134,6 +317,55 @@ mod tests {
         };
 
         let serialized = packet.serialize();
-        dbg!(&serialized);
+        let (_, parsed) = crate::packet::Packet::parse(StatusResponse::parse)(&serialized).unwrap();
+        assert_eq!(content, parsed.inner.content().unwrap());
+    }
+
+    #[test]
+    fn rich_content() {
+        let content = StatusResponseContent {
+            version: StatusVersion {
+                name: "1.20.6".into(),
+                protocol: 766,
+            },
+            players: StatusPlayers {
+                max: 20,
+                online: 1,
+                sample: vec![SamplePlayer {
+                    name: "Notch".into(),
+                    id: 0x069a79f4_44e9_4726_a5be_fca90e38aaf5,
+                }],
+            },
+            description: StatusDescription::text("A ")
+                .append(StatusDescription::text("Minecraft").color("gold").bold())
+                .append(StatusDescription::text(" Server")),
+            favicon: Some(favicon_data_uri(b"\x89PNG")),
+            enforces_secure_chat: true,
+            previews_chat: false,
+        };
+
+        let json = serde_json::to_value(&content).unwrap();
+        assert_eq!(
+            "069a79f4-44e9-4726-a5be-fca90e38aaf5",
+            json["players"]["sample"][0]["id"]
+        );
+        assert_eq!("gold", json["description"]["extra"][0]["color"]);
+        assert_eq!("data:image/png;base64,iVBORw==", json["favicon"]);
+
+        let parsed: StatusResponseContent = serde_json::from_value(json).unwrap();
+        assert_eq!(content, parsed);
+        assert_eq!("A Minecraft Server", parsed.description.plain_text());
+        assert_eq!(Some(b"\x89PNG".to_vec()), parsed.favicon_png());
+    }
+
+    #[test]
+    fn minimal_content() {
+        let json = r#"{"version":{"name":"Paper 1.20.6","protocol":-1},"players":{"max":0,"online":0},"description":"Plain text"}"#;
+        let content: StatusResponseContent = serde_json::from_str(json).unwrap();
+
+        assert_eq!(-1, content.version.protocol);
+        assert!(content.players.sample.is_empty());
+        assert_eq!(StatusDescription::text("Plain text"), content.description);
+        assert_eq!(None, content.favicon_png());
     }
 }
@@ -0,0 +1,136 @@
+//! The status ping used before the Netty rewrite in 1.7, which modern servers still answer
+//!
+//! The client sends [`REQUEST`] and the server responds with a kick packet containing the status
+//! as a UTF-16 string, closing the connection afterwards.
+
+use crate::general::ParseError;
+
+/// The ping of the 1.4 to 1.6 clients, older servers ignore the second byte
+pub const REQUEST: [u8; 2] = [0xfe, 0x01];
+
+const KICK_ID: u8 = 0xff;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegacyStatus {
+    /// The protocol and version are only send by servers since 1.4
+    pub protocol: Option<i32>,
+    pub version: Option<String>,
+    pub motd: String,
+    pub online: i32,
+    pub max: i32,
+}
+
+impl LegacyStatus {
+    pub fn parse(i: &[u8]) -> nom::IResult<&[u8], Self, ParseError> {
+        let (i, _) = nom::bytes::streaming::tag([KICK_ID])(i)?;
+        let (i, length) = nom::number::streaming::be_u16(i)?;
+        let (i, raw) = nom::bytes::streaming::take(length as usize * 2)(i)?;
+
+        let units: Vec<u16> = raw
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect();
+        let content =
+            String::from_utf16(&units).map_err(|_| nom::Err::Error(ParseError::ParseString))?;
+
+        let number = |s: &str| {
+            s.parse::<i32>()
+                .map_err(|_| nom::Err::Error(ParseError::Other))
+        };
+
+        let status = match content.strip_prefix("§1\0") {
+            Some(fields) => {
+                let fields: Vec<&str> = fields.split('\0').collect();
+                let [protocol, version, motd, online, max] = fields[..] else {
+                    return Err(nom::Err::Error(ParseError::Other));
+                };
+
+                Self {
+                    protocol: Some(number(protocol)?),
+                    version: Some(version.to_string()),
+                    motd: motd.to_string(),
+                    online: number(online)?,
+                    max: number(max)?,
+                }
+            }
+            None => {
+                // The MOTD may contain the separator itself, so the counts are taken from the end
+                let mut fields = content.rsplitn(3, '§');
+                let (Some(max), Some(online), Some(motd)) =
+                    (fields.next(), fields.next(), fields.next())
+                else {
+                    return Err(nom::Err::Error(ParseError::Other));
+                };
+
+                Self {
+                    protocol: None,
+                    version: None,
+                    motd: motd.to_string(),
+                    online: number(online)?,
+                    max: number(max)?,
+                }
+            }
+        };
+
+        Ok((i, status))
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let content = match (self.protocol, self.version.as_ref()) {
+            (Some(protocol), Some(version)) => format!(
+                "§1\0{}\0{}\0{}\0{}\0{}",
+                protocol, version, self.motd, self.online, self.max
+            ),
+            _ => format!("{}§{}§{}", self.motd, self.online, self.max),
+        };
+        let units: Vec<u16> = content.encode_utf16().collect();
+
+        let mut result = Vec::with_capacity(3 + units.len() * 2);
+        result.push(KICK_ID);
+        result.extend_from_slice(&(units.len() as u16).to_be_bytes());
+        for unit in units {
+            result.extend_from_slice(&unit.to_be_bytes());
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let status = LegacyStatus {
+            protocol: Some(127),
+            version: Some("1.20.6".into()),
+            motd: "A Minecraft Server".into(),
+            online: 3,
+            max: 20,
+        };
+
+        let serialized = status.serialize();
+        assert_eq!(
+            &[0xff, 0x00, 0x25, 0x00, 0xa7, 0x00, 0x31],
+            &serialized[..7]
+        );
+
+        let (rem, parsed) = LegacyStatus::parse(&serialized).unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(status, parsed);
+    }
+
+    #[test]
+    fn beta_format() {
+        let status = LegacyStatus {
+            protocol: None,
+            version: None,
+            motd: "Old § Server".into(),
+            online: 0,
+            max: 8,
+        };
+
+        let (_, parsed) = LegacyStatus::parse(&status.serialize()).unwrap();
+        assert_eq!(status, parsed);
+    }
+}
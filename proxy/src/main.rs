@@ -1,7 +1,43 @@
 use tracing_subscriber::layer::SubscriberExt;
 
-use networking::{Connection, Transport};
+use auth::Authenticator;
+use networking::{
+    Connection, ConnectionConfig, ConnectionState, NetworkError, SplitTransport, Transport,
+    TransportReceive, TransportSend,
+};
+
+/// Verifies the players with a session server and forwards their profile to the backend, the
+/// keypair is shared by all the connections
+#[derive(Debug)]
+struct Authentication {
+    authenticator: auth::OnlineAuthenticator,
+    key: auth::encryption::ServerKey,
+    /// The secret for the modern player info forwarding, the `velocity_secret` of the backend
+    forwarding_secret: Vec<u8>,
+}
+
+impl Authentication {
+    /// Uses the keypair stored in `proxy.pem`, which is generated on the first start
+    fn new(
+        session_server: &str,
+        forwarding_secret: &str,
+    ) -> Result<Self, auth::encryption::KeyError> {
+        let key = auth::encryption::ServerKey::load_or_generate(
+            "proxy.pem",
+            auth::encryption::DEFAULT_KEY_BITS,
+        )?;
+
+        Ok(Self {
+            authenticator: auth::OnlineAuthenticator::new(session_server),
+            key,
+            forwarding_secret: forwarding_secret.as_bytes().to_vec(),
+        })
+    }
+}
 
+/// With `--online <secret>` the proxy verifies the players with the session server itself and
+/// forwards their profile to the backend using modern player info forwarding, the backend has to
+/// use the same secret. Otherwise the login is forwarded to the backend unchanged.
 fn main() {
     let fmt_layer = tracing_subscriber::fmt::layer().with_ansi(false);
     tracing::subscriber::set_global_default(
@@ -16,11 +52,24 @@ fn main() {
         .build()
         .unwrap();
 
-    runtime.block_on(run_server());
+    let mut authentication = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--online" {
+            let secret = args
+                .next()
+                .expect("--online expects the forwarding secret of the backend");
+            authentication = Some(std::sync::Arc::new(
+                Authentication::new(auth::OnlineAuthenticator::MOJANG, &secret).unwrap(),
+            ));
+        }
+    }
+
+    runtime.block_on(run_server(authentication));
 }
 
 #[tracing::instrument]
-async fn run_server() {
+async fn run_server(authentication: Option<std::sync::Arc<Authentication>>) {
     let listener = tokio::net::TcpListener::bind("0.0.0.0:25565")
         .await
         .unwrap();
@@ -30,27 +79,50 @@ async fn run_server() {
     while let Ok((connection, addr)) = listener.accept().await {
         tracing::info!(?addr);
 
-        let target_connection = tokio::net::TcpStream::connect("127.0.0.1:35565")
-            .await
-            .unwrap();
+        let target_connection = match tokio::net::TcpStream::connect("127.0.0.1:35565").await {
+            Ok(c) => c,
+            Err(e) => {
+                tracing::error!("Connecting to the Server: {:?}", e);
+                continue;
+            }
+        };
 
-        tokio::spawn(handle_connection(connection, target_connection));
+        tokio::spawn(handle_connection(
+            connection,
+            target_connection,
+            addr,
+            authentication.clone(),
+        ));
     }
 
     tracing::error!("Stopped");
 }
 
 #[tracing::instrument(skip(connection, target))]
-async fn handle_connection(connection: tokio::net::TcpStream, target: tokio::net::TcpStream) {
+async fn handle_connection(
+    connection: tokio::net::TcpStream,
+    target: tokio::net::TcpStream,
+    addr: std::net::SocketAddr,
+    authentication: Option<std::sync::Arc<Authentication>>,
+) {
     tracing::info!("Handle Connection");
 
-    let mut connection = Connection::new(
+    let config = ConnectionConfig::default();
+    for stream in [&connection, &target] {
+        if let Err(e) = config.configure_tcp(stream) {
+            tracing::warn!("Configuring TCP Stream: {:?}", e);
+        }
+    }
+
+    let mut connection = Connection::with_config(
         networking::UnencryptedConnection::new(connection),
         bytes::BytesMut::with_capacity(4096),
+        config.clone(),
     );
-    let mut target_connection = Connection::new(
+    let mut target_connection = Connection::with_config(
         networking::UnencryptedConnection::new(target),
         bytes::BytesMut::with_capacity(4096),
+        config,
     );
 
     let packet = match connection
@@ -59,7 +131,7 @@ async fn handle_connection(connection: tokio::net::TcpStream, target: tokio::net
     {
         Ok(p) => p,
         Err(e) => {
-            tracing::error!("Receiving initial Packet: {:?}", e);
+            log_error("User", &e);
             return;
         }
     };
@@ -77,22 +149,29 @@ async fn handle_connection(connection: tokio::net::TcpStream, target: tokio::net
         protocol::handshake::server::NextState::Status => {
             tracing::info!("Status");
 
-            target_connection
+            if let Err(e) = target_connection
                 .send_packet(&protocol::packet::Packet { inner: packet_data })
                 .await
-                .unwrap();
+            {
+                log_error("Server", &e);
+                return;
+            }
 
             status(connection, target_connection).await;
         }
-        protocol::handshake::server::NextState::Login => {
+        protocol::handshake::server::NextState::Login
+        | protocol::handshake::server::NextState::Transfer => {
             tracing::info!("Login");
 
-            target_connection
+            if let Err(e) = target_connection
                 .send_packet(&protocol::packet::Packet { inner: packet_data })
                 .await
-                .unwrap();
+            {
+                server_lost(connection, &e).await;
+                return;
+            }
 
-            login(connection, target_connection).await;
+            login(connection, target_connection, addr, authentication).await;
         }
     };
 }
@@ -103,6 +182,9 @@ where
     S: Transport,
     S2: Transport,
 {
+    connection.set_state(ConnectionState::Status);
+    target.set_state(ConnectionState::Status);
+
     loop {
         let packet = match connection
             .recv_packet(protocol::status::server::ServerBound::parse)
@@ -110,7 +192,7 @@ where
         {
             Ok(p) => p,
             Err(e) => {
-                tracing::error!("Receiving Packet: {:?}", e);
+                log_error("User", &e);
                 return;
             }
         };
@@ -120,36 +202,56 @@ where
             protocol::status::server::ServerBound::Status(s) => {
                 tracing::info!(?s, "Status");
 
-                target
-                    .send_packet(&protocol::packet::Packet { inner: s })
-                    .await
-                    .unwrap();
-
-                let response = target
-                    .recv_packet(protocol::status::client::StatusResponse::parse)
-                    .await
-                    .unwrap();
+                let response = async {
+                    target
+                        .send_packet(&protocol::packet::Packet { inner: s })
+                        .await?;
+                    target
+                        .recv_packet(protocol::status::client::StatusResponse::parse)
+                        .await
+                }
+                .await;
+                let response = match response {
+                    Ok(r) => r,
+                    Err(e) => {
+                        server_lost(connection, &e).await;
+                        return;
+                    }
+                };
                 tracing::info!("Response-Packet: {:?}", response);
 
-                connection.send_packet(&response).await.unwrap();
+                if let Err(e) = connection.send_packet(&response).await {
+                    log_error("User", &e);
+                    return;
+                }
 
                 tracing::info!("Send Response packet");
             }
             protocol::status::server::ServerBound::Ping(p) => {
                 tracing::info!(?p, "Ping");
 
-                target
-                    .send_packet(&protocol::packet::Packet { inner: p })
-                    .await
-                    .unwrap();
-
-                let response = target
-                    .recv_packet(protocol::status::client::PingResponse::parse)
-                    .await
-                    .unwrap();
+                let response = async {
+                    target
+                        .send_packet(&protocol::packet::Packet { inner: p })
+                        .await?;
+                    target
+                        .recv_packet(protocol::status::client::PingResponse::parse)
+                        .await
+                }
+                .await;
+                let response = match response {
+                    Ok(r) => r,
+                    Err(e) => {
+                        server_lost(connection, &e).await;
+                        return;
+                    }
+                };
                 tracing::info!("Response-Packet: {:?}", response);
 
-                connection.send_packet(&response).await.unwrap();
+                if let Err(e) = connection.send_packet(&response).await {
+                    log_error("User", &e);
+                    return;
+                }
 
                 tracing::info!("Send Response Packet");
             }
@@ -161,45 +263,245 @@ where
 async fn login<S, S2>(
     mut connection: Connection<networking::UnencryptedConnection<S>>,
     mut target: Connection<S2>,
+    addr: std::net::SocketAddr,
+    authentication: Option<std::sync::Arc<Authentication>>,
 ) where
     S: core::marker::Unpin + tokio::io::AsyncRead + tokio::io::AsyncWrite,
-    S2: Transport,
+    S2: Transport + SplitTransport,
 {
-    let login_start_packet = connection
+    connection.set_state(ConnectionState::Login);
+    target.set_state(ConnectionState::Login);
+
+    let login_start_packet = match connection
         .recv_packet(protocol::login::server::LoginStart::parse)
         .await
-        .unwrap();
+    {
+        Ok(p) => p,
+        Err(e) => {
+            log_error("User", &e);
+            return;
+        }
+    };
     tracing::info!(?login_start_packet);
 
-    target.send_packet(&login_start_packet).await.unwrap();
+    match authentication {
+        None => {
+            if let Err(e) = target.send_packet(&login_start_packet).await {
+                server_lost(connection, &e).await;
+                return;
+            }
+
+            forward_login(connection, target).await;
+        }
+        Some(authentication) => {
+            let name = login_start_packet.inner.name.0.to_string();
+            let Some((connection, profile)) =
+                authenticate(connection, &authentication, &name).await
+            else {
+                return;
+            };
+            tracing::info!(?profile, "Authenticated");
+
+            let login_start = protocol::packet::Packet {
+                inner: protocol::login::server::LoginStart {
+                    name: protocol::general::PString(profile.name.clone().into()),
+                    uuid: profile.uuid,
+                },
+            };
+            if let Err(e) = target.send_packet(&login_start).await {
+                server_lost(connection, &e).await;
+                return;
+            }
+
+            let player = auth::forwarding::ForwardedPlayer {
+                address: addr.ip().to_string(),
+                uuid: profile.uuid,
+                name: profile.name,
+                properties: profile.properties,
+            };
+            if let Err(e) =
+                forward_player_info(&mut target, &authentication.forwarding_secret, &player).await
+            {
+                server_lost(connection, &e).await;
+                return;
+            }
+
+            forward_login(connection, target).await;
+        }
+    };
+}
 
-    let response_packet = target
+/// Relays the rest of the login, once the backend received the login start
+async fn forward_login<S, S2>(mut connection: Connection<S>, mut target: Connection<S2>)
+where
+    S: Transport + SplitTransport,
+    S2: Transport + SplitTransport,
+{
+    let response_packet = match target
         .recv_packet(protocol::login::client::LoginSuccess::parse)
         .await
-        .unwrap();
+    {
+        Ok(p) => p,
+        Err(e) => {
+            server_lost(connection, &e).await;
+            return;
+        }
+    };
     tracing::info!("Response-Packet: {:?}", response_packet);
 
-    connection.send_packet(&response_packet).await.unwrap();
+    if let Err(e) = connection.send_packet(&response_packet).await {
+        log_error("User", &e);
+        return;
+    }
 
     tracing::info!("Send Login Success");
 
-    let packet = connection
+    let packet = match connection
         .recv_packet(protocol::login::server::LoginAck::parse)
         .await
-        .unwrap();
+    {
+        Ok(p) => p,
+        Err(e) => {
+            log_error("User", &e);
+            return;
+        }
+    };
     tracing::info!(?packet, "Login was Acknowledged");
 
-    target.send_packet(&packet).await.unwrap();
+    if let Err(e) = target.send_packet(&packet).await {
+        server_lost(connection, &e).await;
+        return;
+    }
 
     configuration(connection, target).await;
 }
 
+/// Answers the player info request of the backend with the verified profile of the player, so
+/// the backend uses the same UUID and skin
+async fn forward_player_info<S>(
+    target: &mut Connection<S>,
+    secret: &[u8],
+    player: &auth::forwarding::ForwardedPlayer,
+) -> Result<(), NetworkError>
+where
+    S: Transport,
+{
+    let request = target
+        .recv_packet(protocol::login::client::LoginPluginRequest::parse)
+        .await?;
+
+    // Like a client, requests on unknown channels are answered as not understood
+    let response = if request.inner.channel.0 == auth::forwarding::CHANNEL {
+        player.response(secret, request.inner.message_id.0)
+    } else {
+        tracing::warn!(channel = ?request.inner.channel, "Unknown Login Plugin Request");
+        protocol::login::server::LoginPluginResponse {
+            message_id: request.inner.message_id,
+            data: None,
+        }
+    };
+
+    target
+        .send_packet(&protocol::packet::Packet { inner: response })
+        .await
+}
+
+/// Encrypts the connection of the player and verifies them with the session server, returning
+/// their actual profile to forward to the backend
+async fn authenticate<S>(
+    mut connection: Connection<networking::UnencryptedConnection<S>>,
+    authentication: &Authentication,
+    name: &str,
+) -> Option<(
+    Connection<networking::EncryptedConnection<S>>,
+    auth::Profile,
+)>
+where
+    S: core::marker::Unpin + tokio::io::AsyncRead + tokio::io::AsyncWrite,
+{
+    let verify_token = auth::encryption::verify_token();
+
+    let request = protocol::packet::Packet {
+        inner: protocol::login::client::EncryptionRequest {
+            server_id: protocol::general::PString("".into()),
+            pubkey: authentication.key.public_key().to_vec(),
+            verifytoken: verify_token.to_vec(),
+        },
+    };
+    if let Err(e) = connection.send_packet(&request).await {
+        log_error("User", &e);
+        return None;
+    }
+
+    let response = match connection
+        .recv_packet(protocol::login::server::EncryptionResponse::parse)
+        .await
+    {
+        Ok(p) => p,
+        Err(e) => {
+            log_error("User", &e);
+            return None;
+        }
+    };
+
+    let shared_secret = match authentication
+        .key
+        .decrypt_response(&response.inner, &verify_token)
+    {
+        Ok(secret) => secret,
+        Err(e) => {
+            tracing::warn!("Decrypting the EncryptionResponse: {:?}", e);
+            return None;
+        }
+    };
+
+    let connection = match connection
+        .try_map_transport(|c| c.encrypt::<networking::cipher::DefaultCipher>(&shared_secret))
+    {
+        Ok(c) => c,
+        Err(e) => {
+            log_error("User", &e);
+            return None;
+        }
+    };
+
+    let session = auth::Session {
+        server_id: String::new(),
+        shared_secret,
+        public_key: authentication.key.public_key().to_vec(),
+    };
+    let profile = match authentication
+        .authenticator
+        .authenticate(name, Some(&session))
+        .await
+    {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::warn!(?name, "Authentication failed: {:?}", e);
+
+            let reason = protocol::general::TextComponent::translate(
+                "multiplayer.disconnect.unverified_username",
+                Vec::new(),
+            );
+            if let Err(e) = connection.disconnect(reason).await {
+                log_error("User", &e);
+            }
+            return None;
+        }
+    };
+
+    Some((connection, profile))
+}
+
 #[tracing::instrument(skip(connection, target))]
 async fn configuration<S, S2>(mut connection: Connection<S>, mut target: Connection<S2>)
 where
-    S: Transport,
-    S2: Transport,
+    S: Transport + SplitTransport,
+    S2: Transport + SplitTransport,
 {
+    connection.set_state(ConnectionState::Configuration);
+    target.set_state(ConnectionState::Configuration);
+
     tracing::info!("Entering Configuration State of the connection");
 
     loop {
@@ -208,7 +510,7 @@ where
                 let packet = match user_packet {
                     Ok(packet) => packet,
                     Err(e) => {
-                        tracing::error!("Receiving from User: {:?}", e);
+                        log_error("User", &e);
                         return;
                     }
                 };
@@ -222,7 +524,10 @@ where
                     }
                 };
 
-                target.send_rawpacket(&packet).await.unwrap();
+                if let Err(e) = target.send_rawpacket(&packet).await {
+                    server_lost(connection, &e).await;
+                    return;
+                }
 
                 if packet.id.0 == <protocol::configuration::server::AckFinish as protocol::packet::PacketContent>::ID {
                     break;
@@ -232,7 +537,7 @@ where
                 let packet = match server_packet {
                     Ok(packet) => packet,
                     Err(e) => {
-                        tracing::error!("Receiving from Server: {:?}", e);
+                        server_lost(connection, &e).await;
                         return;
                     }
                 };
@@ -263,7 +568,10 @@ where
                     }
                 };
 
-                connection.send_rawpacket(&packet).await.unwrap();
+                if let Err(e) = connection.send_rawpacket(&packet).await {
+                    log_error("User", &e);
+                    return;
+                }
             }
         }
     }
@@ -274,83 +582,224 @@ where
 #[tracing::instrument(skip(connection, target))]
 async fn play<S, S2>(mut connection: Connection<S>, mut target: Connection<S2>)
 where
-    S: Transport,
-    S2: Transport,
+    S: Transport + SplitTransport,
+    S2: Transport + SplitTransport,
 {
+    connection.set_state(ConnectionState::Play);
+    target.set_state(ConnectionState::Play);
+
     // TODO
     // How do we actually store/capture these packets for use afterwards
 
+    // Both directions are forwarded independently, so a slow peer only holds up the packets
+    // going to it
+    let (client_reader, client_writer) = connection.split();
+    let (server_reader, server_writer) = target.split();
+
+    tokio::select! {
+        _ = forward_to_server(client_reader, server_writer) => {}
+        _ = forward_to_client(server_reader, client_writer) => {}
+    };
+}
+
+async fn forward_to_server<R, W>(mut connection: Connection<R>, mut target: Connection<W>)
+where
+    R: TransportReceive,
+    W: TransportSend,
+{
     loop {
-        tokio::select! {
-            user_packet = connection.recv_rawpacket() => {
-                let packet = match user_packet {
-                    Ok(packet) => packet,
-                    Err(e) => {
-                        tracing::error!("Receiving from User: {:?}", e);
-                        return;
-                    }
-                };
+        let packet = match connection.recv_rawpacket().await {
+            Ok(packet) => packet,
+            Err(e) => {
+                log_error("User", &e);
+                return;
+            }
+        };
 
-                match protocol::play::server::Play::parse(packet.id, &packet.data) {
-                    Ok((rem, packet)) if rem.is_empty() => {
-                        // tracing::info!("[SERVER] {:#?}", packet);
-                    }
-                    Ok((rem, packet)) => {
-                        tracing::error!("[CLIENT] {:?} Unparsed data: {:?}", packet, rem);
-                    }
-                    Err(e) => {
-                        tracing::error!("[CLIENT] 0x{:02x} - Size: {:?} - Error: {:?}", packet.id.0, packet.data.len(), e);
-                    }
-                };
+        match protocol::play::server::Play::parse(packet.id, &packet.data) {
+            Ok((rem, packet)) if rem.is_empty() => {
+                // tracing::info!("[SERVER] {:#?}", packet);
+            }
+            Ok((rem, packet)) => {
+                tracing::error!("[CLIENT] {:?} Unparsed data: {:?}", packet, rem);
+            }
+            Err(e) => {
+                tracing::error!(
+                    "[CLIENT] 0x{:02x} - Size: {:?} - Error: {:?}",
+                    packet.id.0,
+                    packet.data.len(),
+                    e
+                );
+            }
+        };
 
-                if let Err(e) = target.send_rawpacket(&packet).await {
-                    tracing::error!("Forwaring to server: {:?}", e);
-                    return;
-                }
+        if let Err(e) = target.send_rawpacket(&packet).await {
+            log_error("Server", &e);
+            return;
+        }
+    }
+}
+
+async fn forward_to_client<R, W>(mut target: Connection<R>, mut connection: Connection<W>)
+where
+    R: TransportReceive,
+    W: TransportSend,
+{
+    loop {
+        let packet = match target.recv_rawpacket().await {
+            Ok(packet) => packet,
+            Err(e) => {
+                server_lost(connection, &e).await;
+                return;
             }
-            server_packet = target.recv_rawpacket() => {
-                let packet = match server_packet {
-                    Ok(packet) => packet,
-                    Err(e) => {
-                        tracing::error!("Receiving from Server: {:?}", e);
-                        return;
-                    }
-                };
+        };
 
-                use protocol::packet::PacketContent;
-
-                let id = packet.id;
-                let data = &packet.data;
-                match protocol::play::client::Play::parse(packet.id, &packet.data) {
-                    Ok((rem, packet)) if rem.is_empty() => {
-                        match packet {
-                            protocol::play::client::Play::Login(p) => {
-                                tracing::info!("Login Packet: {:?}", p);
-                                let mut tmp = vec![0; 1024];
-                                let serialized = p.serialize(&mut tmp).unwrap();
-
-                                let (_, tmp) = protocol::play::client::Play::parse(id, &tmp).unwrap();
-                                assert_eq!(protocol::play::client::Play::Login(p), tmp);
-                                // assert_eq!(data, serialized);
-                            }
-                            other => {
-                                // tracing::info!("[SERVER] {:#?}", packet);
-                            }
-                        };
+        use protocol::packet::PacketContent;
+
+        let id = packet.id;
+        let data = &packet.data;
+        match protocol::play::client::Play::parse(packet.id, &packet.data) {
+            Ok((rem, packet)) if rem.is_empty() => {
+                match packet {
+                    protocol::play::client::Play::Login(p) => {
+                        tracing::info!("Login Packet: {:?}", p);
+                        let mut tmp = vec![0; 1024];
+                        let serialized = p.serialize(&mut tmp).unwrap();
+
+                        let (_, tmp) = protocol::play::client::Play::parse(id, &tmp).unwrap();
+                        assert_eq!(protocol::play::client::Play::Login(p), tmp);
+                        // assert_eq!(data, serialized);
                     }
-                    Ok((rem, _packet)) => {
-                        tracing::error!("[SERVER -> Client] 0x{:02x} - Unparsed Data: {:?}", packet.id.0, rem.len());
+                    protocol::play::client::Play::ChunkDataAndUpdateLight(chunk) => {
+                        let blocks: i32 = chunk
+                            .data
+                            .sections
+                            .iter()
+                            .map(|s| s.block_count as i32)
+                            .sum();
+                        tracing::debug!(
+                            x = chunk.chunk_x,
+                            z = chunk.chunk_z,
+                            sections = chunk.data.sections.len(),
+                            blocks,
+                            "Chunk"
+                        );
                     }
-                    Err(e) => {
-                        tracing::error!("[SERVER -> Client] 0x{:02x} - Size: {:?} - Error: {:?}", packet.id.0, packet.data.len(), e);
+                    other => {
+                        // tracing::info!("[SERVER] {:#?}", packet);
                     }
                 };
-
-                if let Err(e) = connection.send_rawpacket(&packet).await {
-                    tracing::error!("Forwaring to client: {:?}", e);
-                    return;
-                }
             }
+            Ok((rem, _packet)) => {
+                tracing::error!(
+                    "[SERVER -> Client] 0x{:02x} - Unparsed Data: {:?}",
+                    packet.id.0,
+                    rem.len()
+                );
+            }
+            Err(e) => {
+                tracing::error!(
+                    "[SERVER -> Client] 0x{:02x} - Size: {:?} - Error: {:?}",
+                    packet.id.0,
+                    packet.data.len(),
+                    e
+                );
+            }
+        };
+
+        if let Err(e) = connection.send_rawpacket(&packet).await {
+            log_error("User", &e);
+            return;
         }
     }
 }
+
+/// Logs the error of one side of the proxied connection, closed connections are expected and
+/// not logged as errors
+fn log_error(peer: &str, error: &NetworkError) {
+    match error {
+        NetworkError::Eof => tracing::info!("{} closed the connection", peer),
+        NetworkError::Timeout => tracing::info!("{} timed out", peer),
+        NetworkError::Io(e) => tracing::warn!("{} connection failed: {:?}", peer, e),
+        NetworkError::Parse(_) | NetworkError::FrameTooLarge { .. } => {
+            tracing::warn!("{} send an invalid Packet: {:?}", peer, error)
+        }
+        NetworkError::Crypto(e) => tracing::error!("{} encryption failed: {:?}", peer, e),
+    }
+}
+
+/// Logs the failure of the connection to the backend and tells the player that the connection
+/// was lost
+async fn server_lost<S>(connection: Connection<S>, error: &NetworkError)
+where
+    S: TransportSend,
+{
+    log_error("Server", error);
+
+    let reason = protocol::general::TextComponent::translate("disconnect.lost", Vec::new());
+    if let Err(e) = connection.disconnect(reason).await {
+        log_error("User", &e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn forwards_player_info() {
+        let (mut proxy, mut backend) = networking::memory::pair(4096, ConnectionConfig::default());
+        proxy.set_state(ConnectionState::Login);
+        backend.set_state(ConnectionState::Login);
+
+        let player = auth::forwarding::ForwardedPlayer {
+            address: "127.0.0.1".to_string(),
+            uuid: 0x069a79f4_44e9_4726_a5be_fca90e38aaf5,
+            name: "Notch".to_string(),
+            properties: vec![protocol::login::client::Property {
+                name: protocol::general::PString("textures".into()),
+                value: protocol::general::PString("skin".into()),
+                signature: Some(protocol::general::PString("signature".into())),
+            }],
+        };
+
+        let backend = async move {
+            backend
+                .send_packet(&protocol::packet::Packet {
+                    inner: auth::forwarding::request(3),
+                })
+                .await
+                .unwrap();
+            let response = backend
+                .recv_packet(protocol::login::server::LoginPluginResponse::parse)
+                .await
+                .unwrap();
+            assert_eq!(3, response.inner.message_id.0);
+            auth::forwarding::verify(b"secret", &response.inner).unwrap()
+        };
+
+        let (forwarded, result) =
+            tokio::join!(backend, forward_player_info(&mut proxy, b"secret", &player));
+        result.unwrap();
+        assert_eq!(player, forwarded);
+    }
+
+    #[tokio::test]
+    async fn backend_closed_during_login() {
+        let (mut client, mut connection) =
+            networking::memory::pair(4096, ConnectionConfig::default());
+        let (target, backend) = networking::memory::pair(4096, ConnectionConfig::default());
+        client.set_state(ConnectionState::Login);
+        connection.set_state(ConnectionState::Login);
+
+        // The backend closes the connection instead of sending the login success
+        drop(backend);
+        forward_login(connection, target).await;
+
+        let disconnect = client
+            .recv_packet(protocol::login::client::Disconnect::parse)
+            .await
+            .unwrap();
+        assert!(disconnect.inner.reason.0.contains("disconnect.lost"));
+    }
+}
@@ -0,0 +1,195 @@
+//! Generates the registries from the vanilla data reports in `data/`.
+//!
+//! The reports are created by the vanilla server using
+//! `java -DbundlerMainClass=net.minecraft.data.Main -jar server.jar --reports`
+
+use std::{collections::HashMap, fmt::Write, path::Path};
+
+/// The registries from `data/registries.json` with the names of the generated enums
+const REGISTRIES: [(&str, &str); 8] = [
+    ("minecraft:enchantment", "Enchantment"),
+    ("minecraft:entity_type", "EntityType"),
+    ("minecraft:fluid", "Fluid"),
+    ("minecraft:game_event", "GameEvent"),
+    ("minecraft:item", "Item"),
+    ("minecraft:menu", "Menu"),
+    ("minecraft:particle_type", "ParticleType"),
+    ("minecraft:sound_event", "SoundEvent"),
+];
+
+#[derive(Debug, serde::Deserialize)]
+struct RegistryReport {
+    entries: HashMap<String, EntryReport>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct EntryReport {
+    protocol_id: i32,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct BlockReport {
+    #[serde(default)]
+    properties: HashMap<String, Vec<String>>,
+    states: Vec<StateReport>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct StateReport {
+    id: u16,
+    #[serde(default)]
+    properties: HashMap<String, String>,
+    #[serde(default)]
+    default: bool,
+}
+
+/// Turns `minecraft:oak_stairs` into `OakStairs` and `minecraft:music_disc.11` into
+/// `MusicDisc11`
+fn variant_name(name: &str) -> String {
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+
+    let mut result = String::new();
+    for part in name.split(['_', '.', '/']) {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            result.extend(first.to_uppercase());
+            result.extend(chars);
+        }
+    }
+    if result.starts_with(|c: char| c.is_ascii_digit()) {
+        result.insert(0, '_');
+    }
+    result
+}
+
+fn generate_blocks(out_dir: &Path) {
+    let content = std::fs::read_to_string("data/blocks.json").expect("Reading blocks report");
+    let report: HashMap<String, BlockReport> =
+        serde_json::from_str(&content).expect("Parsing blocks report");
+
+    let mut blocks: Vec<(String, BlockReport)> = report.into_iter().collect();
+    blocks.sort_by_key(|(_, block)| block.states.iter().map(|s| s.id).min());
+
+    let mut variants = String::new();
+    let mut infos = String::new();
+    let mut next_state = 0u16;
+
+    for (name, block) in blocks.iter() {
+        // The report contains the properties unordered, but the states are ordered by the
+        // properties sorted by name with the last one changing the fastest
+        let mut properties: Vec<(&String, &Vec<String>)> = block.properties.iter().collect();
+        properties.sort_by_key(|(name, _)| name.as_str());
+
+        let expected = properties.iter().map(|(_, v)| v.len()).product::<usize>();
+        assert_eq!(expected, block.states.len(), "Number of states of {name}");
+
+        let first_state = next_state;
+        let mut default_state = None;
+        for (offset, state) in block.states.iter().enumerate() {
+            assert_eq!(
+                first_state as usize + offset,
+                state.id as usize,
+                "State IDs of {name}"
+            );
+
+            let mut remaining = offset;
+            for (property, values) in properties.iter().rev() {
+                let value = &values[remaining % values.len()];
+                remaining /= values.len();
+                assert_eq!(
+                    Some(value),
+                    state.properties.get(*property),
+                    "Property {property} of state {}",
+                    state.id
+                );
+            }
+
+            if state.default {
+                default_state = Some(state.id);
+            }
+        }
+        next_state += block.states.len() as u16;
+
+        let variant = variant_name(name);
+        writeln!(variants, "    {variant},").unwrap();
+
+        let mut property_list = String::new();
+        for (property, values) in properties.iter() {
+            write!(
+                property_list,
+                "Property {{ name: {property:?}, values: &{values:?} }}, "
+            )
+            .unwrap();
+        }
+        writeln!(
+            infos,
+            "    BlockInfo {{ block: Block::{variant}, name: {name:?}, first_state: {first_state}, default_state: {}, properties: &[{property_list}] }},",
+            default_state.unwrap_or_else(|| panic!("{name} has no default state"))
+        )
+        .unwrap();
+    }
+
+    let mut code = String::new();
+    writeln!(code, "/// All the blocks in the order of their numeric IDs").unwrap();
+    writeln!(
+        code,
+        "#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]"
+    )
+    .unwrap();
+    writeln!(code, "pub enum Block {{\n{variants}}}\n").unwrap();
+    writeln!(code, "/// The number of block states").unwrap();
+    writeln!(code, "pub const STATE_COUNT: u16 = {next_state};\n").unwrap();
+    writeln!(
+        code,
+        "static BLOCKS: [BlockInfo; {}] = [\n{infos}];",
+        blocks.len()
+    )
+    .unwrap();
+
+    std::fs::write(out_dir.join("blocks.rs"), code).expect("Writing generated blocks");
+}
+
+fn generate_registries(out_dir: &Path) {
+    let content =
+        std::fs::read_to_string("data/registries.json").expect("Reading registries report");
+    let report: HashMap<String, RegistryReport> =
+        serde_json::from_str(&content).expect("Parsing registries report");
+
+    let mut code = String::new();
+    for (registry, enum_name) in REGISTRIES {
+        let entries = &report
+            .get(registry)
+            .unwrap_or_else(|| panic!("Registry {registry} is missing"))
+            .entries;
+
+        let mut entries: Vec<(&String, i32)> = entries
+            .iter()
+            .map(|(name, entry)| (name, entry.protocol_id))
+            .collect();
+        entries.sort_by_key(|(_, id)| *id);
+
+        let mut variants = String::new();
+        for (idx, (name, id)) in entries.iter().enumerate() {
+            assert_eq!(idx as i32, *id, "Protocol IDs of {registry}");
+            writeln!(variants, "    ({}, {name:?}),", variant_name(name)).unwrap();
+        }
+
+        writeln!(
+            code,
+            "registry_enum!({enum_name}, {registry:?}, [\n{variants}]);\n"
+        )
+        .unwrap();
+    }
+
+    std::fs::write(out_dir.join("registries.rs"), code).expect("Writing generated registries");
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=data/blocks.json");
+    println!("cargo:rerun-if-changed=data/registries.json");
+
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+    generate_blocks(Path::new(&out_dir));
+    generate_registries(Path::new(&out_dir));
+}
@@ -0,0 +1,261 @@
+//! The blocks and their states
+//!
+//! Every combination of the property values of a block is its own [`BlockState`], which is
+//! identified by a global numeric ID in the protocol, for example in chunks and block updates.
+
+use std::fmt;
+
+include!(concat!(env!("OUT_DIR"), "/blocks.rs"));
+
+/// A property of a block, like `facing` or `waterlogged`
+#[derive(Debug, PartialEq, Eq)]
+pub struct Property {
+    pub name: &'static str,
+    /// All the possible values, in the order used for the state IDs
+    pub values: &'static [&'static str],
+}
+
+#[derive(Debug)]
+struct BlockInfo {
+    block: Block,
+    name: &'static str,
+    first_state: u16,
+    default_state: u16,
+    /// Sorted by name
+    properties: &'static [Property],
+}
+
+impl Block {
+    fn info(self) -> &'static BlockInfo {
+        &BLOCKS[self as usize]
+    }
+
+    /// The numeric ID of the block
+    pub fn id(self) -> u16 {
+        self as u16
+    }
+
+    pub fn from_id(id: u16) -> Option<Self> {
+        BLOCKS.get(id as usize).map(|info| info.block)
+    }
+
+    /// The namespaced name, like `minecraft:stone`
+    pub fn name(self) -> &'static str {
+        self.info().name
+    }
+
+    /// Looks up a block by its name, the `minecraft:` namespace is optional
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+        BLOCKS
+            .iter()
+            .find(|info| &info.name["minecraft:".len()..] == name)
+            .map(|info| info.block)
+    }
+
+    /// All the blocks in the order of their IDs
+    pub fn all() -> impl Iterator<Item = Self> {
+        BLOCKS.iter().map(|info| info.block)
+    }
+
+    pub fn properties(self) -> &'static [Property] {
+        self.info().properties
+    }
+
+    /// The state used when placing the block without any context
+    pub fn default_state(self) -> BlockState {
+        BlockState(self.info().default_state)
+    }
+
+    /// All the states of the block
+    pub fn states(self) -> impl Iterator<Item = BlockState> {
+        let first = self.info().first_state;
+        let count: usize = self.properties().iter().map(|p| p.values.len()).product();
+        (first..first + count as u16).map(BlockState)
+    }
+}
+
+/// A single state of a block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BlockState(u16);
+
+impl BlockState {
+    pub const AIR: Self = Self(0);
+
+    /// The ID of the state used in the protocol
+    pub fn id(self) -> u16 {
+        self.0
+    }
+
+    pub fn from_id(id: u16) -> Option<Self> {
+        (id < STATE_COUNT).then_some(Self(id))
+    }
+
+    pub fn block(self) -> Block {
+        let idx = BLOCKS.partition_point(|info| info.first_state <= self.0);
+        BLOCKS[idx - 1].block
+    }
+
+    pub fn is_air(self) -> bool {
+        matches!(self.block(), Block::Air | Block::CaveAir | Block::VoidAir)
+    }
+
+    pub fn is_default(self) -> bool {
+        self.block().default_state() == self
+    }
+
+    /// The index of the value for every property, in the order of the properties of the block
+    fn value_indices(self) -> impl Iterator<Item = (&'static Property, usize)> {
+        let info = self.block().info();
+
+        // The last property changes the fastest, so the stride of a property is the product of the
+        // number of values of all the following properties
+        let mut stride: usize = info.properties.iter().map(|p| p.values.len()).product();
+        let offset = (self.0 - info.first_state) as usize;
+        info.properties.iter().map(move |property| {
+            stride /= property.values.len();
+            (property, (offset / stride) % property.values.len())
+        })
+    }
+
+    /// The value of the property, like `north` for `facing`
+    pub fn get(self, property: &str) -> Option<&'static str> {
+        self.value_indices()
+            .find(|(p, _)| p.name == property)
+            .map(|(p, idx)| p.values[idx])
+    }
+
+    /// All the properties with their values
+    pub fn properties(self) -> impl Iterator<Item = (&'static str, &'static str)> {
+        self.value_indices().map(|(p, idx)| (p.name, p.values[idx]))
+    }
+
+    /// The state of the same block with the property set to the value, returns `None` if the
+    /// block does not have the property or the value is not valid for it
+    pub fn with(self, property: &str, value: &str) -> Option<Self> {
+        let info = self.block().info();
+
+        let mut stride = 1;
+        for p in info.properties.iter().rev() {
+            if p.name == property {
+                let new = p.values.iter().position(|v| *v == value)?;
+                let current = ((self.0 - info.first_state) as usize / stride) % p.values.len();
+                let id = self.0 as usize - current * stride + new * stride;
+                return Some(Self(id as u16));
+            }
+            stride *= p.values.len();
+        }
+
+        None
+    }
+}
+
+impl Default for BlockState {
+    fn default() -> Self {
+        Self::AIR
+    }
+}
+
+impl From<Block> for BlockState {
+    fn from(value: Block) -> Self {
+        value.default_state()
+    }
+}
+
+/// Formats the state like the vanilla commands, `minecraft:oak_stairs[facing=north,...]`
+impl fmt::Display for BlockState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.block().name())?;
+
+        let mut properties = self.properties().peekable();
+        if properties.peek().is_some() {
+            write!(f, "[")?;
+            for (idx, (name, value)) in properties.enumerate() {
+                if idx > 0 {
+                    write!(f, ",")?;
+                }
+                write!(f, "{name}={value}")?;
+            }
+            write!(f, "]")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_ids() {
+        assert_eq!(0, Block::Air.default_state().id());
+        assert_eq!(1, Block::Stone.default_state().id());
+        assert_eq!(10, Block::Dirt.default_state().id());
+        assert_eq!(80, Block::Water.default_state().id());
+        assert_eq!(
+            Some(Block::GrassBlock),
+            Block::from_name("minecraft:grass_block")
+        );
+        assert_eq!(Some(Block::GrassBlock), Block::from_name("grass_block"));
+        assert_eq!(Some(Block::Dirt), Block::from_id(Block::Dirt.id()));
+    }
+
+    #[test]
+    fn state_to_block() {
+        for block in [
+            Block::Air,
+            Block::GrassBlock,
+            Block::OakStairs,
+            Block::HeavyCore,
+        ] {
+            for state in block.states() {
+                assert_eq!(block, state.block());
+            }
+        }
+
+        assert_eq!(None, BlockState::from_id(STATE_COUNT));
+        let last = BlockState::from_id(STATE_COUNT - 1).unwrap();
+        assert_eq!(Block::all().last(), Some(last.block()));
+    }
+
+    #[test]
+    fn properties() {
+        let grass = Block::GrassBlock.default_state();
+        assert_eq!(9, grass.id());
+        assert_eq!(Some("false"), grass.get("snowy"));
+        assert_eq!(8, grass.with("snowy", "true").unwrap().id());
+
+        let stairs = Block::OakStairs.default_state();
+        assert_eq!(Some("north"), stairs.get("facing"));
+        assert_eq!(Some("bottom"), stairs.get("half"));
+
+        let changed = stairs
+            .with("facing", "east")
+            .and_then(|s| s.with("waterlogged", "true"))
+            .unwrap();
+        assert_eq!(Block::OakStairs, changed.block());
+        assert_eq!(Some("east"), changed.get("facing"));
+        assert_eq!(Some("true"), changed.get("waterlogged"));
+        assert_eq!(Some("bottom"), changed.get("half"));
+        assert_eq!(None, stairs.with("facing", "up"));
+        assert_eq!(None, stairs.with("level", "0"));
+
+        assert_eq!(
+            Some(95),
+            Block::Water
+                .default_state()
+                .with("level", "15")
+                .map(|s| s.id())
+        );
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!("minecraft:stone", Block::Stone.default_state().to_string());
+        assert_eq!(
+            "minecraft:oak_stairs[facing=north,half=bottom,shape=straight,waterlogged=false]",
+            Block::OakStairs.default_state().to_string()
+        );
+    }
+}
@@ -0,0 +1,10 @@
+//! # Registry
+//! The vanilla registries, generated at build time from the data reports in `data/`
+
+pub mod blocks;
+pub use blocks::{Block, BlockState};
+
+pub mod registries;
+pub use registries::{
+    Enchantment, EntityType, Fluid, GameEvent, Item, Menu, ParticleType, SoundEvent,
+};
@@ -0,0 +1,88 @@
+//! The simple registries, which only map names to numeric IDs
+//!
+//! Each registry is an enum with the entries in the order of their protocol IDs, so they can be
+//! used instead of the raw numbers in packets, like the item in a slot or the type of a spawned
+//! entity.
+
+macro_rules! registry_enum {
+    ($name:ident, $registry:literal, [$(($variant:ident, $entry:literal)),* $(,)?]) => {
+        #[doc = concat!("The entries of the `", $registry, "` registry")]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub enum $name {
+            $($variant),*
+        }
+
+        impl $name {
+            /// The name of the registry
+            pub const REGISTRY: &'static str = $registry;
+
+            const ENTRIES: &'static [(Self, &'static str)] = &[$((Self::$variant, $entry)),*];
+
+            /// The numeric ID used in the protocol
+            pub fn id(self) -> i32 {
+                self as i32
+            }
+
+            pub fn from_id(id: i32) -> Option<Self> {
+                let idx = usize::try_from(id).ok()?;
+                Self::ENTRIES.get(idx).map(|(entry, _)| *entry)
+            }
+
+            /// The namespaced name of the entry
+            pub fn name(self) -> &'static str {
+                Self::ENTRIES[self as usize].1
+            }
+
+            /// Looks up an entry by its name, the `minecraft:` namespace is optional
+            pub fn from_name(name: &str) -> Option<Self> {
+                let name = name.strip_prefix("minecraft:").unwrap_or(name);
+                Self::ENTRIES
+                    .iter()
+                    .find(|(_, entry)| &entry["minecraft:".len()..] == name)
+                    .map(|(entry, _)| *entry)
+            }
+
+            /// All the entries in the order of their IDs
+            pub fn all() -> impl Iterator<Item = Self> {
+                Self::ENTRIES.iter().map(|(entry, _)| *entry)
+            }
+        }
+    };
+}
+
+include!(concat!(env!("OUT_DIR"), "/registries.rs"));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_and_names() {
+        assert_eq!(0, Item::Air.id());
+        assert_eq!("minecraft:stone", Item::Stone.name());
+        assert_eq!(Some(Item::Stone), Item::from_id(1));
+        assert_eq!(Some(Item::DiamondSword), Item::from_name("diamond_sword"));
+        assert_eq!(None, Item::from_id(-1));
+        assert_eq!(None, Item::from_name("minecraft:not_an_item"));
+
+        assert_eq!("minecraft:entity_type", EntityType::REGISTRY);
+        assert_eq!(
+            Some(EntityType::Zombie),
+            EntityType::from_name("minecraft:zombie")
+        );
+        assert_eq!(
+            Some(SoundEvent::MusicDisc11),
+            SoundEvent::from_name("minecraft:music_disc.11")
+        );
+        assert_eq!(2, Fluid::Water.id());
+    }
+
+    #[test]
+    fn roundtrip() {
+        for item in Item::all() {
+            assert_eq!(Some(item), Item::from_id(item.id()));
+            assert_eq!(Some(item), Item::from_name(item.name()));
+        }
+        assert_eq!(Menu::all().count(), Menu::ENTRIES.len());
+    }
+}
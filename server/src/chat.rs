@@ -0,0 +1,733 @@
+//! The signed player chat
+//!
+//! A player with a chat session signs every message with their key. The signature covers the
+//! message itself, its position in the chain of messages of the player and the signatures of the
+//! last messages the player has seen, which are tracked per player by the
+//! [`LastSeenValidator`].
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use openssl::{
+    hash::MessageDigest,
+    pkey::{PKey, Private, Public},
+    sign::{Signer, Verifier},
+};
+use protocol::{
+    chat::{
+        ChatSession, FilterMask, LastSeenUpdate, MessageSignature, PackedSignature,
+        LAST_SEEN_MESSAGES,
+    },
+    general::{PString, TextComponent, VarInt},
+    play::{
+        client::{DisguisedChatMessage, PlayerChatMessage},
+        server::ChatMessage,
+    },
+};
+
+#[derive(Debug, PartialEq)]
+pub enum ChatError {
+    /// The public key of the session could not be decoded
+    InvalidPublicKey,
+    ExpiredPublicKey,
+    /// The public key was not signed by the given services key
+    InvalidKeySignature,
+    /// The message has no signature, but the player has a session or secure chat is enforced
+    MissingSignature,
+    InvalidSignature,
+    /// The message is older than the previous message of the player
+    OutOfOrder,
+    /// The client acknowledged more messages than it could have received
+    InvalidOffset {
+        offset: i32,
+        max: usize,
+    },
+    /// The client acknowledged a message that was never send or already ignored
+    UnknownMessage(usize),
+    /// The client ignored a message it already acknowledged
+    IgnoredAcknowledged(usize),
+}
+
+fn epoch_millis(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// A validated chat session of a player
+#[derive(Debug, Clone)]
+pub struct PlayerSession {
+    player: u128,
+    session: ChatSession,
+    key: PKey<Public>,
+}
+
+impl PlayerSession {
+    /// Decodes the public key of the session and checks that it has not expired
+    pub fn new(player: u128, session: ChatSession, now: SystemTime) -> Result<Self, ChatError> {
+        let key = PKey::public_key_from_der(&session.public_key)
+            .map_err(|_| ChatError::InvalidPublicKey)?;
+
+        if session.expires_at < epoch_millis(now) {
+            return Err(ChatError::ExpiredPublicKey);
+        }
+
+        Ok(Self {
+            player,
+            session,
+            key,
+        })
+    }
+
+    pub fn session(&self) -> &ChatSession {
+        &self.session
+    }
+
+    /// Checks that the key was signed using the key of the Mojang services.
+    ///
+    /// The signed data is the UUID of the player, the expiry time and the encoded public key.
+    pub fn verify_key_signature(&self, services_key: &PKey<Public>) -> Result<(), ChatError> {
+        let mut data = Vec::with_capacity(16 + 8 + self.session.public_key.len());
+        data.extend(self.player.to_be_bytes());
+        data.extend(self.session.expires_at.to_be_bytes());
+        data.extend(&self.session.public_key);
+
+        let valid = Verifier::new(MessageDigest::sha1(), services_key)
+            .and_then(|mut v| v.verify_oneshot(&self.session.key_signature, &data))
+            .unwrap_or(false);
+        if !valid {
+            return Err(ChatError::InvalidKeySignature);
+        }
+
+        Ok(())
+    }
+}
+
+/// The data covered by the signature of a chat message
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedMessage {
+    pub sender: u128,
+    pub session_id: u128,
+    /// The number of messages the sender signed before in this session
+    pub index: i32,
+    pub salt: i64,
+    /// In milliseconds since the unix epoch
+    pub timestamp: i64,
+    pub content: String,
+    /// The signatures of the messages the sender acknowledged with this message
+    pub last_seen: Vec<MessageSignature>,
+}
+
+impl SignedMessage {
+    /// The signature format version
+    const VERSION: i32 = 1;
+
+    /// The bytes that are signed, all the numbers are big endian
+    pub fn signed_data(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend(Self::VERSION.to_be_bytes());
+
+        data.extend(self.sender.to_be_bytes());
+        data.extend(self.session_id.to_be_bytes());
+        data.extend(self.index.to_be_bytes());
+
+        data.extend(self.salt.to_be_bytes());
+        // Only the seconds are included in the signature
+        data.extend((self.timestamp / 1000).to_be_bytes());
+        data.extend((self.content.len() as i32).to_be_bytes());
+        data.extend(self.content.as_bytes());
+        data.extend((self.last_seen.len() as i32).to_be_bytes());
+        for signature in self.last_seen.iter() {
+            data.extend(signature.0.as_slice());
+        }
+
+        data
+    }
+
+    /// Creates the signature the same way the client does
+    pub fn sign(&self, key: &PKey<Private>) -> Option<MessageSignature> {
+        let mut signer = Signer::new(MessageDigest::sha256(), key).ok()?;
+        let raw = signer.sign_oneshot_to_vec(&self.signed_data()).ok()?;
+        let raw: Box<[u8; 256]> = raw.into_boxed_slice().try_into().ok()?;
+        Some(MessageSignature(raw))
+    }
+
+    pub fn verify(&self, key: &PKey<Public>, signature: &MessageSignature) -> bool {
+        Verifier::new(MessageDigest::sha256(), key)
+            .and_then(|mut v| v.verify_oneshot(signature.0.as_slice(), &self.signed_data()))
+            .unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TrackedMessage {
+    signature: MessageSignature,
+    /// Not yet acknowledged or ignored by the client
+    pending: bool,
+}
+
+/// Tracks the signed messages send to a player, which the player acknowledges with its next
+/// messages
+#[derive(Debug, Clone)]
+pub struct LastSeenValidator {
+    /// Always contains at least the window of the last [`LAST_SEEN_MESSAGES`] entries
+    tracked: Vec<Option<TrackedMessage>>,
+    last_pending: Option<MessageSignature>,
+}
+
+impl Default for LastSeenValidator {
+    fn default() -> Self {
+        Self {
+            tracked: vec![None; LAST_SEEN_MESSAGES],
+            last_pending: None,
+        }
+    }
+}
+
+impl LastSeenValidator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a signed message that was send to the player
+    pub fn add_pending(&mut self, signature: MessageSignature) {
+        if self.last_pending.as_ref() == Some(&signature) {
+            return;
+        }
+
+        self.tracked.push(Some(TrackedMessage {
+            signature: signature.clone(),
+            pending: true,
+        }));
+        self.last_pending = Some(signature);
+    }
+
+    /// The number of messages that were not yet moved out of the window
+    pub fn tracked_count(&self) -> usize {
+        self.tracked.len()
+    }
+
+    /// Moves the window forward by the number of messages the client received
+    pub fn apply_offset(&mut self, offset: i32) -> Result<(), ChatError> {
+        let max = self.tracked.len() - LAST_SEEN_MESSAGES;
+        if offset < 0 || offset as usize > max {
+            return Err(ChatError::InvalidOffset { offset, max });
+        }
+
+        self.tracked.drain(..offset as usize);
+        Ok(())
+    }
+
+    /// Applies the update of the client and returns the signatures of the acknowledged messages,
+    /// which are part of the signed data of the message
+    pub fn apply_update(
+        &mut self,
+        update: &LastSeenUpdate,
+    ) -> Result<Vec<MessageSignature>, ChatError> {
+        // The bitset has room for a few more messages than the window, which can not be
+        // acknowledged
+        let outside = update.acknowledged >> LAST_SEEN_MESSAGES;
+        if outside != 0 {
+            return Err(ChatError::UnknownMessage(
+                LAST_SEEN_MESSAGES + outside.trailing_zeros() as usize,
+            ));
+        }
+
+        self.apply_offset(update.offset.0)?;
+
+        let mut acknowledged = Vec::new();
+        for (idx, entry) in self.tracked[..LAST_SEEN_MESSAGES].iter_mut().enumerate() {
+            if update.is_acknowledged(idx) {
+                let message = entry.as_mut().ok_or(ChatError::UnknownMessage(idx))?;
+                message.pending = false;
+                acknowledged.push(message.signature.clone());
+            } else {
+                if entry.as_ref().is_some_and(|m| !m.pending) {
+                    return Err(ChatError::IgnoredAcknowledged(idx));
+                }
+                *entry = None;
+            }
+        }
+
+        Ok(acknowledged)
+    }
+}
+
+/// A chat message received from a player, after it was validated
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceivedMessage {
+    pub message: SignedMessage,
+    /// Only messages from players with a chat session are signed
+    pub signature: Option<MessageSignature>,
+}
+
+impl ReceivedMessage {
+    /// The packet to send the message to other players
+    pub fn packet(
+        &self,
+        chat_type: &ChatType,
+        sender_name: TextComponent,
+        target_name: Option<TextComponent>,
+    ) -> PlayerChatMessage {
+        PlayerChatMessage {
+            sender: self.message.sender,
+            index: VarInt(self.message.index),
+            signature: self.signature.clone(),
+            message: PString(self.message.content.clone().into()),
+            timestamp: self.message.timestamp,
+            salt: self.message.salt,
+            previous_messages: self
+                .message
+                .last_seen
+                .iter()
+                .map(|s| PackedSignature::Full(s.clone()))
+                .collect(),
+            unsigned_content: None,
+            filter: FilterMask::PassThrough,
+            chat_type: VarInt(chat_type.id),
+            sender_name,
+            target_name,
+        }
+    }
+}
+
+/// The chat state of a single player
+#[derive(Debug, Clone)]
+pub struct ChatState {
+    player: u128,
+    enforce_secure_chat: bool,
+    services_keys: Vec<PKey<Public>>,
+    session: Option<PlayerSession>,
+    next_index: i32,
+    last_timestamp: i64,
+    last_seen: LastSeenValidator,
+}
+
+impl ChatState {
+    pub fn new(player: u128, enforce_secure_chat: bool) -> Self {
+        Self {
+            player,
+            enforce_secure_chat,
+            services_keys: Vec::new(),
+            session: None,
+            next_index: 0,
+            last_timestamp: i64::MIN,
+            last_seen: LastSeenValidator::new(),
+        }
+    }
+
+    /// Only accept sessions whose key was signed by one of the keys of the Mojang services
+    pub fn services_keys(mut self, keys: Vec<PKey<Public>>) -> Self {
+        self.services_keys = keys;
+        self
+    }
+
+    pub fn session(&self) -> Option<&PlayerSession> {
+        self.session.as_ref()
+    }
+
+    pub fn enforces_secure_chat(&self) -> bool {
+        self.enforce_secure_chat
+    }
+
+    /// Starts a new chat session, which restarts the chain of messages
+    pub fn set_session(&mut self, session: ChatSession, now: SystemTime) -> Result<(), ChatError> {
+        let session = PlayerSession::new(self.player, session, now)?;
+        if !self.services_keys.is_empty()
+            && !self
+                .services_keys
+                .iter()
+                .any(|key| session.verify_key_signature(key).is_ok())
+        {
+            return Err(ChatError::InvalidKeySignature);
+        }
+
+        self.session = Some(session);
+        self.next_index = 0;
+        Ok(())
+    }
+
+    /// Handles a Message Acknowledgment packet
+    pub fn acknowledge(&mut self, message_count: VarInt) -> Result<(), ChatError> {
+        self.last_seen.apply_offset(message_count.0)
+    }
+
+    /// Records a signed message that was send to the player
+    pub fn track_sent(&mut self, signature: MessageSignature) {
+        self.last_seen.add_pending(signature);
+    }
+
+    /// Validates a chat message of the player
+    pub fn handle_message(&mut self, packet: &ChatMessage) -> Result<ReceivedMessage, ChatError> {
+        let last_seen = self.last_seen.apply_update(&packet.last_seen)?;
+
+        if packet.timestamp < self.last_timestamp {
+            return Err(ChatError::OutOfOrder);
+        }
+        self.last_timestamp = packet.timestamp;
+
+        let message = SignedMessage {
+            sender: self.player,
+            session_id: self
+                .session
+                .as_ref()
+                .map(|s| s.session.session_id)
+                .unwrap_or(0),
+            index: self.next_index,
+            salt: packet.salt,
+            timestamp: packet.timestamp,
+            content: packet.message.0.to_string(),
+            last_seen,
+        };
+
+        let signature = match (&self.session, &packet.signature) {
+            (Some(session), Some(signature)) => {
+                if !message.verify(&session.key, signature) {
+                    return Err(ChatError::InvalidSignature);
+                }
+                self.next_index += 1;
+                Some(signature.clone())
+            }
+            (None, None) if !self.enforce_secure_chat => None,
+            _ => return Err(ChatError::MissingSignature),
+        };
+
+        Ok(ReceivedMessage { message, signature })
+    }
+}
+
+/// A chat type from the `minecraft:chat_type` registry, which defines how the client displays
+/// messages
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatType {
+    /// The numeric ID in the registry
+    pub id: i32,
+    pub translation_key: String,
+    /// The arguments inserted into the translation, `sender`, `target` or `content`
+    pub parameters: Vec<String>,
+    pub style: Vec<(String, nbt::Tag)>,
+}
+
+impl ChatType {
+    /// Loads the chat type from the registry data
+    pub fn from_registry(name: &str) -> Option<Self> {
+        let id = crate::data::registry::entry_id("minecraft:chat_type", name)?;
+        let chat = crate::data::registry::entry("minecraft:chat_type", name)?.get("chat")?;
+
+        let translation_key = chat.get("translation_key")?.as_str()?.to_string();
+        let parameters = chat
+            .get("parameters")?
+            .as_array()?
+            .iter()
+            .filter_map(|p| p.as_str().map(|p| p.to_string()))
+            .collect();
+        let style = chat
+            .get("style")
+            .and_then(|s| s.as_object())
+            .map(|style| {
+                style
+                    .iter()
+                    .map(|(k, v)| (k.clone(), crate::data::registry::json_to_nbt(v)))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Some(Self {
+            id,
+            translation_key,
+            parameters,
+            style,
+        })
+    }
+
+    /// Formats the message like the client would display it
+    pub fn format(
+        &self,
+        sender: &TextComponent,
+        content: &TextComponent,
+        target: Option<&TextComponent>,
+    ) -> TextComponent {
+        let args = self
+            .parameters
+            .iter()
+            .map(|p| match p.as_str() {
+                "sender" => sender.clone(),
+                "target" => target.cloned().unwrap_or_else(|| TextComponent::text("")),
+                _ => content.clone(),
+            })
+            .collect();
+
+        TextComponent::translate(self.translation_key.clone(), args).styled(self.style.clone())
+    }
+
+    /// A message that is formatted by the client, but without any signature
+    pub fn disguised(
+        &self,
+        content: TextComponent,
+        sender_name: TextComponent,
+        target_name: Option<TextComponent>,
+    ) -> DisguisedChatMessage {
+        DisguisedChatMessage {
+            message: content,
+            chat_type: VarInt(self.id),
+            sender_name,
+            target_name,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use openssl::rsa::Rsa;
+
+    struct Client {
+        key: PKey<Private>,
+        session: ChatSession,
+        index: i32,
+    }
+
+    impl Client {
+        fn new() -> Self {
+            let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+            let session = ChatSession {
+                session_id: 42,
+                expires_at: epoch_millis(SystemTime::now() + Duration::from_secs(3600)),
+                public_key: key.public_key_to_der().unwrap(),
+                key_signature: Vec::new(),
+            };
+
+            Self {
+                key,
+                session,
+                index: 0,
+            }
+        }
+
+        fn message(
+            &mut self,
+            content: &str,
+            timestamp: i64,
+            last_seen: LastSeenUpdate,
+            acknowledged: Vec<MessageSignature>,
+        ) -> ChatMessage {
+            let signed = SignedMessage {
+                sender: 1,
+                session_id: self.session.session_id,
+                index: self.index,
+                salt: 1234,
+                timestamp,
+                content: content.to_string(),
+                last_seen: acknowledged,
+            };
+            self.index += 1;
+
+            ChatMessage {
+                message: PString(content.to_string().into()),
+                timestamp,
+                salt: 1234,
+                signature: signed.sign(&self.key),
+                last_seen,
+            }
+        }
+    }
+
+    fn no_update() -> LastSeenUpdate {
+        LastSeenUpdate {
+            offset: VarInt(0),
+            acknowledged: 0,
+        }
+    }
+
+    #[test]
+    fn signed_messages() {
+        let mut client = Client::new();
+        let mut state = ChatState::new(1, true);
+        state
+            .set_session(client.session.clone(), SystemTime::now())
+            .unwrap();
+
+        let packet = client.message("hello", 1000, no_update(), Vec::new());
+        let received = state.handle_message(&packet).unwrap();
+        assert_eq!(0, received.message.index);
+        assert_eq!(packet.signature, received.signature);
+
+        // The client acknowledges its own message, which the server echoed back
+        state.track_sent(received.signature.clone().unwrap());
+        let update = LastSeenUpdate {
+            offset: VarInt(1),
+            acknowledged: 1 << (LAST_SEEN_MESSAGES - 1),
+        };
+        let seen = vec![received.signature.unwrap()];
+        let packet = client.message("again", 2000, update, seen.clone());
+        let received = state.handle_message(&packet).unwrap();
+        assert_eq!(1, received.message.index);
+        assert_eq!(seen, received.message.last_seen);
+
+        // The message stays acknowledged until it leaves the window
+        let update = LastSeenUpdate {
+            offset: VarInt(0),
+            acknowledged: 1 << (LAST_SEEN_MESSAGES - 1),
+        };
+        let mut tampered = client.message("third", 3000, update, seen);
+        tampered.message = PString("changed".into());
+        assert_eq!(
+            Err(ChatError::InvalidSignature),
+            state.handle_message(&tampered)
+        );
+    }
+
+    #[test]
+    fn unsigned_messages() {
+        let mut state = ChatState::new(1, false);
+        let packet = ChatMessage {
+            message: PString("hi".into()),
+            timestamp: 1000,
+            salt: 0,
+            signature: None,
+            last_seen: no_update(),
+        };
+        assert_eq!(None, state.handle_message(&packet).unwrap().signature);
+
+        let mut enforced = ChatState::new(1, true);
+        assert_eq!(
+            Err(ChatError::MissingSignature),
+            enforced.handle_message(&packet)
+        );
+    }
+
+    #[test]
+    fn out_of_order() {
+        let mut client = Client::new();
+        let mut state = ChatState::new(1, true);
+        state
+            .set_session(client.session.clone(), SystemTime::now())
+            .unwrap();
+
+        state
+            .handle_message(&client.message("first", 2000, no_update(), Vec::new()))
+            .unwrap();
+        assert_eq!(
+            Err(ChatError::OutOfOrder),
+            state.handle_message(&client.message("second", 1000, no_update(), Vec::new()))
+        );
+    }
+
+    #[test]
+    fn expired_session() {
+        let mut client = Client::new();
+        client.session.expires_at = 0;
+
+        let mut state = ChatState::new(1, true);
+        assert_eq!(
+            Err(ChatError::ExpiredPublicKey),
+            state.set_session(client.session, SystemTime::now())
+        );
+    }
+
+    #[test]
+    fn key_signature() {
+        let services = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut client = Client::new();
+
+        let mut data = Vec::new();
+        data.extend(1u128.to_be_bytes());
+        data.extend(client.session.expires_at.to_be_bytes());
+        data.extend(&client.session.public_key);
+        let mut signer = Signer::new(MessageDigest::sha1(), &services).unwrap();
+        client.session.key_signature = signer.sign_oneshot_to_vec(&data).unwrap();
+
+        let services_public =
+            PKey::public_key_from_der(&services.public_key_to_der().unwrap()).unwrap();
+        let session = PlayerSession::new(1, client.session.clone(), SystemTime::now()).unwrap();
+        assert_eq!(Ok(()), session.verify_key_signature(&services_public));
+
+        let other = PlayerSession::new(2, client.session.clone(), SystemTime::now()).unwrap();
+        assert_eq!(
+            Err(ChatError::InvalidKeySignature),
+            other.verify_key_signature(&services_public)
+        );
+
+        let mut state = ChatState::new(1, true).services_keys(vec![services_public.clone()]);
+        assert_eq!(
+            Ok(()),
+            state.set_session(client.session.clone(), SystemTime::now())
+        );
+        let mut other = ChatState::new(2, true).services_keys(vec![services_public]);
+        assert_eq!(
+            Err(ChatError::InvalidKeySignature),
+            other.set_session(client.session, SystemTime::now())
+        );
+        assert!(other.session().is_none());
+    }
+
+    #[test]
+    fn last_seen_window() {
+        let mut validator = LastSeenValidator::new();
+        let signature = |b: u8| MessageSignature(Box::new([b; 256]));
+
+        validator.add_pending(signature(1));
+        validator.add_pending(signature(2));
+        assert_eq!(LAST_SEEN_MESSAGES + 2, validator.tracked_count());
+
+        assert_eq!(
+            Err(ChatError::InvalidOffset { offset: 3, max: 2 }),
+            validator.apply_offset(3)
+        );
+
+        // Acknowledge the first and ignore the second message
+        let update = LastSeenUpdate {
+            offset: VarInt(2),
+            acknowledged: 1 << (LAST_SEEN_MESSAGES - 2),
+        };
+        assert_eq!(Ok(vec![signature(1)]), validator.apply_update(&update));
+
+        // The acknowledged message can not be ignored afterwards
+        assert_eq!(
+            Err(ChatError::IgnoredAcknowledged(LAST_SEEN_MESSAGES - 2)),
+            validator.apply_update(&no_update())
+        );
+        let update = LastSeenUpdate {
+            offset: VarInt(0),
+            acknowledged: 1,
+        };
+        assert_eq!(
+            Err(ChatError::UnknownMessage(0)),
+            validator.apply_update(&update)
+        );
+
+        // The bits after the window can be send, but do not belong to any message
+        let update = LastSeenUpdate {
+            offset: VarInt(0),
+            acknowledged: 1 << (LAST_SEEN_MESSAGES + 2),
+        };
+        assert_eq!(
+            Err(ChatError::UnknownMessage(LAST_SEEN_MESSAGES + 2)),
+            validator.apply_update(&update)
+        );
+    }
+
+    #[test]
+    fn chat_type_format() {
+        let chat = ChatType::from_registry("minecraft:chat").unwrap();
+        assert_eq!("chat.type.text", chat.translation_key);
+        assert_eq!(vec!["sender", "content"], chat.parameters);
+
+        let incoming = ChatType::from_registry("minecraft:msg_command_incoming").unwrap();
+        assert!(!incoming.style.is_empty());
+
+        let formatted = chat.format(
+            &TextComponent::text("player"),
+            &TextComponent::text("hi"),
+            None,
+        );
+        assert_eq!(
+            TextComponent::translate(
+                "chat.type.text",
+                vec![TextComponent::text("player"), TextComponent::text("hi")]
+            ),
+            formatted
+        );
+    }
+}
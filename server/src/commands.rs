@@ -0,0 +1,677 @@
+//! A brigadier-like Command Tree, which gets send to the clients using the
+//! [`Commands`](protocol::play::client::Commands) packet and is used to dispatch the commands
+//! received from them.
+//!
+//! # Example
+//! ```rust
+//! # use server::commands::{CommandTree, literal, argument};
+//! # use protocol::play::client::{CommandParser, NumberRange};
+//! let tree = CommandTree::<u32>::new()
+//!     .register(literal("add").then(
+//!         argument("amount", CommandParser::BrigadierInteger(NumberRange::default()))
+//!             .executes(|total: &mut u32, args| {
+//!                 *total += args.get("amount").and_then(|v| v.as_integer()).unwrap() as u32;
+//!             }),
+//!     ));
+//!
+//! let mut total = 0;
+//! tree.dispatch(&mut total, "add 5").unwrap();
+//! assert_eq!(5, total);
+//! ```
+
+use protocol::{
+    general::{PString, VarInt},
+    play::client::{CommandParser, Commands, StringMode},
+};
+
+type Executor<C> = Box<dyn Fn(&mut C, &Arguments) + Send + Sync>;
+
+/// The Suggestion-Types, which can be handled by the client without asking the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuggestionType {
+    AskServer,
+    AllRecipes,
+    AvailableSounds,
+    SummonableEntities,
+}
+
+impl SuggestionType {
+    pub fn identifier(&self) -> &'static str {
+        match self {
+            Self::AskServer => "minecraft:ask_server",
+            Self::AllRecipes => "minecraft:all_recipes",
+            Self::AvailableSounds => "minecraft:available_sounds",
+            Self::SummonableEntities => "minecraft:summonable_entities",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum NodeKind {
+    Literal(String),
+    Argument { name: String, parser: CommandParser },
+}
+
+/// A single Node in the Command Tree, created using [`literal`] or [`argument`]
+pub struct CommandNode<C> {
+    kind: NodeKind,
+    children: Vec<CommandNode<C>>,
+    executor: Option<Executor<C>>,
+    redirect: Option<Vec<String>>,
+    suggestions: Option<SuggestionType>,
+}
+
+/// Creates a new literal Node, which only matches the exact given name
+pub fn literal<C>(name: impl Into<String>) -> CommandNode<C> {
+    CommandNode::new(NodeKind::Literal(name.into()))
+}
+
+/// Creates a new argument Node, which parses its value using the given parser
+pub fn argument<C>(name: impl Into<String>, parser: CommandParser) -> CommandNode<C> {
+    CommandNode::new(NodeKind::Argument {
+        name: name.into(),
+        parser,
+    })
+}
+
+impl<C> core::fmt::Debug for CommandNode<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CommandNode")
+            .field("kind", &self.kind)
+            .field("children", &self.children)
+            .field("executable", &self.executor.is_some())
+            .field("redirect", &self.redirect)
+            .field("suggestions", &self.suggestions)
+            .finish()
+    }
+}
+
+impl<C> CommandNode<C> {
+    fn new(kind: NodeKind) -> Self {
+        Self {
+            kind,
+            children: Vec::new(),
+            executor: None,
+            redirect: None,
+            suggestions: None,
+        }
+    }
+
+    /// The name of the Node, used to reference it in redirects
+    pub fn name(&self) -> &str {
+        match &self.kind {
+            NodeKind::Literal(name) => name,
+            NodeKind::Argument { name, .. } => name,
+        }
+    }
+
+    /// Adds a child Node
+    pub fn then(mut self, child: CommandNode<C>) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Marks this Node as executable, running the given function if the command ends here
+    pub fn executes<F>(mut self, func: F) -> Self
+    where
+        F: Fn(&mut C, &Arguments) + Send + Sync + 'static,
+    {
+        self.executor = Some(Box::new(func));
+        self
+    }
+
+    /// Continues parsing at the children of the Node at the given path, an empty path refers to
+    /// the root of the tree
+    pub fn redirect<S>(mut self, path: &[S]) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.redirect = Some(path.iter().map(|p| p.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Sets the suggestion type for an argument Node
+    pub fn suggests(mut self, suggestions: SuggestionType) -> Self {
+        self.suggestions = Some(suggestions);
+        self
+    }
+}
+
+/// The parsed value of an argument
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgumentValue {
+    Bool(bool),
+    Integer(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+}
+
+impl ArgumentValue {
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i32> {
+        match self {
+            Self::Integer(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_long(&self) -> Option<i64> {
+        match self {
+            Self::Long(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_float(&self) -> Option<f32> {
+        match self {
+            Self::Float(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_double(&self) -> Option<f64> {
+        match self {
+            Self::Double(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+/// The arguments parsed while dispatching a command
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Arguments {
+    values: Vec<(String, ArgumentValue)>,
+}
+
+impl Arguments {
+    pub fn get(&self, name: &str) -> Option<&ArgumentValue> {
+        self.values
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DispatchError {
+    /// No Node matched the input at the given position
+    Unknown { position: usize },
+    /// The argument could not be parsed or was out of range
+    InvalidArgument { name: String, input: String },
+    /// The input ended at a Node that is not executable
+    Incomplete,
+    /// A redirect points to a path that does not exist
+    InvalidRedirect(Vec<String>),
+}
+
+/// The complete Command Tree
+pub struct CommandTree<C> {
+    children: Vec<CommandNode<C>>,
+}
+
+impl<C> core::fmt::Debug for CommandTree<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CommandTree")
+            .field("children", &self.children)
+            .finish()
+    }
+}
+
+impl<C> Default for CommandTree<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> CommandTree<C> {
+    pub fn new() -> Self {
+        Self {
+            children: Vec::new(),
+        }
+    }
+
+    /// Registers a new top-level command
+    pub fn register(mut self, node: CommandNode<C>) -> Self {
+        self.children.push(node);
+        self
+    }
+
+    fn resolve(&self, path: &[String]) -> Option<&[CommandNode<C>]> {
+        let mut current = self.children.as_slice();
+        for part in path {
+            let node = current.iter().find(|n| n.name() == part)?;
+            current = node.children.as_slice();
+        }
+        Some(current)
+    }
+
+    /// Generates the [`Commands`] packet describing this tree
+    pub fn packet(&self) -> Result<Commands, DispatchError> {
+        // Assign every node an index, the root always has index 0
+        let mut flattened: Vec<(Vec<String>, &CommandNode<C>)> = Vec::new();
+        let mut pending: Vec<(Vec<String>, &CommandNode<C>)> = self
+            .children
+            .iter()
+            .rev()
+            .map(|n| (vec![n.name().to_string()], n))
+            .collect();
+        while let Some((path, node)) = pending.pop() {
+            for child in node.children.iter().rev() {
+                let mut child_path = path.clone();
+                child_path.push(child.name().to_string());
+                pending.push((child_path, child));
+            }
+            flattened.push((path, node));
+        }
+
+        let index_of = |path: &[String]| -> Option<VarInt> {
+            if path.is_empty() {
+                return Some(VarInt(0));
+            }
+            flattened
+                .iter()
+                .position(|(p, _)| p.as_slice() == path)
+                .map(|idx| VarInt(idx as i32 + 1))
+        };
+        let children_of = |path: &[String], nodes: &[CommandNode<C>]| -> Vec<VarInt> {
+            nodes
+                .iter()
+                .filter_map(|child| {
+                    let mut child_path = path.to_vec();
+                    child_path.push(child.name().to_string());
+                    index_of(&child_path)
+                })
+                .collect()
+        };
+
+        let mut nodes = Vec::with_capacity(flattened.len() + 1);
+        nodes.push(protocol::play::client::CommandNode {
+            flags: protocol::play::client::CommandNode::TYPE_ROOT,
+            children: children_of(&[], &self.children),
+            redirect: None,
+            name: None,
+            parser: None,
+            suggestions_type: None,
+        });
+
+        for (path, node) in flattened.iter() {
+            let mut flags = match node.kind {
+                NodeKind::Literal(_) => protocol::play::client::CommandNode::TYPE_LITERAL,
+                NodeKind::Argument { .. } => protocol::play::client::CommandNode::TYPE_ARGUMENT,
+            };
+            if node.executor.is_some() {
+                flags |= protocol::play::client::CommandNode::EXECUTABLE;
+            }
+
+            let redirect = match node.redirect.as_ref() {
+                Some(target) => {
+                    flags |= protocol::play::client::CommandNode::HAS_REDIRECT;
+                    Some(
+                        index_of(target)
+                            .ok_or_else(|| DispatchError::InvalidRedirect(target.clone()))?,
+                    )
+                }
+                None => None,
+            };
+
+            let (parser, suggestions_type) = match &node.kind {
+                NodeKind::Literal(_) => (None, None),
+                NodeKind::Argument { parser, .. } => {
+                    let suggestions = node.suggestions.map(|s| {
+                        flags |= protocol::play::client::CommandNode::HAS_SUGGESTIONS_TYPE;
+                        PString(s.identifier().into())
+                    });
+                    (Some(parser.clone()), suggestions)
+                }
+            };
+
+            nodes.push(protocol::play::client::CommandNode {
+                flags,
+                children: children_of(path, &node.children),
+                redirect,
+                name: Some(PString(node.name().to_string().into())),
+                parser,
+                suggestions_type,
+            });
+        }
+
+        Ok(Commands {
+            nodes,
+            root_index: VarInt(0),
+        })
+    }
+
+    /// Parses the given command (without the leading `/`) and runs the matching executor
+    pub fn dispatch(&self, ctx: &mut C, input: &str) -> Result<(), DispatchError> {
+        let mut arguments = Arguments::default();
+        let executor = self.parse_nodes(&self.children, input, 0, &mut arguments)?;
+        executor(ctx, &arguments);
+        Ok(())
+    }
+
+    fn parse_nodes<'t>(
+        &'t self,
+        nodes: &'t [CommandNode<C>],
+        input: &str,
+        position: usize,
+        arguments: &mut Arguments,
+    ) -> Result<&'t Executor<C>, DispatchError> {
+        let mut error = DispatchError::Unknown { position };
+
+        // Literals take precedence over arguments, like in brigadier
+        let literals = nodes
+            .iter()
+            .filter(|n| matches!(n.kind, NodeKind::Literal(_)));
+        let args = nodes
+            .iter()
+            .filter(|n| matches!(n.kind, NodeKind::Argument { .. }));
+
+        for node in literals.chain(args) {
+            let consumed = match &node.kind {
+                NodeKind::Literal(name) => {
+                    let (word, rest) = split_word(&input[position..]);
+                    if word != name {
+                        continue;
+                    }
+                    input.len() - rest.len()
+                }
+                NodeKind::Argument { name, parser } => {
+                    match parse_argument(parser, &input[position..]) {
+                        Some((value, rest)) => {
+                            arguments.values.push((name.clone(), value));
+                            input.len() - rest.len()
+                        }
+                        None => {
+                            let (word, _) = split_word(&input[position..]);
+                            error = DispatchError::InvalidArgument {
+                                name: name.clone(),
+                                input: word.to_string(),
+                            };
+                            continue;
+                        }
+                    }
+                }
+            };
+
+            let pushed = matches!(node.kind, NodeKind::Argument { .. });
+            match self.parse_node(node, input, consumed, arguments) {
+                Ok(executor) => return Ok(executor),
+                Err(e) => {
+                    if pushed {
+                        arguments.values.pop();
+                    }
+                    error = e;
+                }
+            }
+        }
+
+        Err(error)
+    }
+
+    fn parse_node<'t>(
+        &'t self,
+        node: &'t CommandNode<C>,
+        input: &str,
+        position: usize,
+        arguments: &mut Arguments,
+    ) -> Result<&'t Executor<C>, DispatchError> {
+        if position >= input.len() {
+            return node.executor.as_ref().ok_or(DispatchError::Incomplete);
+        }
+
+        // Arguments are separated by a single space
+        if !input[position..].starts_with(' ') {
+            return Err(DispatchError::Unknown { position });
+        }
+        let position = position + 1;
+
+        let children = match node.redirect.as_ref() {
+            Some(target) => self
+                .resolve(target)
+                .ok_or_else(|| DispatchError::InvalidRedirect(target.clone()))?,
+            None => node.children.as_slice(),
+        };
+
+        self.parse_nodes(children, input, position, arguments)
+    }
+}
+
+fn split_word(input: &str) -> (&str, &str) {
+    match input.find(' ') {
+        Some(idx) => input.split_at(idx),
+        None => (input, ""),
+    }
+}
+
+fn parse_quoted(input: &str) -> Option<(String, &str)> {
+    let mut result = String::new();
+    let mut escaped = false;
+    for (idx, c) in input.char_indices().skip(1) {
+        match c {
+            _ if escaped => {
+                result.push(c);
+                escaped = false;
+            }
+            '\\' => escaped = true,
+            '"' => return Some((result, &input[idx + 1..])),
+            _ => result.push(c),
+        }
+    }
+    None
+}
+
+fn in_range<T>(value: T, range: &protocol::play::client::NumberRange<T>) -> bool
+where
+    T: PartialOrd + Copy,
+{
+    range.min.map(|min| value >= min).unwrap_or(true)
+        && range.max.map(|max| value <= max).unwrap_or(true)
+}
+
+/// Parses a single argument from the start of the input, returning the value and the remaining
+/// input. Parsers without a dedicated implementation read a single word as a string.
+fn parse_argument<'i>(parser: &CommandParser, input: &'i str) -> Option<(ArgumentValue, &'i str)> {
+    let (word, rest) = split_word(input);
+
+    match parser {
+        CommandParser::BrigadierBool => match word {
+            "true" => Some((ArgumentValue::Bool(true), rest)),
+            "false" => Some((ArgumentValue::Bool(false), rest)),
+            _ => None,
+        },
+        CommandParser::BrigadierInteger(range) => {
+            let value: i32 = word.parse().ok()?;
+            in_range(value, range).then_some((ArgumentValue::Integer(value), rest))
+        }
+        CommandParser::BrigadierLong(range) => {
+            let value: i64 = word.parse().ok()?;
+            in_range(value, range).then_some((ArgumentValue::Long(value), rest))
+        }
+        CommandParser::BrigadierFloat(range) => {
+            let value: f32 = word.parse().ok()?;
+            in_range(value, range).then_some((ArgumentValue::Float(value), rest))
+        }
+        CommandParser::BrigadierDouble(range) => {
+            let value: f64 = word.parse().ok()?;
+            in_range(value, range).then_some((ArgumentValue::Double(value), rest))
+        }
+        CommandParser::BrigadierString(StringMode::GreedyPhrase) | CommandParser::Message => {
+            (!input.is_empty()).then(|| (ArgumentValue::String(input.to_string()), ""))
+        }
+        CommandParser::BrigadierString(StringMode::QuotablePhrase) if input.starts_with('"') => {
+            let (value, rest) = parse_quoted(input)?;
+            Some((ArgumentValue::String(value), rest))
+        }
+        _ => (!word.is_empty()).then(|| (ArgumentValue::String(word.to_string()), rest)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use protocol::play::client::NumberRange;
+
+    fn test_tree() -> CommandTree<Vec<String>> {
+        CommandTree::new()
+            .register(
+                literal("say").then(argument("message", CommandParser::Message).executes(
+                    |out: &mut Vec<String>, args| {
+                        out.push(args.get("message").unwrap().as_str().unwrap().to_string());
+                    },
+                )),
+            )
+            .register(
+                literal("time").then(
+                    literal("set").then(
+                        argument(
+                            "value",
+                            CommandParser::BrigadierInteger(NumberRange::new(Some(0), None)),
+                        )
+                        .executes(|out: &mut Vec<String>, args| {
+                            let value = args.get("value").unwrap().as_integer().unwrap();
+                            out.push(format!("time {}", value));
+                        }),
+                    ),
+                ),
+            )
+            .register(
+                literal("name")
+                    .then(
+                        argument(
+                            "name",
+                            CommandParser::BrigadierString(StringMode::QuotablePhrase),
+                        )
+                        .suggests(SuggestionType::AskServer)
+                        .executes(|out: &mut Vec<String>, args| {
+                            out.push(args.get("name").unwrap().as_str().unwrap().to_string());
+                        }),
+                    )
+                    .executes(|out: &mut Vec<String>, _| {
+                        out.push("no name".to_string());
+                    }),
+            )
+            .register(literal("t").redirect(&["time"]))
+    }
+
+    #[test]
+    fn dispatch_literal_and_arguments() {
+        let tree = test_tree();
+        let mut out = Vec::new();
+
+        tree.dispatch(&mut out, "say hello world").unwrap();
+        tree.dispatch(&mut out, "time set 20").unwrap();
+        tree.dispatch(&mut out, "name").unwrap();
+        tree.dispatch(&mut out, "name \"with \\\"quotes\\\"\"")
+            .unwrap();
+        tree.dispatch(&mut out, "name single").unwrap();
+
+        assert_eq!(
+            vec![
+                "hello world".to_string(),
+                "time 20".to_string(),
+                "no name".to_string(),
+                "with \"quotes\"".to_string(),
+                "single".to_string(),
+            ],
+            out
+        );
+    }
+
+    #[test]
+    fn dispatch_redirect() {
+        let tree = test_tree();
+        let mut out = Vec::new();
+
+        tree.dispatch(&mut out, "t set 3").unwrap();
+        assert_eq!(vec!["time 3".to_string()], out);
+    }
+
+    #[test]
+    fn dispatch_errors() {
+        let tree = test_tree();
+        let mut out = Vec::new();
+
+        assert_eq!(
+            Err(DispatchError::Unknown { position: 0 }),
+            tree.dispatch(&mut out, "unknown")
+        );
+        assert_eq!(
+            Err(DispatchError::Incomplete),
+            tree.dispatch(&mut out, "time set")
+        );
+        assert_eq!(
+            Err(DispatchError::InvalidArgument {
+                name: "value".to_string(),
+                input: "-1".to_string()
+            }),
+            tree.dispatch(&mut out, "time set -1")
+        );
+        assert_eq!(
+            Err(DispatchError::Unknown { position: 11 }),
+            tree.dispatch(&mut out, "time set 1 2")
+        );
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn packet_layout() {
+        let tree = test_tree();
+        let packet = tree.packet().unwrap();
+
+        assert_eq!(VarInt(0), packet.root_index);
+        // root + say, message + time, set, value + name, name + t
+        assert_eq!(9, packet.nodes.len());
+
+        let root = &packet.nodes[0];
+        assert_eq!(
+            vec![VarInt(1), VarInt(3), VarInt(6), VarInt(8)],
+            root.children
+        );
+
+        let message = &packet.nodes[2];
+        assert_eq!(Some(CommandParser::Message), message.parser);
+        assert!(message.is_executable());
+
+        let name_arg = &packet.nodes[7];
+        assert_eq!(
+            Some(PString("minecraft:ask_server".into())),
+            name_arg.suggestions_type
+        );
+        assert_eq!(
+            protocol::play::client::CommandNode::TYPE_ARGUMENT
+                | protocol::play::client::CommandNode::EXECUTABLE
+                | protocol::play::client::CommandNode::HAS_SUGGESTIONS_TYPE,
+            name_arg.flags
+        );
+
+        let redirect = &packet.nodes[8];
+        assert_eq!(Some(VarInt(3)), redirect.redirect);
+        assert!(redirect.children.is_empty());
+    }
+
+    #[test]
+    fn packet_invalid_redirect() {
+        let tree = CommandTree::<()>::new().register(literal("a").redirect(&["b"]));
+        assert_eq!(
+            DispatchError::InvalidRedirect(vec!["b".to_string()]),
+            tree.packet().unwrap_err()
+        );
+    }
+}
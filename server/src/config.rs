@@ -2,4 +2,50 @@
 pub struct ServerConfig {
     pub max_players: usize,
     pub motd: String,
+    /// How players are verified during the login, not used for players forwarded by a proxy
+    pub authentication: Authentication,
+    /// The keypair used to encrypt the connections of players that are verified online
+    pub key: auth::encryption::ServerKey,
+    /// The secret shared with a Velocity proxy, enables modern player info forwarding
+    pub velocity_secret: Option<String>,
+    /// Whether players transferred from other servers are allowed to join
+    pub accepts_transfers: bool,
+    /// How the registries are synchronized with the client
+    pub registry_mode: RegistryMode,
+    /// The resource packs pushed to every player during configuration
+    pub resource_packs: Vec<crate::resource_pack::ResourcePack>,
+    /// Whether players declining a required resource pack get kicked
+    pub kick_on_declined_pack: bool,
+    /// Whether all chat messages need to be signed by the players
+    pub enforce_secure_chat: bool,
+    /// The keys of the Mojang services, see [`fetch_services_keys`](auth::fetch_services_keys).
+    /// The chat sessions of the players have to be signed by one of them, unless this is empty
+    pub services_keys: Vec<openssl::pkey::PKey<openssl::pkey::Public>>,
+    /// The timeouts and limits of the player connections
+    pub connection: networking::ConnectionConfig,
 }
+
+/// Selects the [`Authenticator`](auth::Authenticator) used for the login
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Authentication {
+    /// Players are not verified and get the offline UUID of their name, the connection is not
+    /// encrypted
+    Offline,
+    /// Players are verified by the session server at the base URL, usually
+    /// [`OnlineAuthenticator::MOJANG`](auth::OnlineAuthenticator::MOJANG)
+    Online { session_server: String },
+}
+
+/// How the registry data is send to the client during configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RegistryMode {
+    /// Only send the entry ids and rely on the data of the vanilla pack, if the client
+    /// reports having it in its known packs
+    #[default]
+    KnownPacks,
+    /// Always send the complete data for all the entries
+    Full,
+}
+
+/// The vanilla data pack, which contains the data for all the synchronized registries
+pub const VANILLA_PACK: (&str, &str, &str) = ("minecraft", "core", "1.20.6");
@@ -0,0 +1,167 @@
+//! Cookies and server transfers
+//!
+//! Cookies are stored on the client and can be requested again later, even by a different
+//! server after a transfer. Because the client can modify them, the [`CookieJar`] signs every
+//! cookie it creates and rejects cookies with an invalid signature.
+
+use protocol::general::{PString, VarInt};
+
+/// The maximum size of a cookie payload accepted by the vanilla client
+pub const MAX_COOKIE_SIZE: usize = 5120;
+
+const SIGNATURE_LENGTH: usize = 32;
+
+#[derive(Debug, PartialEq)]
+pub enum CookieError {
+    /// The signed payload would exceed [`MAX_COOKIE_SIZE`]
+    TooLarge { size: usize },
+    /// The client did not send a payload for the cookie
+    Missing,
+    /// The cookie was not created with the secret of this jar
+    InvalidSignature,
+}
+
+/// Creates and verifies cookies signed with a secret, which needs to be shared by all the
+/// servers a player could be transferred between
+#[derive(Debug, Clone)]
+pub struct CookieJar {
+    secret: Vec<u8>,
+}
+
+impl CookieJar {
+    pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            secret: secret.into(),
+        }
+    }
+
+    /// Signs the payload for the given cookie key
+    pub fn seal(&self, key: &str, payload: &[u8]) -> Result<Vec<u8>, CookieError> {
+        let size = SIGNATURE_LENGTH + payload.len();
+        if size > MAX_COOKIE_SIZE {
+            return Err(CookieError::TooLarge { size });
+        }
+
+        let mut result = self.signature(key, payload);
+        result.extend_from_slice(payload);
+        Ok(result)
+    }
+
+    /// Verifies the signature of a received cookie and returns its original payload
+    pub fn open(&self, key: &str, data: Option<&[u8]>) -> Result<Vec<u8>, CookieError> {
+        let data = data.ok_or(CookieError::Missing)?;
+        if data.len() < SIGNATURE_LENGTH {
+            return Err(CookieError::InvalidSignature);
+        }
+
+        let (signature, payload) = data.split_at(SIGNATURE_LENGTH);
+        if !openssl::memcmp::eq(signature, &self.signature(key, payload)) {
+            return Err(CookieError::InvalidSignature);
+        }
+
+        Ok(payload.to_vec())
+    }
+
+    /// The key is part of the signature, so a cookie can not be replayed under another key
+    fn signature(&self, key: &str, payload: &[u8]) -> Vec<u8> {
+        let mut content = Vec::with_capacity(key.len() + 1 + payload.len());
+        content.extend_from_slice(key.as_bytes());
+        content.push(0);
+        content.extend_from_slice(payload);
+
+        auth::forwarding::hmac_sha256(&self.secret, &content)
+    }
+
+    /// Creates the packet to store a signed cookie on the client during configuration
+    pub fn store_configuration(
+        &self,
+        key: &str,
+        payload: &[u8],
+    ) -> Result<protocol::configuration::client::StoreCookie, CookieError> {
+        Ok(protocol::configuration::client::StoreCookie {
+            key: PString(key.to_string().into()),
+            payload: self.seal(key, payload)?,
+        })
+    }
+
+    /// Creates the packet to store a signed cookie on the client during play
+    pub fn store_play(
+        &self,
+        key: &str,
+        payload: &[u8],
+    ) -> Result<protocol::play::client::StoreCookie, CookieError> {
+        Ok(protocol::play::client::StoreCookie {
+            key: PString(key.to_string().into()),
+            payload: self.seal(key, payload)?,
+        })
+    }
+}
+
+/// Creates the packet transferring the player to another server during configuration
+pub fn transfer_configuration(host: &str, port: u16) -> protocol::configuration::client::Transfer {
+    protocol::configuration::client::Transfer {
+        host: PString(host.to_string().into()),
+        port: VarInt(port as i32),
+    }
+}
+
+/// Creates the packet transferring the player to another server during play
+pub fn transfer_play(host: &str, port: u16) -> protocol::play::client::Transfer {
+    protocol::play::client::Transfer {
+        host: PString(host.to_string().into()),
+        port: VarInt(port as i32),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seal_open() {
+        let jar = CookieJar::new("secret");
+
+        let sealed = jar.seal("test:cookie", b"payload").unwrap();
+        assert_eq!(
+            Ok(b"payload".to_vec()),
+            jar.open("test:cookie", Some(&sealed))
+        );
+    }
+
+    #[test]
+    fn tampered() {
+        let jar = CookieJar::new("secret");
+
+        let mut sealed = jar.seal("test:cookie", b"payload").unwrap();
+        assert_eq!(
+            Err(CookieError::InvalidSignature),
+            jar.open("test:other", Some(&sealed))
+        );
+        assert_eq!(
+            Err(CookieError::InvalidSignature),
+            CookieJar::new("other").open("test:cookie", Some(&sealed))
+        );
+
+        *sealed.last_mut().unwrap() ^= 0xff;
+        assert_eq!(
+            Err(CookieError::InvalidSignature),
+            jar.open("test:cookie", Some(&sealed))
+        );
+        assert_eq!(Err(CookieError::Missing), jar.open("test:cookie", None));
+    }
+
+    #[test]
+    fn too_large() {
+        let jar = CookieJar::new("secret");
+
+        assert_eq!(
+            Err(CookieError::TooLarge {
+                size: MAX_COOKIE_SIZE + 1
+            }),
+            jar.seal(
+                "test:cookie",
+                &vec![0; MAX_COOKIE_SIZE - SIGNATURE_LENGTH + 1]
+            )
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! Keeps the connection of a player alive during the configuration and while playing
+//!
+//! The server regularly sends a keep alive with a new id, which the client has to answer with
+//! the same id. Clients that stop answering are disconnected, the time until the answer is the
+//! latency shown in the tab list.
+
+use std::time::{Duration, Instant};
+
+/// How often a keep alive is send
+pub const INTERVAL: Duration = Duration::from_secs(15);
+/// How long the client has to answer a keep alive
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, PartialEq)]
+pub enum KeepAliveError {
+    /// The client did not answer the keep alive within the timeout
+    Timeout,
+    /// The client answered with an id that was not send or already answered
+    UnexpectedId(i64),
+}
+
+#[derive(Debug)]
+pub struct KeepAlive {
+    next_id: i64,
+    /// The id and time of the keep alive that was not answered yet
+    pending: Option<(i64, Instant)>,
+    last_sent: Instant,
+    latency: Option<Duration>,
+}
+
+impl KeepAlive {
+    /// The first keep alive is send one [`INTERVAL`] after `now`
+    pub fn new(now: Instant) -> Self {
+        Self {
+            next_id: 0,
+            pending: None,
+            last_sent: now,
+            latency: None,
+        }
+    }
+
+    /// The next time [`poll`](Self::poll) has something to do
+    pub fn deadline(&self) -> Instant {
+        match self.pending {
+            Some((_, sent)) => sent + TIMEOUT,
+            None => self.last_sent + INTERVAL,
+        }
+    }
+
+    /// Returns the id of the keep alive to send, if one is due
+    pub fn poll(&mut self, now: Instant) -> Result<Option<i64>, KeepAliveError> {
+        match self.pending {
+            Some((_, sent)) if now.duration_since(sent) >= TIMEOUT => Err(KeepAliveError::Timeout),
+            Some(_) => Ok(None),
+            None if now.duration_since(self.last_sent) >= INTERVAL => {
+                let id = self.next_id;
+                self.next_id = self.next_id.wrapping_add(1);
+                self.pending = Some((id, now));
+                self.last_sent = now;
+                Ok(Some(id))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Handles the answer of the client, returning the updated latency
+    pub fn received(&mut self, id: i64, now: Instant) -> Result<Duration, KeepAliveError> {
+        let sent = match self.pending {
+            Some((pending, sent)) if pending == id => sent,
+            _ => return Err(KeepAliveError::UnexpectedId(id)),
+        };
+        self.pending = None;
+
+        // Smoothed like the vanilla server does, so a single slow answer does not stand out
+        let sample = now.duration_since(sent);
+        let latency = match self.latency {
+            Some(previous) => (previous * 3 + sample) / 4,
+            None => sample,
+        };
+        self.latency = Some(latency);
+
+        Ok(latency)
+    }
+
+    pub fn latency(&self) -> Option<Duration> {
+        self.latency
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sends_and_answers() {
+        let start = Instant::now();
+        let mut keep_alive = KeepAlive::new(start);
+
+        assert_eq!(Ok(None), keep_alive.poll(start + Duration::from_secs(1)));
+        assert_eq!(start + INTERVAL, keep_alive.deadline());
+
+        let sent = start + INTERVAL;
+        let id = keep_alive.poll(sent).unwrap().unwrap();
+        // Only one keep alive is pending at a time
+        assert_eq!(Ok(None), keep_alive.poll(sent + INTERVAL));
+
+        assert_eq!(
+            Ok(Duration::from_millis(100)),
+            keep_alive.received(id, sent + Duration::from_millis(100))
+        );
+        assert_eq!(
+            Err(KeepAliveError::UnexpectedId(id)),
+            keep_alive.received(id, sent + Duration::from_millis(200))
+        );
+
+        let sent = sent + INTERVAL;
+        let next = keep_alive.poll(sent).unwrap().unwrap();
+        assert_ne!(id, next);
+        assert_eq!(
+            Ok(Duration::from_millis(175)),
+            keep_alive.received(next, sent + Duration::from_millis(400))
+        );
+        assert_eq!(Some(Duration::from_millis(175)), keep_alive.latency());
+    }
+
+    #[test]
+    fn timeout() {
+        let start = Instant::now();
+        let mut keep_alive = KeepAlive::new(start);
+
+        let sent = start + INTERVAL;
+        keep_alive.poll(sent).unwrap().unwrap();
+        assert_eq!(sent + TIMEOUT, keep_alive.deadline());
+
+        assert_eq!(Ok(None), keep_alive.poll(sent + TIMEOUT / 2));
+        assert_eq!(
+            Err(KeepAliveError::Timeout),
+            keep_alive.poll(sent + TIMEOUT)
+        );
+    }
+}
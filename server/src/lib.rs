@@ -2,18 +2,29 @@
 
 use serde_derive::Deserialize;
 
+pub mod chat;
+pub mod commands;
 pub mod config;
+pub mod cookie;
+pub mod keep_alive;
 
 pub mod updates;
 pub mod world;
 
 pub mod data;
 
+pub mod players;
+pub mod plugin;
+pub mod resource_pack;
+pub mod tablist;
+pub mod tags;
+
 #[derive(Debug, Deserialize)]
 pub struct ProfileResponse {
     pub id: String,
     pub name: String,
     pub properties: Vec<ProfileProperty>,
+    #[serde(default)]
     pub profileActions: Vec<serde_json::Value>,
 }
 
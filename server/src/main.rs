@@ -1,6 +1,10 @@
 use tracing_subscriber::layer::SubscriberExt;
 
-use networking::{Connection, Transport};
+use networking::{Connection, ConnectionState, NetworkError, Transport};
+
+/// The protocol version of the clients that can join
+const PROTOCOL_VERSION: i32 = 766;
+const VERSION_NAME: &str = "1.20.6";
 
 fn main() {
     let fmt_layer = tracing_subscriber::fmt::layer().with_ansi(false);
@@ -16,9 +20,40 @@ fn main() {
         .build()
         .unwrap();
 
+    let authentication = server::config::Authentication::Online {
+        session_server: auth::OnlineAuthenticator::MOJANG.into(),
+    };
+    // Only players verified online have chat sessions signed by the services
+    let services_keys = match &authentication {
+        server::config::Authentication::Online { .. } => runtime
+            .block_on(auth::fetch_services_keys(auth::SERVICES_KEYS))
+            .unwrap_or_else(|e| {
+                tracing::warn!(
+                    "Loading the services keys, chat sessions are not verified: {:?}",
+                    e
+                );
+                Vec::new()
+            }),
+        server::config::Authentication::Offline => Vec::new(),
+    };
+
     let config = server::config::ServerConfig {
         max_players: 69,
         motd: "just a test".into(),
+        authentication,
+        key: auth::encryption::ServerKey::load_or_generate(
+            "server.pem",
+            auth::encryption::DEFAULT_KEY_BITS,
+        )
+        .unwrap(),
+        velocity_secret: None,
+        accepts_transfers: false,
+        registry_mode: server::config::RegistryMode::KnownPacks,
+        resource_packs: Vec::new(),
+        kick_on_declined_pack: true,
+        enforce_secure_chat: false,
+        services_keys,
+        connection: networking::ConnectionConfig::default(),
     };
 
     runtime.block_on(run_server(config));
@@ -26,6 +61,9 @@ fn main() {
 
 #[tracing::instrument(skip(server_conf))]
 async fn run_server(server_conf: server::config::ServerConfig) {
+    let server_conf = std::sync::Arc::new(server_conf);
+    let players = std::sync::Arc::new(server::players::PlayerCount::new());
+
     let listener = tokio::net::TcpListener::bind("0.0.0.0:25565")
         .await
         .unwrap();
@@ -35,28 +73,50 @@ async fn run_server(server_conf: server::config::ServerConfig) {
     while let Ok((connection, addr)) = listener.accept().await {
         tracing::info!(?addr);
 
-        tokio::spawn(handle_connection(connection));
+        tokio::spawn(handle_connection(
+            connection,
+            server_conf.clone(),
+            players.clone(),
+        ));
     }
 
     tracing::error!("Stopped");
 }
 
-#[tracing::instrument(skip(connection))]
-async fn handle_connection(connection: tokio::net::TcpStream) {
+#[tracing::instrument(skip(connection, server_conf, players))]
+async fn handle_connection(
+    connection: tokio::net::TcpStream,
+    server_conf: std::sync::Arc<server::config::ServerConfig>,
+    players: std::sync::Arc<server::players::PlayerCount>,
+) {
     tracing::info!("Handle Connection");
 
+    if let Err(e) = server_conf.connection.configure_tcp(&connection) {
+        tracing::warn!("Configuring TCP Stream: {:?}", e);
+    }
+
     let buffer = bytes::BytesMut::with_capacity(4096);
     let connection = networking::UnencryptedConnection::new(connection);
 
-    let mut connection = Connection::new(connection, buffer);
+    let connection = Connection::with_config(connection, buffer, server_conf.connection.clone());
+
+    handshake(connection, &server_conf, &players).await;
+}
 
+async fn handshake<S>(
+    mut connection: Connection<networking::UnencryptedConnection<S>>,
+    server_conf: &server::config::ServerConfig,
+    players: &server::players::PlayerCount,
+) where
+    S: core::marker::Unpin + tokio::io::AsyncRead + tokio::io::AsyncWrite,
+{
     let packet = match connection
         .recv_legacy_packet(protocol::handshake::server::Handshaking::parse)
         .await
     {
         Ok(p) => p,
         Err(e) => {
-            tracing::error!("Receiving initial Packet: {:?}", e);
+            close_on_error(connection, e).await;
             return;
         }
     };
@@ -76,10 +136,39 @@ async fn handle_connection(connection: tokio::net::TcpStream) {
 
             status(connection).await;
         }
-        protocol::handshake::server::NextState::Login => {
-            tracing::info!("Login");
+        protocol::handshake::server::NextState::Login
+        | protocol::handshake::server::NextState::Transfer => {
+            let transfer =
+                packet_data.next_state == protocol::handshake::server::NextState::Transfer;
+            tracing::info!(transfer, "Login");
+            connection.set_state(ConnectionState::Login);
 
-            login(connection).await;
+            if packet_data.protocol_version.0 != PROTOCOL_VERSION {
+                tracing::info!(
+                    version = packet_data.protocol_version.0,
+                    "Incompatible Client"
+                );
+                disconnect(
+                    connection,
+                    protocol::general::TextComponent::translate(
+                        "multiplayer.disconnect.incompatible",
+                        vec![protocol::general::TextComponent::text(VERSION_NAME)],
+                    ),
+                )
+                .await;
+                return;
+            }
+
+            if transfer && !server_conf.accepts_transfers {
+                disconnect(
+                    connection,
+                    protocol::general::TextComponent::text("This server does not accept transfers"),
+                )
+                .await;
+                return;
+            }
+
+            login(connection, server_conf, players).await;
         }
     };
 }
@@ -88,6 +177,8 @@ async fn status<S>(mut connection: Connection<S>)
 where
     S: Transport,
 {
+    connection.set_state(ConnectionState::Status);
+
     loop {
         let packet = match connection
             .recv_packet(protocol::status::server::ServerBound::parse)
@@ -95,7 +186,7 @@ where
         {
             Ok(p) => p,
             Err(e) => {
-                tracing::error!("Receiving Packet: {:?}", e);
+                close_on_error(connection, e).await;
                 return;
             }
         };
@@ -107,17 +198,18 @@ where
 
                 let response_content = protocol::status::client::StatusResponseContent {
                     version: protocol::status::client::StatusVersion {
-                        name: "1.20.6".into(),
-                        protocol: 766,
+                        name: VERSION_NAME.into(),
+                        protocol: PROTOCOL_VERSION,
                     },
                     players: protocol::status::client::StatusPlayers {
                         max: 69,
                         online: 0,
                         sample: Vec::new(),
                     },
-                    description: protocol::status::client::StatusDescription {
-                        text: "custom server implemenation".into(),
-                    },
+                    description: protocol::status::client::StatusDescription::text(
+                        "custom server implemenation",
+                    ),
+                    favicon: None,
                     previews_chat: false,
                     enforces_secure_chat: false,
                 };
@@ -125,7 +217,10 @@ where
                 let response_packet = protocol::packet::Packet {
                     inner: protocol::status::client::StatusResponse::new(&response_content),
                 };
-                connection.send_packet(&response_packet).await.unwrap();
+                if let Err(e) = connection.send_packet(&response_packet).await {
+                    close_on_error(connection, e).await;
+                    return;
+                }
 
                 tracing::info!("Send Response packet");
             }
@@ -135,7 +230,10 @@ where
                 let response_packet = protocol::packet::Packet {
                     inner: protocol::status::client::PingResponse { payload: p.payload },
                 };
-                connection.send_packet(&response_packet).await.unwrap();
+                if let Err(e) = connection.send_packet(&response_packet).await {
+                    close_on_error(connection, e).await;
+                    return;
+                }
 
                 tracing::info!("Send Response Packet");
             }
@@ -143,34 +241,191 @@ where
     }
 }
 
-async fn login<S>(mut connection: Connection<networking::UnencryptedConnection<S>>)
-where
+async fn login<S>(
+    mut connection: Connection<networking::UnencryptedConnection<S>>,
+    server_conf: &server::config::ServerConfig,
+    players: &server::players::PlayerCount,
+) where
     S: core::marker::Unpin + tokio::io::AsyncRead + tokio::io::AsyncWrite,
 {
-    let login_start_packet = connection
+    connection.set_state(ConnectionState::Login);
+
+    let login_start_packet = match connection
         .recv_packet(protocol::login::server::LoginStart::parse)
         .await
-        .unwrap();
+    {
+        Ok(p) => p,
+        Err(e) => {
+            close_on_error(connection, e).await;
+            return;
+        }
+    };
     tracing::info!(?login_start_packet);
 
-    let private_key = openssl::rsa::Rsa::generate(1024).unwrap();
-    let pub_key = openssl::rsa::Rsa::from_public_components(
-        (private_key.n()).to_owned().unwrap(),
-        (private_key.e()).to_owned().unwrap(),
+    if let Some(secret) = server_conf.velocity_secret.as_ref() {
+        return forwarded_login(connection, secret.as_bytes(), server_conf, players).await;
+    }
+
+    let name = login_start_packet.inner.name.0.into_owned();
+    match &server_conf.authentication {
+        server::config::Authentication::Offline => {
+            authenticate(
+                connection,
+                &auth::OfflineAuthenticator,
+                &name,
+                server_conf,
+                players,
+            )
+            .await
+        }
+        server::config::Authentication::Online { session_server } => {
+            let authenticator = auth::OnlineAuthenticator::new(session_server.clone());
+            authenticate(connection, &authenticator, &name, server_conf, players).await
+        }
+    };
+}
+
+/// Authenticates the player, enabling the encryption first if the authenticator requires it
+async fn authenticate<S, A>(
+    connection: Connection<networking::UnencryptedConnection<S>>,
+    authenticator: &A,
+    name: &str,
+    server_conf: &server::config::ServerConfig,
+    players: &server::players::PlayerCount,
+) where
+    S: core::marker::Unpin + tokio::io::AsyncRead + tokio::io::AsyncWrite,
+    A: auth::Authenticator,
+{
+    if !authenticator.requires_encryption() {
+        return authenticated_login(connection, authenticator, name, None, server_conf, players)
+            .await;
+    }
+
+    let Some((connection, session)) = enable_encryption(connection, &server_conf.key).await else {
+        return;
+    };
+    authenticated_login(
+        connection,
+        authenticator,
+        name,
+        Some(session),
+        server_conf,
+        players,
     )
-    .unwrap();
-    let pub_key_encoded = protocol::login::encryption::asn1_encode_key(pub_key.n(), pub_key.e());
+    .await
+}
 
-    let server_verify_token = [123, 71, 83, 90];
+async fn authenticated_login<S, A>(
+    connection: Connection<S>,
+    authenticator: &A,
+    name: &str,
+    session: Option<auth::Session>,
+    server_conf: &server::config::ServerConfig,
+    players: &server::players::PlayerCount,
+) where
+    S: Transport,
+    A: auth::Authenticator,
+{
+    let profile = match authenticator.authenticate(name, session.as_ref()).await {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::warn!(?name, "Authentication failed: {:?}", e);
+
+            disconnect(
+                connection,
+                protocol::general::TextComponent::translate(
+                    "multiplayer.disconnect.unverified_username",
+                    Vec::new(),
+                ),
+            )
+            .await;
+            return;
+        }
+    };
+    tracing::info!(?profile, "Authenticated");
+
+    finish_login(connection, profile, server_conf, players).await;
+}
+
+/// Sends the login success for the profile and moves on to the configuration once the client
+/// acknowledged it, the player counts as online until the connection is closed
+async fn finish_login<S>(
+    mut connection: Connection<S>,
+    profile: auth::Profile,
+    server_conf: &server::config::ServerConfig,
+    players: &server::players::PlayerCount,
+) where
+    S: Transport,
+{
+    let Some(_slot) = players.join(server_conf.max_players) else {
+        disconnect(
+            connection,
+            protocol::general::TextComponent::translate(
+                "multiplayer.disconnect.server_full",
+                Vec::new(),
+            ),
+        )
+        .await;
+        return;
+    };
+
+    let player = server::tablist::TabEntry::new(profile.uuid, profile.name.clone())
+        .properties(profile.properties.clone());
+
+    let success = protocol::packet::Packet {
+        inner: protocol::login::client::LoginSuccess {
+            uuid: profile.uuid,
+            name: protocol::general::PString(profile.name.into()),
+            properites: profile.properties,
+        },
+    };
+    if let Err(e) = connection.send_packet(&success).await {
+        close_on_error(connection, e).await;
+        return;
+    }
+
+    tracing::info!("Send Login Success");
+
+    let packet = match connection
+        .recv_packet(protocol::login::server::LoginAck::parse)
+        .await
+    {
+        Ok(p) => p,
+        Err(e) => {
+            close_on_error(connection, e).await;
+            return;
+        }
+    };
+    tracing::info!(?packet, "Login was Acknowledged");
+
+    configuration(connection, server_conf, player).await;
+}
+
+/// Sends the encryption request with the key of the server and enables the encryption using the
+/// shared secret from the response
+async fn enable_encryption<S>(
+    mut connection: Connection<networking::UnencryptedConnection<S>>,
+    key: &auth::encryption::ServerKey,
+) -> Option<(
+    Connection<networking::EncryptedConnection<S>>,
+    auth::Session,
+)>
+where
+    S: core::marker::Unpin + tokio::io::AsyncRead + tokio::io::AsyncWrite,
+{
+    let server_verify_token = auth::encryption::verify_token();
 
     let encryption_packet = protocol::packet::Packet {
         inner: protocol::login::client::EncryptionRequest {
             server_id: protocol::general::PString("".into()),
-            pubkey: pub_key_encoded.clone(),
+            pubkey: key.public_key().to_vec(),
             verifytoken: server_verify_token.to_vec(),
         },
     };
-    connection.send_packet(&encryption_packet).await.unwrap();
+    if let Err(e) = connection.send_packet(&encryption_packet).await {
+        close_on_error(connection, e).await;
+        return None;
+    }
 
     tracing::info!("Send EncryptionRequest");
 
@@ -180,139 +435,193 @@ where
     {
         Ok(p) => p,
         Err(e) => {
-            tracing::error!("Receiving EncryptionResponse: {:?}", e);
-            loop {
-                tokio::task::yield_now().await;
-            }
+            close_on_error(connection, e).await;
+            return None;
         }
     };
 
-    let mut shared_secret = [0; 128];
-    let written = private_key
-        .private_decrypt(
-            &encryption_response.inner.shared_secret,
-            &mut shared_secret,
-            openssl::rsa::Padding::PKCS1,
-        )
-        .unwrap();
-    let shared_secret = &shared_secret[..written];
-
-    let mut client_verify_token = [0; 128];
-    let written = private_key
-        .private_decrypt(
-            &encryption_response.inner.verify_token,
-            &mut client_verify_token,
-            openssl::rsa::Padding::PKCS1,
-        )
-        .unwrap();
-    let client_verify_token = &client_verify_token[..written];
+    let shared_secret = match key.decrypt_response(&encryption_response.inner, &server_verify_token)
+    {
+        Ok(secret) => secret,
+        Err(e) => {
+            tracing::warn!("Decrypting the EncryptionResponse: {:?}", e);
 
-    if client_verify_token != &server_verify_token {
-        tracing::error!("Verify Tokens dont match");
-    }
+            let reason = match e {
+                auth::encryption::KeyError::InvalidVerifyToken => "Invalid verify token",
+                _ => "Invalid encryption response",
+            };
+            disconnect(
+                connection,
+                protocol::general::TextComponent::translate(
+                    "disconnect.loginFailedInfo",
+                    vec![protocol::general::TextComponent::text(reason)],
+                ),
+            )
+            .await;
+            return None;
+        }
+    };
 
-    let http_client = reqwest::Client::new();
+    let session = auth::Session {
+        server_id: String::new(),
+        shared_secret: shared_secret.clone(),
+        public_key: key.public_key().to_vec(),
+    };
 
-    let url = format!(
-        "https://sessionserver.mojang.com/session/minecraft/profile/{:x}?unsigned=false",
-        login_start_packet.inner.uuid
-    );
-    let req = http_client.request(reqwest::Method::GET, url);
-    let session_resp = req.send().await.unwrap();
-    let raw_response = session_resp.text().await.unwrap();
-    let session_json: server::ProfileResponse = serde_json::from_str(&raw_response).unwrap();
+    match connection
+        .try_map_transport(|c| c.encrypt::<networking::cipher::DefaultCipher>(&shared_secret))
+    {
+        Ok(c) => Some((c, session)),
+        Err(e) => {
+            tracing::error!("Enabling Encryption: {:?}", e);
+            None
+        }
+    }
+}
 
-    let session_uuid = uuid::Uuid::parse_str(&session_json.id).unwrap();
+/// Login for players connecting through a Velocity proxy, which already authenticated them
+async fn forwarded_login<S>(
+    mut connection: Connection<S>,
+    secret: &[u8],
+    server_conf: &server::config::ServerConfig,
+    players: &server::players::PlayerCount,
+) where
+    S: Transport,
+{
+    const MESSAGE_ID: i32 = 0;
 
-    let response_packet = protocol::packet::Packet {
-        inner: protocol::login::client::LoginSuccess {
-            uuid: session_uuid.as_u128(),
-            name: protocol::general::PString(std::borrow::Cow::Owned(session_json.name.clone())),
-            properites: Vec::new(),
-        },
+    let request = protocol::packet::Packet {
+        inner: auth::forwarding::request(MESSAGE_ID),
     };
+    if let Err(e) = connection.send_packet(&request).await {
+        close_on_error(connection, e).await;
+        return;
+    }
 
-    let mut connection = connection.map_transport(|c| {
-        c.encrypt(
-            openssl::symm::Crypter::new(
-                openssl::symm::Cipher::aes_128_cfb8(),
-                openssl::symm::Mode::Decrypt,
-                shared_secret,
-                Some(shared_secret),
-            )
-            .unwrap(),
-            openssl::symm::Crypter::new(
-                openssl::symm::Cipher::aes_128_cfb8(),
-                openssl::symm::Mode::Encrypt,
-                shared_secret,
-                Some(shared_secret),
-            )
-            .unwrap(),
-        )
-    });
-
-    connection.send_packet(&response_packet).await.unwrap();
+    let response = match connection
+        .recv_packet(protocol::login::server::LoginPluginResponse::parse)
+        .await
+    {
+        Ok(p) => p,
+        Err(e) => {
+            close_on_error(connection, e).await;
+            return;
+        }
+    };
 
-    tracing::info!("Send Login Success");
+    let player = match auth::forwarding::verify(secret, &response.inner) {
+        Ok(p) if response.inner.message_id.0 == MESSAGE_ID => p,
+        result => {
+            match result {
+                Ok(_) => tracing::error!("Received response for unknown message"),
+                Err(e) => tracing::error!("Player Info Forwarding: {:?}", e),
+            };
 
-    let packet = connection
-        .recv_packet(protocol::login::server::LoginAck::parse)
-        .await
-        .unwrap();
-    tracing::info!(?packet, "Login was Acknowledged");
+            disconnect(
+                connection,
+                protocol::general::TextComponent::text("Unable to verify player details"),
+            )
+            .await;
+            return;
+        }
+    };
+    tracing::info!(?player, "Forwarded Player");
 
-    configuration(connection).await;
+    let profile = auth::Profile {
+        uuid: player.uuid,
+        name: player.name,
+        properties: player.properties,
+    };
+    finish_login(connection, profile, server_conf, players).await;
 }
 
-async fn configuration<S>(mut connection: Connection<S>)
-where
+async fn configuration<S>(
+    mut connection: Connection<S>,
+    server_conf: &server::config::ServerConfig,
+    mut player: server::tablist::TabEntry,
+) where
     S: Transport,
 {
     tracing::info!("Entering Configuration State of the connection");
+    connection.set_state(ConnectionState::Configuration);
 
-    connection
-        .send_packet(&protocol::packet::Packet {
-            inner: protocol::configuration::client::FeatureFlags {
-                flags: vec![protocol::general::PString("minecraft:vanilla".into())],
-            },
-        })
-        .await
-        .unwrap();
+    let plugin_channels = server::plugin::PluginChannels::<()>::new();
+    let mut plugin_state = server::plugin::ClientPluginState::default();
+    let mut resource_packs = server::resource_pack::ResourcePackTracker::new()
+        .kick_on_decline(server_conf.kick_on_declined_pack);
 
-    connection
-        .send_packet(&protocol::packet::Packet {
-            inner: protocol::configuration::client::KnownPacks {
-                packs: vec![(
-                    protocol::general::PString("minecraft".into()),
-                    protocol::general::PString("core".into()),
-                    protocol::general::PString("1.20.6".into()),
-                )],
-            },
-        })
-        .await
-        .unwrap();
+    let setup = async {
+        connection
+            .send_packet(&protocol::packet::Packet {
+                inner: protocol::configuration::client::PluginMessage::new(
+                    &protocol::plugin::Brand {
+                        brand: "rustcraft".to_string(),
+                    },
+                ),
+            })
+            .await?;
 
-    for (name, entities) in server::data::registry::all_registries() {
-        tracing::info!("Sending Registry: {:?}", name);
         connection
             .send_packet(&protocol::packet::Packet {
-                inner: protocol::configuration::client::RegistryData {
-                    id: name,
-                    entries: entities,
+                inner: protocol::configuration::client::FeatureFlags {
+                    flags: vec![protocol::general::PString("minecraft:vanilla".into())],
                 },
             })
-            .await
-            .unwrap();
+            .await?;
+
+        connection
+            .send_packet(&protocol::packet::Packet {
+                inner: protocol::configuration::client::KnownPacks {
+                    packs: vec![(
+                        protocol::general::PString(server::config::VANILLA_PACK.0.into()),
+                        protocol::general::PString(server::config::VANILLA_PACK.1.into()),
+                        protocol::general::PString(server::config::VANILLA_PACK.2.into()),
+                    )],
+                },
+            })
+            .await?;
+
+        for pack in server_conf.resource_packs.iter() {
+            tracing::info!("Pushing Resource Pack: {:?}", pack.url);
+
+            connection
+                .send_packet(&protocol::packet::Packet {
+                    inner: pack.configuration_packet(),
+                })
+                .await?;
+            resource_packs.push(pack.clone());
+        }
+
+        Ok::<_, NetworkError>(())
+    };
+    if let Err(e) = setup.await {
+        close_on_error(connection, e).await;
+        return;
     }
 
+    let mut keep_alive = server::keep_alive::KeepAlive::new(std::time::Instant::now());
+
     loop {
-        let packet = match connection
-            .recv_packet(protocol::configuration::server::ConfigurationMessage::parse)
+        let received = tokio::select! {
+            received = connection.recv_packet(protocol::configuration::server::ConfigurationMessage::parse) => Some(received),
+            _ = tokio::time::sleep_until(keep_alive.deadline().into()) => None,
+        };
+        let Some(received) = received else {
+            connection = match send_keep_alive(connection, &mut keep_alive, |id| {
+                protocol::configuration::client::KeepAlive { id }
+            })
             .await
-        {
+            {
+                Some(c) => c,
+                None => return,
+            };
+            continue;
+        };
+
+        let packet = match received {
             Ok(p) => p,
             Err(e) => {
-                tracing::error!("Receiving Packet: {:?}", e);
+                close_on_error(connection, e).await;
                 return;
             }
         };
@@ -320,31 +629,139 @@ where
         match packet.inner {
             protocol::configuration::server::ConfigurationMessage::ClientInformation(c_info) => {
                 tracing::info!("Client Information: {:?}", c_info);
-
-                let response = protocol::packet::Packet {
-                    inner: protocol::configuration::client::Finish {},
-                };
-                connection.send_packet(&response).await.unwrap();
             }
             protocol::configuration::server::ConfigurationMessage::PluginMessage(pm) => {
                 tracing::info!("Plugin Message: {:?}", pm);
+
+                if let Err(e) =
+                    plugin_channels.handle(&mut plugin_state, &mut (), &pm.channel.0, &pm.data)
+                {
+                    tracing::warn!("Handling Plugin Message: {:?}", e);
+                }
+            }
+            protocol::configuration::server::ConfigurationMessage::KeepAlive(answer) => {
+                match keep_alive.received(answer.id, std::time::Instant::now()) {
+                    Ok(latency) => player.latency = latency.as_millis() as i32,
+                    Err(e) => {
+                        tracing::info!("Keep Alive: {:?}", e);
+                        disconnect(
+                            connection,
+                            protocol::general::TextComponent::translate(
+                                "disconnect.timeout",
+                                Vec::new(),
+                            ),
+                        )
+                        .await;
+                        return;
+                    }
+                };
+            }
+            protocol::configuration::server::ConfigurationMessage::CookieResponse(cookie) => {
+                tracing::info!("Cookie Response: {:?}", cookie);
             }
             protocol::configuration::server::ConfigurationMessage::KnownPacks(packs) => {
                 tracing::info!("Known Packs: {:?}", packs);
+
+                let (namespace, id, version) = server::config::VANILLA_PACK;
+                let has_vanilla = packs
+                    .packs
+                    .iter()
+                    .any(|(n, i, v)| n.0 == namespace && i.0 == id && v.0 == version);
+                let include_data =
+                    server_conf.registry_mode == server::config::RegistryMode::Full || !has_vanilla;
+
+                let registries = async {
+                    for (name, entries) in server::data::registry::all_registries(include_data) {
+                        tracing::info!("Sending Registry: {:?}", name);
+                        connection
+                            .send_packet(&protocol::packet::Packet {
+                                inner: protocol::configuration::client::RegistryData {
+                                    id: name,
+                                    entries,
+                                },
+                            })
+                            .await?;
+                    }
+
+                    connection
+                        .send_packet(&protocol::packet::Packet {
+                            inner: server::tags::TagRegistry::vanilla().packet(),
+                        })
+                        .await?;
+
+                    connection
+                        .send_packet(&protocol::packet::Packet {
+                            inner: protocol::configuration::client::Finish {},
+                        })
+                        .await
+                };
+                if let Err(e) = registries.await {
+                    close_on_error(connection, e).await;
+                    return;
+                }
+            }
+            protocol::configuration::server::ConfigurationMessage::ResourcePackResponse(
+                response,
+            ) => {
+                tracing::info!("Resource Pack Response: {:?}", response);
+
+                match resource_packs.handle(response.uuid, response.result) {
+                    Ok(_) => {}
+                    Err(server::resource_pack::ResourcePackError::UnknownPack(uuid)) => {
+                        tracing::warn!("Response for unknown Resource Pack: {:x}", uuid);
+                    }
+                    Err(e) => {
+                        tracing::info!("Kicking Player: {:?}", e);
+
+                        disconnect(
+                            connection,
+                            protocol::general::TextComponent::text(
+                                "This server requires its resource pack",
+                            ),
+                        )
+                        .await;
+                        return;
+                    }
+                };
             }
             protocol::configuration::server::ConfigurationMessage::AckFinish(_) => {
                 tracing::info!("Received AckFinish");
 
-                return play(connection).await;
+                let chat =
+                    server::chat::ChatState::new(player.uuid, server_conf.enforce_secure_chat)
+                        .services_keys(server_conf.services_keys.clone());
+                return play(
+                    connection,
+                    plugin_channels,
+                    plugin_state,
+                    resource_packs,
+                    chat,
+                    keep_alive,
+                    player,
+                )
+                .await;
             }
         };
     }
 }
 
-async fn play<S>(mut connection: Connection<S>)
-where
+async fn play<S>(
+    mut connection: Connection<S>,
+    plugin_channels: server::plugin::PluginChannels<()>,
+    mut plugin_state: server::plugin::ClientPluginState,
+    mut resource_packs: server::resource_pack::ResourcePackTracker,
+    mut chat: server::chat::ChatState,
+    mut keep_alive: server::keep_alive::KeepAlive,
+    player: server::tablist::TabEntry,
+) where
     S: Transport,
 {
+    connection.set_state(ConnectionState::Play);
+
+    let overworld =
+        server::data::registry::entry_id("minecraft:dimension_type", "minecraft:overworld")
+            .unwrap_or(0);
+
     let login = protocol::packet::Packet {
         inner: protocol::play::client::Login {
             entity_id: 123,
@@ -356,7 +773,7 @@ where
             reduced_debug_info: false,
             enable_respawn_rule: true,
             do_limited_crafting: false,
-            dimension_type: protocol::general::PString("".into()),
+            dimension_type: protocol::general::VarInt(overworld),
             dimension_name: protocol::general::PString("minecraft:overworld".into()),
             hashed_seed: 1785937579151603980,
             game_mode: 0,
@@ -365,26 +782,779 @@ where
             is_flat: false,
             death_location: None,
             portal_cooldown: protocol::general::VarInt(10),
-            enforce_secure_chat: false,
+            enforce_secure_chat: chat.enforces_secure_chat(),
         },
     };
-    connection.send_packet(&login).await.unwrap();
+    let player_uuid = player.uuid;
+    let player_name = protocol::general::TextComponent::text(player.name.clone());
 
-    tracing::info!("Send Login Packet");
+    let mut tablist = server::tablist::TabList::new();
+    tablist.insert(player);
+    let changes = tablist.changes();
 
-    let game_event = protocol::packet::Packet {
-        inner: protocol::play::client::GameEvent {
-            event: 13,
-            value: 0.0,
-        },
-    };
-    connection.send_packet(&game_event).await.unwrap();
+    let chat_type = server::chat::ChatType::from_registry("minecraft:chat")
+        .expect("The chat type is part of the registry data");
+
+    let commands = server::commands::CommandTree::<()>::new().register(
+        server::commands::literal("ping").executes(|_, _| {
+            tracing::info!("Pong");
+        }),
+    );
+
+    let plains = server::data::registry::entry_id("minecraft:worldgen/biome", "minecraft:plains")
+        .unwrap_or(0);
+
+    let join = async {
+        connection.send_packet(&login).await?;
+
+        tracing::info!("Send Login Packet");
+
+        let game_event = protocol::packet::Packet {
+            inner: protocol::play::client::GameEvent {
+                event: protocol::play::client::GameEvent::START_WAITING_FOR_CHUNKS,
+                value: 0.0,
+            },
+        };
+        connection.send_packet(&game_event).await?;
+
+        tracing::info!("Send Game-Event Packet");
 
-    tracing::info!("Send Game-Event Packet");
+        if let Some(remove) = changes.remove {
+            connection
+                .send_packet(&protocol::packet::Packet { inner: remove })
+                .await?;
+        }
+        for update in changes.updates {
+            connection
+                .send_packet(&protocol::packet::Packet { inner: update })
+                .await?;
+        }
 
-    // Send Chunk Data
+        tracing::info!("Send Tab List");
+
+        connection
+            .send_packet(&protocol::packet::Packet {
+                inner: protocol::play::client::SystemChatMessage {
+                    content: protocol::general::TextComponent::translate(
+                        "multiplayer.player.joined",
+                        vec![player_name.clone()],
+                    )
+                    .styled([("color".to_string(), nbt::Tag::String_("yellow".to_string()))]),
+                    overlay: false,
+                },
+            })
+            .await?;
+
+        match commands.packet() {
+            Ok(packet) => {
+                connection
+                    .send_packet(&protocol::packet::Packet { inner: packet })
+                    .await?;
+
+                tracing::info!("Send Commands Packet");
+            }
+            Err(e) => {
+                tracing::error!("Building Commands Packet: {:?}", e);
+            }
+        };
+
+        connection
+            .send_packet(&protocol::packet::Packet {
+                inner: protocol::play::client::SetCenterChunk {
+                    chunk_x: protocol::general::VarInt(0),
+                    chunk_z: protocol::general::VarInt(0),
+                },
+            })
+            .await?;
+
+        connection
+            .send_packet(&protocol::packet::Packet {
+                inner: protocol::play::client::ChunkBatchStart {},
+            })
+            .await?;
+
+        let mut chunk_count = 0;
+        for chunk_x in -4..=4 {
+            for chunk_z in -4..=4 {
+                let chunk = server::world::Chunk::<-64, 320>::from_fn(
+                    server::world::coordinates::WorldChunkCoordinate(chunk_x),
+                    server::world::coordinates::WorldChunkCoordinate(chunk_z),
+                    |_, _, y| match y {
+                        ..-62 => server::world::BlockData::Stone,
+                        -62..-61 => server::world::BlockData::Dirt,
+                        -61 => server::world::BlockData::State(
+                            registry::Block::GrassBlock.default_state(),
+                        ),
+                        _ => server::world::BlockData::Air,
+                    },
+                );
+
+                connection
+                    .send_packet(&protocol::packet::Packet {
+                        inner: chunk.packet(plains),
+                    })
+                    .await?;
+                chunk_count += 1;
+            }
+        }
+
+        connection
+            .send_packet(&protocol::packet::Packet {
+                inner: protocol::play::client::ChunkBatchFinished {
+                    size: protocol::general::VarInt(chunk_count),
+                },
+            })
+            .await?;
+
+        tracing::info!("Send Chunks");
+
+        connection
+            .send_packet(&protocol::packet::Packet {
+                inner: protocol::play::client::SynchronizePlayerPosition {
+                    x: 0.0,
+                    y: -60.0,
+                    z: 0.0,
+                    yaw: 0.0,
+                    pitch: 0.0,
+                    flags: 0,
+                    teleport_id: protocol::general::VarInt(1),
+                },
+            })
+            .await
+    };
+    if let Err(e) = join.await {
+        close_on_error(connection, e).await;
+        return;
+    }
 
     loop {
-        tokio::task::yield_now().await;
+        let received = tokio::select! {
+            received = connection.recv_rawpacket() => Some(received),
+            _ = tokio::time::sleep_until(keep_alive.deadline().into()) => None,
+        };
+        let Some(received) = received else {
+            connection = match send_keep_alive(connection, &mut keep_alive, |id| {
+                protocol::play::client::KeepAlive { id }
+            })
+            .await
+            {
+                Some(c) => c,
+                None => return,
+            };
+            continue;
+        };
+
+        let raw_packet = match received {
+            Ok(p) => p,
+            Err(e) => {
+                close_on_error(connection, e).await;
+                return;
+            }
+        };
+
+        let packet = match protocol::play::server::Play::parse(raw_packet.id, &raw_packet.data) {
+            Ok((_, p)) => p,
+            Err(e) => {
+                tracing::debug!(id = ?raw_packet.id, "Unhandled Packet: {:?}", e);
+                continue;
+            }
+        };
+
+        match packet {
+            protocol::play::server::Play::ChatCommand(cmd) => {
+                tracing::info!("Command: {:?}", cmd.command);
+
+                if let Err(e) = commands.dispatch(&mut (), &cmd.command.0) {
+                    tracing::error!("Executing Command: {:?}", e);
+                }
+            }
+            protocol::play::server::Play::PlayerSession(update) => {
+                tracing::info!("Player Session: {:x}", update.session.session_id);
+
+                if let Err(e) =
+                    chat.set_session(update.session.clone(), std::time::SystemTime::now())
+                {
+                    tracing::warn!("Invalid Chat Session: {:?}", e);
+
+                    // Like the vanilla server, a session with an invalid key is never ignored
+                    let reason = match e {
+                        server::chat::ChatError::ExpiredPublicKey => {
+                            "multiplayer.disconnect.expired_public_key"
+                        }
+                        _ => "multiplayer.disconnect.invalid_public_key_signature",
+                    };
+                    disconnect(
+                        connection,
+                        protocol::general::TextComponent::translate(reason, Vec::new()),
+                    )
+                    .await;
+                    return;
+                }
+
+                // The other players need the session to verify the messages
+                if let Some(entry) = tablist.get_mut(player_uuid) {
+                    entry.chat_session = Some(update.session);
+                }
+                for update in tablist.changes().updates {
+                    if let Err(e) = connection
+                        .send_packet(&protocol::packet::Packet { inner: update })
+                        .await
+                    {
+                        close_on_error(connection, e).await;
+                        return;
+                    }
+                }
+            }
+            protocol::play::server::Play::KeepAlive(answer) => {
+                let latency = match keep_alive.received(answer.id, std::time::Instant::now()) {
+                    Ok(l) => l,
+                    Err(e) => {
+                        tracing::info!("Keep Alive: {:?}", e);
+                        disconnect(
+                            connection,
+                            protocol::general::TextComponent::translate(
+                                "disconnect.timeout",
+                                Vec::new(),
+                            ),
+                        )
+                        .await;
+                        return;
+                    }
+                };
+
+                if let Some(entry) = tablist.get_mut(player_uuid) {
+                    entry.latency = latency.as_millis() as i32;
+                }
+                for update in tablist.changes().updates {
+                    if let Err(e) = connection
+                        .send_packet(&protocol::packet::Packet { inner: update })
+                        .await
+                    {
+                        close_on_error(connection, e).await;
+                        return;
+                    }
+                }
+            }
+            protocol::play::server::Play::MessageAcknowledgment(ack) => {
+                if let Err(e) = chat.acknowledge(ack.message_count) {
+                    tracing::warn!("Invalid Message Acknowledgment: {:?}", e);
+                }
+            }
+            protocol::play::server::Play::ChatMessage(message) => {
+                let received = match chat.handle_message(&message) {
+                    Ok(r) => r,
+                    Err(e) => {
+                        tracing::info!("Kicking Player: {:?}", e);
+
+                        disconnect(
+                            connection,
+                            protocol::general::TextComponent::translate(
+                                "multiplayer.disconnect.chat_validation_failed",
+                                Vec::new(),
+                            ),
+                        )
+                        .await;
+                        return;
+                    }
+                };
+
+                tracing::info!("Chat: {:?}", received.message.content);
+
+                let packet = protocol::packet::Packet {
+                    inner: received.packet(&chat_type, player_name.clone(), None),
+                };
+                if let Err(e) = connection.send_packet(&packet).await {
+                    close_on_error(connection, e).await;
+                    return;
+                }
+                if let Some(signature) = received.signature {
+                    chat.track_sent(signature);
+                }
+            }
+            protocol::play::server::Play::PluginMessage(pm) => {
+                tracing::info!("Plugin Message: {:?}", pm);
+
+                if let Err(e) =
+                    plugin_channels.handle(&mut plugin_state, &mut (), &pm.channel.0, &pm.data)
+                {
+                    tracing::warn!("Handling Plugin Message: {:?}", e);
+                }
+            }
+            protocol::play::server::Play::ResourcePackResponse(response) => {
+                tracing::info!("Resource Pack Response: {:?}", response);
+
+                match resource_packs.handle(response.uuid, response.result) {
+                    Ok(_) => {}
+                    Err(server::resource_pack::ResourcePackError::UnknownPack(uuid)) => {
+                        tracing::warn!("Response for unknown Resource Pack: {:x}", uuid);
+                    }
+                    Err(e) => {
+                        tracing::info!("Kicking Player: {:?}", e);
+
+                        disconnect(
+                            connection,
+                            protocol::general::TextComponent::text(
+                                "This server requires its resource pack",
+                            ),
+                        )
+                        .await;
+                        return;
+                    }
+                };
+            }
+            other => {
+                tracing::debug!(?other);
+            }
+        };
+    }
+}
+
+/// Sends the next keep alive if one is due and disconnects clients that did not answer the last
+/// one in time, returns the connection if it is still open
+async fn send_keep_alive<S, P>(
+    mut connection: Connection<S>,
+    keep_alive: &mut server::keep_alive::KeepAlive,
+    packet: impl FnOnce(i64) -> P,
+) -> Option<Connection<S>>
+where
+    S: Transport,
+    P: protocol::packet::PacketContent,
+{
+    match keep_alive.poll(std::time::Instant::now()) {
+        Ok(Some(id)) => {
+            if let Err(e) = connection
+                .send_packet(&protocol::packet::Packet { inner: packet(id) })
+                .await
+            {
+                close_on_error(connection, e).await;
+                return None;
+            }
+        }
+        Ok(None) => {}
+        Err(e) => {
+            tracing::info!("Keep Alive: {:?}", e);
+            disconnect(
+                connection,
+                protocol::general::TextComponent::translate("disconnect.timeout", Vec::new()),
+            )
+            .await;
+            return None;
+        }
+    };
+
+    Some(connection)
+}
+
+/// Logs the error and, if the connection is still usable, tells the client why it is being
+/// disconnected
+async fn close_on_error<S>(connection: Connection<S>, error: NetworkError)
+where
+    S: Transport,
+{
+    let reason = match &error {
+        NetworkError::Eof => {
+            tracing::info!(state = ?connection.state(), "Client closed the connection");
+            return;
+        }
+        NetworkError::Io(e) => {
+            tracing::warn!(state = ?connection.state(), "Connection failed: {:?}", e);
+            return;
+        }
+        NetworkError::Crypto(e) => {
+            tracing::error!(state = ?connection.state(), "Encryption failed: {:?}", e);
+            return;
+        }
+        NetworkError::Timeout => {
+            tracing::info!(state = ?connection.state(), "Connection timed out");
+            "disconnect.timeout"
+        }
+        NetworkError::Parse(_) | NetworkError::FrameTooLarge { .. } => {
+            tracing::warn!(state = ?connection.state(), "Invalid Packet: {:?}", error);
+            "disconnect.packetError"
+        }
+    };
+
+    disconnect(
+        connection,
+        protocol::general::TextComponent::translate(reason, Vec::new()),
+    )
+    .await;
+}
+
+/// Disconnects the player with the reason, failing to send it is only logged as the connection
+/// is closed either way
+async fn disconnect<S>(connection: Connection<S>, reason: protocol::general::TextComponent)
+where
+    S: Transport,
+{
+    tracing::info!(state = ?connection.state(), ?reason, "Disconnecting");
+
+    if let Err(e) = connection.disconnect(reason).await {
+        tracing::warn!("Sending Disconnect: {:?}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use networking::testing::FakeClient;
+    use protocol::{
+        configuration::client::Configuration, handshake::server::NextState,
+        serialize::SerializeItem,
+    };
+
+    fn config() -> server::config::ServerConfig {
+        server::config::ServerConfig {
+            max_players: 10,
+            motd: "test".into(),
+            authentication: server::config::Authentication::Offline,
+            key: auth::encryption::ServerKey::generate(1024).unwrap(),
+            velocity_secret: None,
+            accepts_transfers: false,
+            registry_mode: server::config::RegistryMode::KnownPacks,
+            resource_packs: Vec::new(),
+            kick_on_declined_pack: true,
+            enforce_secure_chat: false,
+            services_keys: Vec::new(),
+            connection: networking::ConnectionConfig::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn status_and_ping() {
+        let server_conf = config();
+        let players = server::players::PlayerCount::new();
+        let (mut client, connection) = FakeClient::pair();
+
+        let script = async move {
+            client.handshake(NextState::Status).await;
+
+            client
+                .send(protocol::status::server::StatusRequest {})
+                .await;
+            let response = client.expect_raw().await;
+            assert_eq!(0x00, response.id.0);
+            let (_, content) = protocol::general::PString::parse(&response.data).unwrap();
+            let content: serde_json::Value = serde_json::from_str(&content.0).unwrap();
+            assert_eq!(766, content["version"]["protocol"]);
+
+            client.ping(0x1234_5678).await;
+        };
+
+        tokio::join!(handshake(connection, &server_conf, &players), script);
+    }
+
+    #[tokio::test]
+    async fn transfer_rejected() {
+        let server_conf = config();
+        let players = server::players::PlayerCount::new();
+        let (mut client, connection) = FakeClient::pair();
+
+        let script = async move {
+            client.handshake(NextState::Transfer).await;
+
+            let disconnect = client
+                .expect(protocol::login::client::Disconnect::parse)
+                .await;
+            assert!(disconnect.reason.0.contains("does not accept transfers"));
+            client.expect_closed().await;
+        };
+
+        tokio::join!(handshake(connection, &server_conf, &players), script);
+    }
+
+    #[tokio::test]
+    async fn incompatible_version() {
+        let server_conf = config();
+        let players = server::players::PlayerCount::new();
+        let (mut client, connection) = FakeClient::pair();
+
+        let script = async move {
+            client
+                .send(protocol::handshake::server::Handshaking {
+                    protocol_version: protocol::general::VarInt(765),
+                    server_addr: protocol::general::PString("localhost".into()),
+                    server_port: 25565,
+                    next_state: NextState::Login,
+                })
+                .await;
+            client.set_state(ConnectionState::Login);
+
+            let disconnect = client
+                .expect(protocol::login::client::Disconnect::parse)
+                .await;
+            let reason: serde_json::Value = serde_json::from_str(&disconnect.reason.0).unwrap();
+            assert_eq!("multiplayer.disconnect.incompatible", reason["translate"]);
+            assert_eq!("1.20.6", reason["with"][0]["text"]);
+            client.expect_closed().await;
+        };
+
+        tokio::join!(handshake(connection, &server_conf, &players), script);
+    }
+
+    #[tokio::test]
+    async fn server_full() {
+        let server_conf = server::config::ServerConfig {
+            max_players: 1,
+            ..config()
+        };
+        let players = server::players::PlayerCount::new();
+        let _online = players.join(server_conf.max_players).unwrap();
+        let (mut client, connection) = FakeClient::pair();
+
+        let script = async move {
+            client.handshake(NextState::Login).await;
+            client.login_start("Notch", 1234).await;
+
+            let disconnect = client
+                .expect(protocol::login::client::Disconnect::parse)
+                .await;
+            assert_eq!(
+                r#"{"translate":"multiplayer.disconnect.server_full"}"#,
+                disconnect.reason.0
+            );
+            client.expect_closed().await;
+        };
+
+        tokio::join!(handshake(connection, &server_conf, &players), script);
+        assert_eq!(1, players.online());
+    }
+
+    #[tokio::test]
+    async fn offline_login() {
+        let server_conf = config();
+        let players = server::players::PlayerCount::new();
+        let (mut client, connection) = FakeClient::pair();
+
+        let script = async move {
+            client.handshake(NextState::Login).await;
+            client.login_start("Notch", 1234).await;
+
+            // The offline UUID is used instead of the one send by the client
+            let success = client
+                .expect(protocol::login::client::LoginSuccess::parse)
+                .await;
+            assert_eq!(protocol::login::offline_uuid("Notch"), success.uuid);
+            assert_eq!("Notch", success.name.0);
+            client.send(protocol::login::server::LoginAck {}).await;
+
+            client.set_state(ConnectionState::Configuration);
+            assert!(matches!(
+                client.expect(Configuration::parse).await,
+                Configuration::PluginMessage(_)
+            ));
+        };
+
+        // The server continues with the configuration, which is not part of this test
+        tokio::select! {
+            _ = handshake(connection, &server_conf, &players) => panic!("The login did not finish"),
+            _ = script => {}
+        };
+    }
+
+    /// Stands in for the session server, accepting only the session it expects
+    struct StubAuthenticator {
+        shared_secret: Vec<u8>,
+        public_key: Vec<u8>,
+    }
+
+    impl auth::Authenticator for StubAuthenticator {
+        fn requires_encryption(&self) -> bool {
+            true
+        }
+
+        async fn authenticate(
+            &self,
+            name: &str,
+            session: Option<&auth::Session>,
+        ) -> Result<auth::Profile, auth::AuthError> {
+            let session = session.ok_or(auth::AuthError::MissingSession)?;
+            if !session.server_id.is_empty()
+                || session.shared_secret != self.shared_secret
+                || session.public_key != self.public_key
+            {
+                return Err(auth::AuthError::NotAuthenticated);
+            }
+
+            Ok(auth::Profile {
+                uuid: 1234,
+                name: name.to_string(),
+                properties: vec![protocol::login::client::Property {
+                    name: protocol::general::PString("textures".into()),
+                    value: protocol::general::PString("skin".into()),
+                    signature: Some(protocol::general::PString("signature".into())),
+                }],
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn login_encryption() {
+        let server_conf = config();
+        let players = server::players::PlayerCount::new();
+        let shared_secret = [42; 16];
+        let authenticator = StubAuthenticator {
+            shared_secret: shared_secret.to_vec(),
+            public_key: server_conf.key.public_key().to_vec(),
+        };
+        let (mut client, mut connection) = FakeClient::pair();
+        connection.set_state(ConnectionState::Login);
+        client.set_state(ConnectionState::Login);
+
+        let public_key = server_conf.key.public_key().to_vec();
+        let online = &players;
+        let script = async move {
+            let request = client
+                .expect(protocol::login::client::EncryptionRequest::parse)
+                .await;
+            assert_eq!("", request.server_id.0);
+            assert_eq!(public_key, request.pubkey);
+            assert_eq!(4, request.verifytoken.len());
+
+            let key = openssl::rsa::Rsa::public_key_from_der(&request.pubkey).unwrap();
+            let encrypt = |data: &[u8]| {
+                let mut result = vec![0; key.size() as usize];
+                let written = key
+                    .public_encrypt(data, &mut result, openssl::rsa::Padding::PKCS1)
+                    .unwrap();
+                result.truncate(written);
+                result
+            };
+            client
+                .send(protocol::login::server::EncryptionResponse {
+                    shared_secret: encrypt(&shared_secret),
+                    verify_token: encrypt(&request.verifytoken),
+                })
+                .await;
+
+            // The profile of the authenticator is used, including the signed skin
+            let mut client = client.encrypt(&shared_secret);
+            let success = client
+                .expect(protocol::login::client::LoginSuccess::parse)
+                .await;
+            assert_eq!(1234, success.uuid);
+            assert_eq!("Notch", success.name.0);
+            assert_eq!(1, success.properites.len());
+            assert_eq!("textures", success.properites[0].name.0);
+            client.send(protocol::login::server::LoginAck {}).await;
+
+            client.set_state(ConnectionState::Configuration);
+            assert!(matches!(
+                client.expect(Configuration::parse).await,
+                Configuration::PluginMessage(_)
+            ));
+            assert_eq!(1, online.online());
+        };
+
+        // The server continues with the configuration, which is not part of this test
+        tokio::select! {
+            _ = authenticate(connection, &authenticator, "Notch", &server_conf, &players) => {
+                panic!("The login did not finish")
+            }
+            _ = script => {}
+        };
+    }
+
+    #[tokio::test]
+    async fn login_encryption_wrong_token() {
+        let key = auth::encryption::ServerKey::generate(1024).unwrap();
+        let (mut client, mut connection) = FakeClient::pair();
+        connection.set_state(ConnectionState::Login);
+        client.set_state(ConnectionState::Login);
+
+        let script = async move {
+            let request = client
+                .expect(protocol::login::client::EncryptionRequest::parse)
+                .await;
+
+            let key = openssl::rsa::Rsa::public_key_from_der(&request.pubkey).unwrap();
+            let encrypt = |data: &[u8]| {
+                let mut result = vec![0; key.size() as usize];
+                let written = key
+                    .public_encrypt(data, &mut result, openssl::rsa::Padding::PKCS1)
+                    .unwrap();
+                result.truncate(written);
+                result
+            };
+            let wrong_token: Vec<u8> = request.verifytoken.iter().map(|b| !b).collect();
+            client
+                .send(protocol::login::server::EncryptionResponse {
+                    shared_secret: encrypt(&[42; 16]),
+                    verify_token: encrypt(&wrong_token),
+                })
+                .await;
+
+            // The connection is not encrypted, as the token was rejected
+            let disconnect = client
+                .expect(protocol::login::client::Disconnect::parse)
+                .await;
+            assert!(disconnect.reason.0.contains("Invalid verify token"));
+            client.expect_closed().await;
+        };
+
+        let server = async {
+            assert!(enable_encryption(connection, &key).await.is_none());
+        };
+
+        tokio::join!(server, script);
+    }
+
+    #[tokio::test]
+    async fn configuration_known_packs() {
+        let server_conf = config();
+        let (mut client, mut connection) = FakeClient::pair();
+        connection.set_state(ConnectionState::Configuration);
+        client.set_state(ConnectionState::Configuration);
+
+        let script = async move {
+            match client.expect(Configuration::parse).await {
+                Configuration::PluginMessage(message) => {
+                    assert_eq!("minecraft:brand", message.channel.0)
+                }
+                other => panic!("Expected the brand, got {:?}", other),
+            };
+            match client.expect(Configuration::parse).await {
+                Configuration::FeatureFlags(flags) => {
+                    assert_eq!(
+                        vec!["minecraft:vanilla"],
+                        flags.flags.iter().map(|f| f.0.as_ref()).collect::<Vec<_>>()
+                    )
+                }
+                other => panic!("Expected the feature flags, got {:?}", other),
+            };
+            let packs = match client.expect(Configuration::parse).await {
+                Configuration::KnownPacks(packs) => packs.packs,
+                other => panic!("Expected the known packs, got {:?}", other),
+            };
+
+            client
+                .send(protocol::configuration::server::KnownPacks { packs })
+                .await;
+
+            for (name, _) in server::data::registry::all_registries(false) {
+                match client.expect(Configuration::parse).await {
+                    Configuration::RegistryData(data) => assert_eq!(name, data.id),
+                    other => panic!("Expected registry {:?}, got {:?}", name, other),
+                };
+            }
+            assert!(matches!(
+                client.expect(Configuration::parse).await,
+                Configuration::UpdateTags(_)
+            ));
+            assert!(matches!(
+                client.expect(Configuration::parse).await,
+                Configuration::Finish(_)
+            ));
+        };
+
+        tokio::join!(
+            configuration(
+                connection,
+                &server_conf,
+                server::tablist::TabEntry::new(1234, "test")
+            ),
+            script
+        );
     }
 }
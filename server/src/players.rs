@@ -0,0 +1,64 @@
+//! Keeps track of the number of players online, to enforce the player limit of the server
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The number of players online, shared by all the connections
+#[derive(Debug, Default)]
+pub struct PlayerCount {
+    online: AtomicUsize,
+}
+
+/// A player counted by [`PlayerCount::join`], who is counted until this is dropped
+#[derive(Debug)]
+pub struct PlayerSlot<'c> {
+    count: &'c PlayerCount,
+}
+
+impl PlayerCount {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn online(&self) -> usize {
+        self.online.load(Ordering::Acquire)
+    }
+
+    /// Counts a joining player, unless `max` players are already online
+    pub fn join(&self, max: usize) -> Option<PlayerSlot<'_>> {
+        self.online
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |online| {
+                (online < max).then_some(online + 1)
+            })
+            .ok()
+            .map(|_| PlayerSlot { count: self })
+    }
+}
+
+impl Drop for PlayerSlot<'_> {
+    fn drop(&mut self) {
+        self.count.online.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn player_limit() {
+        let count = PlayerCount::new();
+
+        let first = count.join(2).unwrap();
+        let second = count.join(2).unwrap();
+        assert_eq!(2, count.online());
+        assert!(count.join(2).is_none());
+
+        drop(first);
+        assert_eq!(1, count.online());
+        let third = count.join(2);
+        assert!(third.is_some());
+
+        drop((second, third));
+        assert_eq!(0, count.online());
+    }
+}
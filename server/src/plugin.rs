@@ -0,0 +1,221 @@
+//! Handling of Plugin Messages received from clients
+//!
+//! The built-in channels (`minecraft:brand`, `minecraft:register` and `minecraft:unregister`)
+//! are always handled and update the [`ClientPluginState`], custom channels can be registered
+//! with a typed payload using [`PluginChannels::register`].
+
+use std::collections::{HashMap, HashSet};
+
+use protocol::{
+    general::ParseError,
+    plugin::{Brand, PluginChannel, Register, Unregister},
+};
+
+type Handler<S> = Box<dyn Fn(&mut S, &[u8]) -> Result<(), ParseError> + Send + Sync>;
+
+/// The plugin related information known about a single client
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ClientPluginState {
+    /// The brand of the client, like `vanilla` or `fabric`
+    pub brand: Option<String>,
+    /// The channels the client registered for
+    pub channels: HashSet<String>,
+}
+
+impl ClientPluginState {
+    /// Checks if the client is listening on the given channel
+    pub fn supports(&self, channel: &str) -> bool {
+        self.channels.contains(channel)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PluginError {
+    /// No handler was registered for the channel
+    UnknownChannel(String),
+    /// The payload could not be decoded for the channel
+    Decode { channel: String, error: ParseError },
+}
+
+/// A registry of custom plugin channels, the handlers get access to some state `S`
+pub struct PluginChannels<S> {
+    handlers: HashMap<&'static str, Handler<S>>,
+}
+
+impl<S> core::fmt::Debug for PluginChannels<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PluginChannels")
+            .field("channels", &self.handlers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<S> Default for PluginChannels<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> PluginChannels<S> {
+    pub fn new() -> Self {
+        Self {
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// Registers a handler for the channel `P`, replacing any previous handler for it
+    pub fn register<P, F>(mut self, handler: F) -> Self
+    where
+        P: PluginChannel,
+        F: Fn(&mut S, P) + Send + Sync + 'static,
+    {
+        self.handlers.insert(
+            P::CHANNEL,
+            Box::new(move |state, data| {
+                let payload = P::decode(data)?;
+                handler(state, payload);
+                Ok(())
+            }),
+        );
+        self
+    }
+
+    /// All the custom channels, which should be announced to the client using
+    /// [`Register`]
+    pub fn channels(&self) -> Vec<String> {
+        let mut channels: Vec<String> = self.handlers.keys().map(|c| c.to_string()).collect();
+        channels.sort();
+        channels
+    }
+
+    /// The [`Register`] payload announcing all the custom channels
+    pub fn register_payload(&self) -> Register {
+        Register {
+            channels: self.channels(),
+        }
+    }
+
+    /// Handles a received Plugin Message
+    pub fn handle(
+        &self,
+        client: &mut ClientPluginState,
+        state: &mut S,
+        channel: &str,
+        data: &[u8],
+    ) -> Result<(), PluginError> {
+        let decode_err = |error| PluginError::Decode {
+            channel: channel.to_string(),
+            error,
+        };
+
+        match channel {
+            Brand::CHANNEL => {
+                let brand = Brand::decode(data).map_err(decode_err)?;
+                client.brand = Some(brand.brand);
+                Ok(())
+            }
+            Register::CHANNEL => {
+                let register = Register::decode(data).map_err(decode_err)?;
+                client.channels.extend(register.channels);
+                Ok(())
+            }
+            Unregister::CHANNEL => {
+                let unregister = Unregister::decode(data).map_err(decode_err)?;
+                for channel in unregister.channels {
+                    client.channels.remove(&channel);
+                }
+                Ok(())
+            }
+            custom => {
+                let handler = self
+                    .handlers
+                    .get(custom)
+                    .ok_or_else(|| PluginError::UnknownChannel(custom.to_string()))?;
+                handler(state, data).map_err(decode_err)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Counter(u8);
+
+    impl PluginChannel for Counter {
+        const CHANNEL: &'static str = "test:counter";
+
+        fn encode(&self) -> Vec<u8> {
+            vec![self.0]
+        }
+
+        fn decode(data: &[u8]) -> Result<Self, ParseError> {
+            match data {
+                [v] => Ok(Self(*v)),
+                _ => Err(ParseError::Other),
+            }
+        }
+    }
+
+    #[test]
+    fn builtin_channels() {
+        let channels = PluginChannels::<()>::new();
+        let mut client = ClientPluginState::default();
+
+        channels
+            .handle(&mut client, &mut (), "minecraft:brand", b"\x07vanilla")
+            .unwrap();
+        channels
+            .handle(&mut client, &mut (), "minecraft:register", b"a:b\0c:d")
+            .unwrap();
+        channels
+            .handle(&mut client, &mut (), "minecraft:unregister", b"a:b")
+            .unwrap();
+
+        assert_eq!(Some("vanilla".to_string()), client.brand);
+        assert!(!client.supports("a:b"));
+        assert!(client.supports("c:d"));
+    }
+
+    #[test]
+    fn custom_channel() {
+        let channels = PluginChannels::<u32>::new()
+            .register(|total: &mut u32, counter: Counter| *total += counter.0 as u32);
+        let mut client = ClientPluginState::default();
+        let mut total = 0;
+
+        assert_eq!(vec!["test:counter".to_string()], channels.channels());
+
+        channels
+            .handle(
+                &mut client,
+                &mut total,
+                "test:counter",
+                &Counter(3).encode(),
+            )
+            .unwrap();
+        channels
+            .handle(
+                &mut client,
+                &mut total,
+                "test:counter",
+                &Counter(4).encode(),
+            )
+            .unwrap();
+        assert_eq!(7, total);
+
+        assert_eq!(
+            Err(PluginError::Decode {
+                channel: "test:counter".to_string(),
+                error: ParseError::Other
+            }),
+            channels.handle(&mut client, &mut total, "test:counter", &[])
+        );
+        assert_eq!(
+            Err(PluginError::UnknownChannel("test:other".to_string())),
+            channels.handle(&mut client, &mut total, "test:other", &[])
+        );
+    }
+}
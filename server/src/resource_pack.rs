@@ -0,0 +1,297 @@
+//! Pushing resource packs to players and tracking their status
+//!
+//! Every pack is identified by its UUID, the client reports the progress for each of them using
+//! a Resource Pack Response, which is fed into the [`ResourcePackTracker`] of the player.
+
+use protocol::general::{PString, ResourcePackResult, TextComponent};
+
+/// A resource pack offered to the players
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourcePack {
+    pub uuid: u128,
+    pub url: String,
+    /// The hex encoded SHA-1 hash of the pack, used by the client to check its cached version
+    pub hash: String,
+    /// Whether the player has to accept the pack to stay on the server
+    pub required: bool,
+    /// Shown to the player in the prompt to accept the pack
+    pub prompt: Option<TextComponent>,
+}
+
+impl ResourcePack {
+    pub fn new(uuid: u128, url: impl Into<String>, hash: impl Into<String>) -> Self {
+        Self {
+            uuid,
+            url: url.into(),
+            hash: hash.into(),
+            required: false,
+            prompt: None,
+        }
+    }
+
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    pub fn prompt(mut self, prompt: TextComponent) -> Self {
+        self.prompt = Some(prompt);
+        self
+    }
+
+    /// Creates the packet to push the pack during configuration
+    pub fn configuration_packet(&self) -> protocol::configuration::client::AddResourcePack {
+        protocol::configuration::client::AddResourcePack {
+            uuid: self.uuid,
+            url: PString(self.url.clone().into()),
+            hash: PString(self.hash.clone().into()),
+            forced: self.required,
+            prompt: self.prompt.clone(),
+        }
+    }
+
+    /// Creates the packet to push the pack during play
+    pub fn play_packet(&self) -> protocol::play::client::AddResourcePack {
+        protocol::play::client::AddResourcePack {
+            uuid: self.uuid,
+            url: PString(self.url.clone().into()),
+            hash: PString(self.hash.clone().into()),
+            forced: self.required,
+            prompt: self.prompt.clone(),
+        }
+    }
+}
+
+/// Removes a pushed pack, or all of them, from a player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PackRemoval {
+    /// The pack to remove, all the packs are removed if this is `None`
+    pub uuid: Option<u128>,
+}
+
+impl PackRemoval {
+    /// Creates the packet to remove the pack during configuration
+    pub fn configuration_packet(&self) -> protocol::configuration::client::RemoveResourcePack {
+        protocol::configuration::client::RemoveResourcePack { uuid: self.uuid }
+    }
+
+    /// Creates the packet to remove the pack during play
+    pub fn play_packet(&self) -> protocol::play::client::RemoveResourcePack {
+        protocol::play::client::RemoveResourcePack { uuid: self.uuid }
+    }
+}
+
+/// The status of a pack for a single player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackStatus {
+    /// The pack was pushed, but the client did not respond yet
+    Pending,
+    Accepted,
+    Downloaded,
+    Loaded,
+    Declined,
+    /// The client unloaded the pack again, like when it is replaced, which is not a rejection
+    Discarded,
+    /// The pack could not be downloaded or applied, contains the exact reason
+    Failed(ResourcePackResult),
+}
+
+impl PackStatus {
+    /// Whether the client will not send any further responses for the pack
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            Self::Loaded | Self::Declined | Self::Discarded | Self::Failed(_)
+        )
+    }
+}
+
+impl From<ResourcePackResult> for PackStatus {
+    fn from(value: ResourcePackResult) -> Self {
+        match value {
+            ResourcePackResult::Accepted => Self::Accepted,
+            ResourcePackResult::Downloaded => Self::Downloaded,
+            ResourcePackResult::SuccessfullyLoaded => Self::Loaded,
+            ResourcePackResult::Declined => Self::Declined,
+            ResourcePackResult::Discarded => Self::Discarded,
+            other => Self::Failed(other),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ResourcePackError {
+    /// The client responded for a pack that was never pushed to it
+    UnknownPack(u128),
+    /// A required pack was declined or failed and the player should be kicked
+    RequiredPackRejected { uuid: u128, status: PackStatus },
+}
+
+/// Tracks the status of all the packs pushed to a single player
+#[derive(Debug, Clone, Default)]
+pub struct ResourcePackTracker {
+    packs: Vec<(ResourcePack, PackStatus)>,
+    kick_on_decline: bool,
+}
+
+impl ResourcePackTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report players declining or failing to load a required pack as an error from
+    /// [`handle`](Self::handle), so they can be kicked
+    pub fn kick_on_decline(mut self, kick: bool) -> Self {
+        self.kick_on_decline = kick;
+        self
+    }
+
+    /// Records that the pack is pushed to the player, replacing any previous pack with the same
+    /// UUID
+    pub fn push(&mut self, pack: ResourcePack) {
+        self.packs.retain(|(p, _)| p.uuid != pack.uuid);
+        self.packs.push((pack, PackStatus::Pending));
+    }
+
+    /// Removes the pack with the UUID, or all the packs if none is given, returning the
+    /// removal to send to the player
+    pub fn pop(&mut self, uuid: Option<u128>) -> PackRemoval {
+        match uuid {
+            Some(uuid) => self.packs.retain(|(p, _)| p.uuid != uuid),
+            None => self.packs.clear(),
+        };
+
+        PackRemoval { uuid }
+    }
+
+    /// The current status of the pack
+    pub fn status(&self, uuid: u128) -> Option<PackStatus> {
+        self.packs
+            .iter()
+            .find(|(p, _)| p.uuid == uuid)
+            .map(|(_, status)| *status)
+    }
+
+    /// All the packs with their current status
+    pub fn packs(&self) -> impl Iterator<Item = (&ResourcePack, PackStatus)> {
+        self.packs.iter().map(|(p, s)| (p, *s))
+    }
+
+    /// Whether the client finished handling all the pushed packs
+    pub fn is_settled(&self) -> bool {
+        self.packs.iter().all(|(_, s)| s.is_final())
+    }
+
+    /// Updates the status of a pack based on the Resource Pack Response of the client
+    pub fn handle(
+        &mut self,
+        uuid: u128,
+        result: ResourcePackResult,
+    ) -> Result<PackStatus, ResourcePackError> {
+        let (pack, status) = self
+            .packs
+            .iter_mut()
+            .find(|(p, _)| p.uuid == uuid)
+            .ok_or(ResourcePackError::UnknownPack(uuid))?;
+
+        *status = PackStatus::from(result);
+
+        let rejected = matches!(status, PackStatus::Declined | PackStatus::Failed(_));
+        if self.kick_on_decline && pack.required && rejected {
+            return Err(ResourcePackError::RequiredPackRejected {
+                uuid,
+                status: *status,
+            });
+        }
+
+        Ok(*status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    #[test]
+    fn track_status() {
+        let mut tracker = ResourcePackTracker::new();
+        tracker.push(ResourcePack::new(1, "https://example.com/a.zip", HASH));
+        tracker.push(ResourcePack::new(2, "https://example.com/b.zip", HASH));
+
+        assert_eq!(Some(PackStatus::Pending), tracker.status(1));
+        assert_eq!(
+            Ok(PackStatus::Accepted),
+            tracker.handle(1, ResourcePackResult::Accepted)
+        );
+        assert_eq!(
+            Ok(PackStatus::Loaded),
+            tracker.handle(1, ResourcePackResult::SuccessfullyLoaded)
+        );
+        assert!(!tracker.is_settled());
+
+        assert_eq!(
+            Ok(PackStatus::Failed(ResourcePackResult::InvalidUrl)),
+            tracker.handle(2, ResourcePackResult::InvalidUrl)
+        );
+        assert!(tracker.is_settled());
+
+        assert_eq!(
+            Err(ResourcePackError::UnknownPack(3)),
+            tracker.handle(3, ResourcePackResult::Accepted)
+        );
+
+        assert_eq!(Some(1), tracker.pop(Some(1)).play_packet().uuid);
+        assert_eq!(None, tracker.status(1));
+        assert_eq!(None, tracker.pop(None).configuration_packet().uuid);
+        assert_eq!(0, tracker.packs().count());
+    }
+
+    #[test]
+    fn kick_on_decline() {
+        let mut tracker = ResourcePackTracker::new().kick_on_decline(true);
+        tracker.push(ResourcePack::new(1, "https://example.com/a.zip", HASH).required(true));
+        tracker.push(ResourcePack::new(2, "https://example.com/b.zip", HASH));
+
+        assert_eq!(
+            Ok(PackStatus::Declined),
+            tracker.handle(2, ResourcePackResult::Declined)
+        );
+        assert_eq!(
+            Err(ResourcePackError::RequiredPackRejected {
+                uuid: 1,
+                status: PackStatus::Declined
+            }),
+            tracker.handle(1, ResourcePackResult::Declined)
+        );
+    }
+
+    #[test]
+    fn discarded_is_not_rejected() {
+        let mut tracker = ResourcePackTracker::new().kick_on_decline(true);
+        tracker.push(ResourcePack::new(1, "https://example.com/a.zip", HASH).required(true));
+
+        assert_eq!(
+            Ok(PackStatus::Loaded),
+            tracker.handle(1, ResourcePackResult::SuccessfullyLoaded)
+        );
+        assert_eq!(
+            Ok(PackStatus::Discarded),
+            tracker.handle(1, ResourcePackResult::Discarded)
+        );
+        assert!(tracker.is_settled());
+    }
+
+    #[test]
+    fn packets() {
+        let pack = ResourcePack::new(1, "https://example.com/a.zip", HASH)
+            .required(true)
+            .prompt(TextComponent::text("Please accept"));
+
+        let packet = pack.play_packet();
+        assert!(packet.forced);
+        assert_eq!(Some(TextComponent::text("Please accept")), packet.prompt);
+        assert_eq!(HASH, pack.configuration_packet().hash.0);
+    }
+}
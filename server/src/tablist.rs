@@ -0,0 +1,304 @@
+//! The tab list (player list) shown to the clients
+//!
+//! The [`TabList`] stores the desired state of all the entries and remembers what was last send
+//! to the clients, so only the actual changes need to be send.
+
+use std::collections::BTreeMap;
+
+use protocol::{
+    general::{PString, TextComponent, VarInt},
+    login::client::Property,
+    play::client::{ChatSession, PlayerAction, PlayerInfoRemove, PlayerInfoUpdate},
+};
+
+/// A single player in the tab list
+#[derive(Debug, Clone, PartialEq)]
+pub struct TabEntry {
+    pub uuid: u128,
+    pub name: String,
+    pub properties: Vec<Property>,
+    pub chat_session: Option<ChatSession>,
+    pub game_mode: i32,
+    /// Whether the player is shown in the tab list at all
+    pub listed: bool,
+    /// The latency in milliseconds
+    pub latency: i32,
+    /// Replaces the name of the player in the tab list
+    pub display_name: Option<TextComponent>,
+}
+
+impl TabEntry {
+    pub fn new(uuid: u128, name: impl Into<String>) -> Self {
+        Self {
+            uuid,
+            name: name.into(),
+            properties: Vec::new(),
+            chat_session: None,
+            game_mode: 0,
+            listed: true,
+            latency: 0,
+            display_name: None,
+        }
+    }
+
+    pub fn properties(mut self, properties: Vec<Property>) -> Self {
+        self.properties = properties;
+        self
+    }
+
+    fn add_player(&self) -> PlayerAction {
+        PlayerAction::AddPlayer {
+            name: PString(self.name.clone().into()),
+            properties: self.properties.clone(),
+        }
+    }
+
+    fn initialize_chat(&self) -> PlayerAction {
+        PlayerAction::InitializeChat {
+            session: self.chat_session.clone(),
+        }
+    }
+
+    fn update_gamemode(&self) -> PlayerAction {
+        PlayerAction::UpdateGamemode {
+            gamemode: VarInt(self.game_mode),
+        }
+    }
+
+    fn update_listed(&self) -> PlayerAction {
+        PlayerAction::UpdateListed {
+            listed: self.listed,
+        }
+    }
+
+    fn update_latency(&self) -> PlayerAction {
+        PlayerAction::UpdateLatency {
+            ping: VarInt(self.latency),
+        }
+    }
+
+    fn update_display_name(&self) -> PlayerAction {
+        PlayerAction::UpdateDisplayName {
+            display_name: self.display_name.clone(),
+        }
+    }
+
+    /// All the actions needed to add the entry to a client
+    fn all_actions(&self) -> Vec<PlayerAction> {
+        vec![
+            self.add_player(),
+            self.initialize_chat(),
+            self.update_gamemode(),
+            self.update_listed(),
+            self.update_latency(),
+            self.update_display_name(),
+        ]
+    }
+
+    /// The actions needed to turn the previous entry into this one
+    fn changed_actions(&self, previous: &Self) -> Vec<PlayerAction> {
+        let mut actions = Vec::new();
+        if self.chat_session != previous.chat_session {
+            actions.push(self.initialize_chat());
+        }
+        if self.game_mode != previous.game_mode {
+            actions.push(self.update_gamemode());
+        }
+        if self.listed != previous.listed {
+            actions.push(self.update_listed());
+        }
+        if self.latency != previous.latency {
+            actions.push(self.update_latency());
+        }
+        if self.display_name != previous.display_name {
+            actions.push(self.update_display_name());
+        }
+        actions
+    }
+}
+
+/// The packets needed to bring the clients up to date with the [`TabList`]
+#[derive(Debug, Default, PartialEq)]
+pub struct TabListChanges {
+    /// Needs to be send before the updates
+    pub remove: Option<PlayerInfoRemove>,
+    pub updates: Vec<PlayerInfoUpdate>,
+}
+
+impl TabListChanges {
+    pub fn is_empty(&self) -> bool {
+        self.remove.is_none() && self.updates.is_empty()
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TabList {
+    entries: BTreeMap<u128, TabEntry>,
+    sent: BTreeMap<u128, TabEntry>,
+}
+
+impl TabList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces the entry for the player
+    pub fn insert(&mut self, entry: TabEntry) {
+        self.entries.insert(entry.uuid, entry);
+    }
+
+    pub fn remove(&mut self, uuid: u128) -> Option<TabEntry> {
+        self.entries.remove(&uuid)
+    }
+
+    pub fn get(&self, uuid: u128) -> Option<&TabEntry> {
+        self.entries.get(&uuid)
+    }
+
+    pub fn get_mut(&mut self, uuid: u128) -> Option<&mut TabEntry> {
+        self.entries.get_mut(&uuid)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &TabEntry> {
+        self.entries.values()
+    }
+
+    /// The packets to send the complete tab list to a client that just joined
+    pub fn full(&self) -> Option<PlayerInfoUpdate> {
+        if self.sent.is_empty() {
+            return None;
+        }
+
+        Some(PlayerInfoUpdate::new(
+            self.sent
+                .values()
+                .map(|entry| (entry.uuid, entry.all_actions()))
+                .collect(),
+        ))
+    }
+
+    /// Computes the packets for all the changes since the last call and marks them as send.
+    ///
+    /// The players in a single [`PlayerInfoUpdate`] all need to have the same actions, so the
+    /// changes are grouped by the actions needed
+    pub fn changes(&mut self) -> TabListChanges {
+        let mut removed = Vec::new();
+        let mut groups: BTreeMap<i8, Vec<(u128, Vec<PlayerAction>)>> = BTreeMap::new();
+
+        for (uuid, previous) in self.sent.iter() {
+            match self.entries.get(uuid) {
+                None => removed.push(*uuid),
+                // The profile can not be updated, so the player has to be added again
+                Some(current)
+                    if current.name != previous.name
+                        || current.properties != previous.properties =>
+                {
+                    removed.push(*uuid);
+                }
+                Some(_) => {}
+            };
+        }
+
+        for (uuid, current) in self.entries.iter() {
+            let actions = match self.sent.get(uuid) {
+                Some(previous) if !removed.contains(uuid) => current.changed_actions(previous),
+                _ => current.all_actions(),
+            };
+            if actions.is_empty() {
+                continue;
+            }
+
+            let mask = actions.iter().fold(0, |acc, a| acc | a.mask());
+            groups.entry(mask).or_default().push((*uuid, actions));
+        }
+
+        self.sent = self.entries.clone();
+
+        TabListChanges {
+            remove: (!removed.is_empty()).then_some(PlayerInfoRemove { players: removed }),
+            updates: groups.into_values().map(PlayerInfoUpdate::new).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_update_remove() {
+        let mut tablist = TabList::new();
+        assert!(tablist.changes().is_empty());
+
+        tablist.insert(TabEntry::new(1, "first"));
+        tablist.insert(TabEntry::new(2, "second"));
+
+        let changes = tablist.changes();
+        assert_eq!(None, changes.remove);
+        assert_eq!(1, changes.updates.len());
+        assert_eq!(0x3f, changes.updates[0].actions);
+        assert_eq!(2, changes.updates[0].players.len());
+        assert!(tablist.changes().is_empty());
+
+        tablist.get_mut(1).unwrap().latency = 20;
+        tablist.get_mut(2).unwrap().latency = 30;
+        tablist.get_mut(2).unwrap().game_mode = 1;
+
+        let changes = tablist.changes();
+        assert_eq!(
+            vec![
+                PlayerInfoUpdate {
+                    actions: PlayerAction::UPDATE_LATENCY,
+                    players: vec![(1, vec![PlayerAction::UpdateLatency { ping: VarInt(20) }])]
+                },
+                PlayerInfoUpdate {
+                    actions: PlayerAction::UPDATE_GAMEMODE | PlayerAction::UPDATE_LATENCY,
+                    players: vec![(
+                        2,
+                        vec![
+                            PlayerAction::UpdateGamemode {
+                                gamemode: VarInt(1)
+                            },
+                            PlayerAction::UpdateLatency { ping: VarInt(30) }
+                        ]
+                    )]
+                },
+            ],
+            changes.updates
+        );
+
+        tablist.remove(1);
+        assert_eq!(
+            TabListChanges {
+                remove: Some(PlayerInfoRemove { players: vec![1] }),
+                updates: Vec::new(),
+            },
+            tablist.changes()
+        );
+    }
+
+    #[test]
+    fn renamed_player_is_readded() {
+        let mut tablist = TabList::new();
+        tablist.insert(TabEntry::new(1, "first"));
+        tablist.changes();
+
+        tablist.insert(TabEntry::new(1, "renamed"));
+        let changes = tablist.changes();
+        assert_eq!(Some(PlayerInfoRemove { players: vec![1] }), changes.remove);
+        assert_eq!(0x3f, changes.updates[0].actions);
+    }
+
+    #[test]
+    fn full() {
+        let mut tablist = TabList::new();
+        assert_eq!(None, tablist.full());
+
+        tablist.insert(TabEntry::new(1, "first"));
+        tablist.changes();
+
+        let full = tablist.full().unwrap();
+        assert_eq!(0x3f, full.actions);
+        assert_eq!(1, full.players.len());
+    }
+}
@@ -0,0 +1,245 @@
+//! The tags send to the client using the [`UpdateTags`] packet
+//!
+//! Tags group entries of a registry, like all the fluids that behave like water, and the client
+//! relies on some of them for its own logic, like swimming in `minecraft:water`.
+
+use std::collections::BTreeMap;
+
+use protocol::{
+    configuration::client::UpdateTags,
+    general::{PString, VarInt},
+};
+use registry::{Block, EntityType, Fluid, Item};
+
+/// The name of the block registry, which is not one of the simple registries
+const BLOCK_REGISTRY: &str = "minecraft:block";
+
+/// A collection of tags for the different registries
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TagRegistry {
+    registries: BTreeMap<String, BTreeMap<String, Vec<i32>>>,
+}
+
+impl TagRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The tags needed by the vanilla client
+    pub fn vanilla() -> Self {
+        Self::new()
+            .with(
+                BLOCK_REGISTRY,
+                "minecraft:climbable",
+                [
+                    Block::Ladder,
+                    Block::Vine,
+                    Block::Scaffolding,
+                    Block::WeepingVines,
+                    Block::WeepingVinesPlant,
+                    Block::TwistingVines,
+                    Block::TwistingVinesPlant,
+                    Block::CaveVines,
+                    Block::CaveVinesPlant,
+                ]
+                .map(|block| i32::from(block.id())),
+            )
+            .with(
+                BLOCK_REGISTRY,
+                "minecraft:fire",
+                [Block::Fire, Block::SoulFire].map(|block| i32::from(block.id())),
+            )
+            .with(
+                Item::REGISTRY,
+                "minecraft:arrows",
+                [Item::Arrow, Item::TippedArrow, Item::SpectralArrow].map(Item::id),
+            )
+            .with(
+                Item::REGISTRY,
+                "minecraft:swords",
+                [
+                    Item::DiamondSword,
+                    Item::StoneSword,
+                    Item::GoldenSword,
+                    Item::NetheriteSword,
+                    Item::WoodenSword,
+                    Item::IronSword,
+                ]
+                .map(Item::id),
+            )
+            .with(
+                Fluid::REGISTRY,
+                "minecraft:water",
+                [Fluid::Water.id(), Fluid::FlowingWater.id()],
+            )
+            .with(
+                Fluid::REGISTRY,
+                "minecraft:lava",
+                [Fluid::Lava.id(), Fluid::FlowingLava.id()],
+            )
+            .with(
+                EntityType::REGISTRY,
+                "minecraft:arrows",
+                [EntityType::Arrow, EntityType::SpectralArrow].map(EntityType::id),
+            )
+            .with(
+                EntityType::REGISTRY,
+                "minecraft:skeletons",
+                [
+                    EntityType::Skeleton,
+                    EntityType::Stray,
+                    EntityType::WitherSkeleton,
+                    EntityType::SkeletonHorse,
+                    EntityType::Bogged,
+                ]
+                .map(EntityType::id),
+            )
+    }
+
+    /// Adds the entries to the tag in the given registry, like `minecraft:block`,
+    /// `minecraft:item`, `minecraft:fluid` or `minecraft:entity_type`
+    pub fn with(
+        mut self,
+        registry: &str,
+        tag: &str,
+        entries: impl IntoIterator<Item = i32>,
+    ) -> Self {
+        self.insert(registry, tag, entries);
+        self
+    }
+
+    /// Adds the entries to the tag in the given registry
+    pub fn insert(&mut self, registry: &str, tag: &str, entries: impl IntoIterator<Item = i32>) {
+        let values = self
+            .registries
+            .entry(registry.to_string())
+            .or_default()
+            .entry(tag.to_string())
+            .or_default();
+
+        for entry in entries {
+            if !values.contains(&entry) {
+                values.push(entry);
+            }
+        }
+    }
+
+    /// The entries of a tag
+    pub fn get(&self, registry: &str, tag: &str) -> Option<&[i32]> {
+        self.registries
+            .get(registry)?
+            .get(tag)
+            .map(|v| v.as_slice())
+    }
+
+    /// Creates the packet containing all the tags
+    pub fn packet(&self) -> UpdateTags {
+        UpdateTags {
+            tags: self
+                .registries
+                .iter()
+                .map(|(registry, tags)| {
+                    (
+                        PString(registry.clone().into()),
+                        tags.iter()
+                            .map(|(tag, entries)| {
+                                (
+                                    PString(tag.clone().into()),
+                                    entries.iter().map(|e| VarInt(*e)).collect(),
+                                )
+                            })
+                            .collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vanilla_blocks() {
+        let tags = TagRegistry::vanilla();
+
+        assert_eq!(
+            Some([196, 317, 772, 805, 806, 807, 808, 1009, 1010].as_slice()),
+            tags.get("minecraft:block", "minecraft:climbable")
+        );
+        assert_eq!(
+            Some([173, 174].as_slice()),
+            tags.get("minecraft:block", "minecraft:fire")
+        );
+    }
+
+    #[test]
+    fn vanilla_items() {
+        let tags = TagRegistry::vanilla();
+
+        assert_eq!(
+            Some([801, 1160, 1159].as_slice()),
+            tags.get("minecraft:item", "minecraft:arrows")
+        );
+        assert_eq!(
+            Some([837, 822, 827, 842, 817, 832].as_slice()),
+            tags.get("minecraft:item", "minecraft:swords")
+        );
+    }
+
+    #[test]
+    fn vanilla_fluids() {
+        let tags = TagRegistry::vanilla();
+
+        assert_eq!(
+            Some([2, 1].as_slice()),
+            tags.get("minecraft:fluid", "minecraft:water")
+        );
+        assert_eq!(
+            Some([4, 3].as_slice()),
+            tags.get("minecraft:fluid", "minecraft:lava")
+        );
+    }
+
+    #[test]
+    fn vanilla_entity_types() {
+        let tags = TagRegistry::vanilla();
+
+        assert_eq!(
+            Some([4, 99].as_slice()),
+            tags.get("minecraft:entity_type", "minecraft:arrows")
+        );
+        assert_eq!(
+            Some([91, 102, 120, 92, 11].as_slice()),
+            tags.get("minecraft:entity_type", "minecraft:skeletons")
+        );
+    }
+
+    #[test]
+    fn packet() {
+        let tags = TagRegistry::new()
+            .with("minecraft:item", "test:b", [3, 3, 1])
+            .with("minecraft:block", "test:a", [0])
+            .with("minecraft:item", "test:a", [2]);
+
+        assert_eq!(
+            UpdateTags {
+                tags: vec![
+                    (
+                        PString("minecraft:block".into()),
+                        vec![(PString("test:a".into()), vec![VarInt(0)])]
+                    ),
+                    (
+                        PString("minecraft:item".into()),
+                        vec![
+                            (PString("test:a".into()), vec![VarInt(2)]),
+                            (PString("test:b".into()), vec![VarInt(3), VarInt(1)])
+                        ]
+                    )
+                ]
+            },
+            tags.packet()
+        );
+    }
+}
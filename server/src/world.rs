@@ -1,6 +1,9 @@
 //! All the world related information
 
+use registry::{Block as RegistryBlock, BlockState};
+
 pub mod coordinates;
+pub mod light;
 
 mod chunk;
 pub use chunk::Chunk;
@@ -43,6 +46,90 @@ pub enum BlockData {
     Air,
     Stone,
     Dirt,
-    Water { height: u8 },
+    Water {
+        height: u8,
+    },
     WaterSource,
+    /// Any other block, which has no special handling yet
+    State(BlockState),
+}
+
+impl BlockData {
+    /// Whether the block is included in the given heightmap
+    pub fn in_heightmap(&self, kind: protocol::chunk::HeightmapKind) -> bool {
+        match kind {
+            protocol::chunk::HeightmapKind::WorldSurface => !self.state().is_air(),
+            // Fluids are included, so rain stops at the surface of water
+            protocol::chunk::HeightmapKind::MotionBlocking => match self {
+                Self::Stone | Self::Dirt | Self::Water { .. } | Self::WaterSource => true,
+                Self::Air => false,
+                Self::State(state) => !state.is_air(),
+            },
+        }
+    }
+
+    /// The block state from the registry
+    pub fn state(&self) -> BlockState {
+        match self {
+            Self::Air => BlockState::AIR,
+            Self::Stone => RegistryBlock::Stone.default_state(),
+            Self::Dirt => RegistryBlock::Dirt.default_state(),
+            // The water states are ordered by their level, with 0 being the source
+            Self::Water { height } => RegistryBlock::Water
+                .default_state()
+                .with("level", &(*height).min(15).to_string())
+                .expect("Water has a level from 0 to 15"),
+            Self::WaterSource => RegistryBlock::Water.default_state(),
+            Self::State(state) => *state,
+        }
+    }
+
+    /// Converts the state into the block data, using the specific variants where possible
+    pub fn from_state(state: BlockState) -> Self {
+        match state.block() {
+            RegistryBlock::Air => Self::Air,
+            RegistryBlock::Stone => Self::Stone,
+            RegistryBlock::Dirt => Self::Dirt,
+            RegistryBlock::Water => match state.get("level").and_then(|l| l.parse().ok()) {
+                Some(0) | None => Self::WaterSource,
+                Some(height) => Self::Water { height },
+            },
+            _ => Self::State(state),
+        }
+    }
+
+    /// The ID of the block state used in the protocol
+    pub fn state_id(&self) -> i32 {
+        self.state().id() as i32
+    }
+}
+
+impl From<BlockState> for BlockData {
+    fn from(value: BlockState) -> Self {
+        Self::from_state(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_states() {
+        assert_eq!(0, BlockData::Air.state_id());
+        assert_eq!(1, BlockData::Stone.state_id());
+        assert_eq!(10, BlockData::Dirt.state_id());
+        assert_eq!(80, BlockData::WaterSource.state_id());
+        assert_eq!(87, BlockData::Water { height: 7 }.state_id());
+
+        for data in [
+            BlockData::Air,
+            BlockData::Dirt,
+            BlockData::WaterSource,
+            BlockData::Water { height: 7 },
+            BlockData::State(RegistryBlock::GrassBlock.default_state()),
+        ] {
+            assert_eq!(data, BlockData::from_state(data.state()));
+        }
+    }
 }
@@ -1,3 +1,5 @@
+use protocol::chunk::{ChunkData, ChunkSection, Heightmap, HeightmapKind};
+
 use crate::world::{
     coordinates::{ChunkCoordinate, WorldChunkCoordinate, WorldCoordinate},
     Block, BlockData,
@@ -11,6 +13,8 @@ pub struct Chunk<const L: i16, const U: i16> {
     /// The Chunk-Z coordinate
     z: WorldChunkCoordinate,
     blocks: Vec<Block>,
+    /// The heightmaps send to the client, updated whenever a block changes
+    heightmaps: [(HeightmapKind, Heightmap); 2],
 }
 
 impl<const L: i16, const U: i16> core::fmt::Debug for Chunk<L, U> {
@@ -53,8 +57,8 @@ impl<const L: i16, const U: i16> Chunk<L, U> {
     pub fn air(x: WorldChunkCoordinate, z: WorldChunkCoordinate) -> Self {
         let mut blocks = Vec::with_capacity(U.abs_diff(L) as usize * 16 * 16);
         for y in L..U {
-            for x in 0..16 {
-                for z in 0..16 {
+            for z in 0..16 {
+                for x in 0..16 {
                     blocks.push(Block {
                         last_modified: 0,
                         x,
@@ -66,7 +70,12 @@ impl<const L: i16, const U: i16> Chunk<L, U> {
             }
         }
 
-        Self { x, z, blocks }
+        Self {
+            x,
+            z,
+            blocks,
+            heightmaps: Self::empty_heightmaps(),
+        }
     }
 
     pub fn from_fn<F>(x: WorldChunkCoordinate, z: WorldChunkCoordinate, mut func: F) -> Self
@@ -75,8 +84,8 @@ impl<const L: i16, const U: i16> Chunk<L, U> {
     {
         let mut blocks = Vec::with_capacity(U.abs_diff(L) as usize * 16 * 16);
         for y in L..U {
-            for x in 0..16 {
-                for z in 0..16 {
+            for z in 0..16 {
+                for x in 0..16 {
                     blocks.push(Block {
                         last_modified: 0,
                         x,
@@ -88,7 +97,76 @@ impl<const L: i16, const U: i16> Chunk<L, U> {
             }
         }
 
-        Self { x, z, blocks }
+        let mut chunk = Self {
+            x,
+            z,
+            blocks,
+            heightmaps: Self::empty_heightmaps(),
+        };
+        for x in 0..16 {
+            for z in 0..16 {
+                for idx in 0..chunk.heightmaps.len() {
+                    let kind = chunk.heightmaps[idx].0;
+                    let height = chunk.column_height(kind, x, z, U - 1);
+                    chunk.heightmaps[idx].1.set(x as usize, z as usize, height);
+                }
+            }
+        }
+        chunk
+    }
+
+    fn empty_heightmaps() -> [(HeightmapKind, Heightmap); 2] {
+        [
+            (HeightmapKind::MotionBlocking, Heightmap::new()),
+            (HeightmapKind::WorldSurface, Heightmap::new()),
+        ]
+    }
+
+    /// The height of the highest block in the heightmap at or below `from_y`
+    fn column_height(&self, kind: HeightmapKind, x: u8, z: u8, from_y: i16) -> u16 {
+        (L..=from_y)
+            .rev()
+            .find(|y| {
+                let idx =
+                    ((y + Self::OFFSET) as usize) * 16 * 16 + (z as usize) * 16 + (x as usize);
+                self.blocks[idx].data.in_heightmap(kind)
+            })
+            .map(|y| (y - L + 1) as u16)
+            .unwrap_or(0)
+    }
+
+    /// Updates the heightmaps after the block at the position changed
+    fn update_heightmaps(&mut self, x: u8, y: i16, z: u8) {
+        let idx = ((y + Self::OFFSET) as usize) * 16 * 16 + (z as usize) * 16 + (x as usize);
+        let height = (y - L + 1) as u16;
+
+        for map_idx in 0..self.heightmaps.len() {
+            let (kind, map) = &self.heightmaps[map_idx];
+            let current = map.get(x as usize, z as usize);
+
+            let updated = if self.blocks[idx].data.in_heightmap(*kind) {
+                current.max(height)
+            } else if current == height {
+                // The highest block was removed, so the next one below needs to be found
+                self.column_height(*kind, x, z, y - 1)
+            } else {
+                current
+            };
+
+            self.heightmaps[map_idx]
+                .1
+                .set(x as usize, z as usize, updated);
+        }
+    }
+
+    /// The heightmap of the given kind
+    pub fn heightmap(&self, kind: HeightmapKind) -> &Heightmap {
+        &self
+            .heightmaps
+            .iter()
+            .find(|(k, _)| *k == kind)
+            .expect("All the heightmaps are maintained")
+            .1
     }
 
     pub fn world_coordinates(&self) -> (WorldCoordinate, WorldCoordinate) {
@@ -126,6 +204,52 @@ impl<const L: i16, const U: i16> Chunk<L, U> {
         let block = self.blocks.get_mut(idx).unwrap(); // TODO
         block.last_modified = tick;
         block.data = value;
+
+        self.update_heightmaps(x.0, y, z.0);
+    }
+
+    /// Encodes the blocks into the sections send to the client, all in the given biome
+    pub fn sections(&self, biome: i32) -> Vec<ChunkSection> {
+        self.blocks
+            .chunks(16 * 16 * 16)
+            .map(|blocks| {
+                let states: Vec<i32> = blocks.iter().map(|b| b.block().state_id()).collect();
+                if states.len() != 16 * 16 * 16 {
+                    // An incomplete section at the top of the world, which the client can not
+                    // receive
+                    return ChunkSection::empty(biome);
+                }
+
+                ChunkSection::from_dense(&states, &[biome; 64])
+                    .expect("The states have the correct length and fit into the palette")
+            })
+            .collect()
+    }
+
+    /// Creates the packet to send the chunk to the client, including its light
+    pub fn packet(&self, biome: i32) -> protocol::play::client::ChunkDataAndUpdateLight {
+        protocol::play::client::ChunkDataAndUpdateLight {
+            chunk_x: self.x.0 as i32,
+            chunk_z: self.z.0 as i32,
+            height_maps: protocol::play::client::HeightMap::new(
+                U.abs_diff(L),
+                &[
+                    (
+                        HeightmapKind::MotionBlocking,
+                        self.heightmap(HeightmapKind::MotionBlocking),
+                    ),
+                    (
+                        HeightmapKind::WorldSurface,
+                        self.heightmap(HeightmapKind::WorldSurface),
+                    ),
+                ],
+            ),
+            data: ChunkData {
+                sections: self.sections(biome),
+            },
+            block_entities: Vec::new(),
+            light: self.light().light_data(),
+        }
     }
 
     pub fn update_block<F, T>(
@@ -146,9 +270,10 @@ impl<const L: i16, const U: i16> Chunk<L, U> {
 
         let block = self.blocks.get_mut(idx).ok_or(())?;
 
-        let result = update(block)?;
+        let result = update(block);
+        self.update_heightmaps(x.0, y, z.0);
 
-        Ok(result)
+        result
     }
 }
 
@@ -171,6 +296,93 @@ mod tests {
         assert_eq!(BlockData::Air, block.data);
     }
 
+    #[test]
+    fn sections() {
+        let chunk = Chunk::<0, 32>::from_fn(
+            WorldChunkCoordinate(1),
+            WorldChunkCoordinate(2),
+            |x, _, y| match (x, y) {
+                (1, 0) => BlockData::Dirt,
+                (_, 0..16) => BlockData::Stone,
+                _ => BlockData::Air,
+            },
+        );
+
+        let sections = chunk.sections(39);
+        assert_eq!(2, sections.len());
+        assert_eq!(4096, sections[0].block_count);
+        assert_eq!(ChunkSection::empty(39), sections[1]);
+
+        let blocks = sections[0].blocks().unwrap();
+        assert_eq!(
+            BlockData::Dirt.state_id(),
+            blocks[ChunkSection::block_index(1, 0, 0)]
+        );
+        assert_eq!(
+            BlockData::Stone.state_id(),
+            blocks[ChunkSection::block_index(0, 0, 1)]
+        );
+        assert_eq!(
+            &BlockData::Dirt,
+            chunk
+                .get_block(ChunkCoordinate(1), 0, ChunkCoordinate(0))
+                .unwrap()
+                .block()
+        );
+
+        let packet = chunk.packet(39);
+        assert_eq!((1, 2), (packet.chunk_x, packet.chunk_z));
+    }
+
+    #[test]
+    fn heightmaps() {
+        let mut chunk = Chunk::<-16, 16>::from_fn(
+            WorldChunkCoordinate(0),
+            WorldChunkCoordinate(0),
+            |x, _, y| match (x, y) {
+                (_, ..0) => BlockData::Stone,
+                (0, 0) => BlockData::WaterSource,
+                _ => BlockData::Air,
+            },
+        );
+
+        let motion_blocking = chunk.heightmap(HeightmapKind::MotionBlocking);
+        assert_eq!(16, motion_blocking.get(1, 0));
+        assert_eq!(17, motion_blocking.get(0, 0));
+
+        chunk.set_block(
+            ChunkCoordinate(1),
+            10,
+            ChunkCoordinate(0),
+            1,
+            BlockData::Dirt,
+        );
+        assert_eq!(27, chunk.heightmap(HeightmapKind::WorldSurface).get(1, 0));
+
+        chunk.set_block(
+            ChunkCoordinate(1),
+            10,
+            ChunkCoordinate(0),
+            2,
+            BlockData::Air,
+        );
+        assert_eq!(16, chunk.heightmap(HeightmapKind::WorldSurface).get(1, 0));
+
+        chunk
+            .update_block(ChunkCoordinate(0), 0, ChunkCoordinate(0), |block| {
+                block.data = BlockData::Air;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(16, chunk.heightmap(HeightmapKind::MotionBlocking).get(0, 0));
+
+        let packet = chunk.packet(0);
+        assert_eq!(
+            Some(Ok(chunk.heightmap(HeightmapKind::WorldSurface).clone())),
+            packet.height_maps.get(HeightmapKind::WorldSurface, 32)
+        );
+    }
+
     #[test]
     fn update_value_in_chunk() {
         let mut chunk = Chunk::<0, 100>::air(WorldChunkCoordinate(0), WorldChunkCoordinate(0));
@@ -0,0 +1,378 @@
+//! Sky- and block-light propagation
+//!
+//! The light is calculated for a single chunk column, so light from the neighbouring chunks is
+//! not taken into account.
+
+use std::collections::VecDeque;
+
+use protocol::chunk::{LightArray, LightData};
+use registry::{Block, BlockState};
+
+use crate::world::{coordinates::ChunkCoordinate, BlockData, Chunk};
+
+/// The highest light level
+pub const MAX_LIGHT: u8 = 15;
+
+impl BlockData {
+    /// How much the light level decreases when passing through the block
+    pub fn light_opacity(&self) -> u8 {
+        match self {
+            Self::Air => 0,
+            Self::Water { .. } | Self::WaterSource => 1,
+            Self::Stone | Self::Dirt => MAX_LIGHT,
+            // Without the block shapes every other block is treated as a full block
+            Self::State(state) if state.is_air() => 0,
+            Self::State(_) => MAX_LIGHT,
+        }
+    }
+
+    /// The light level emitted by the block itself
+    pub fn light_emission(&self) -> u8 {
+        match self {
+            Self::Air | Self::Stone | Self::Dirt | Self::Water { .. } | Self::WaterSource => 0,
+            Self::State(state) => state_light_emission(*state),
+        }
+    }
+}
+
+/// The light level emitted by a block state, blocks like furnaces only emit light while lit
+fn state_light_emission(state: BlockState) -> u8 {
+    let lit = state.get("lit") == Some("true");
+
+    match state.block() {
+        Block::Lava
+        | Block::Fire
+        | Block::Glowstone
+        | Block::JackOLantern
+        | Block::Lantern
+        | Block::SeaLantern
+        | Block::Shroomlight
+        | Block::Beacon
+        | Block::EndPortal
+        | Block::OchreFroglight
+        | Block::VerdantFroglight
+        | Block::PearlescentFroglight => MAX_LIGHT,
+        Block::Torch | Block::WallTorch | Block::EndRod => 14,
+        Block::Furnace | Block::BlastFurnace | Block::Smoker if lit => 13,
+        Block::NetherPortal => 11,
+        Block::SoulFire
+        | Block::SoulTorch
+        | Block::SoulWallTorch
+        | Block::SoulLantern
+        | Block::CryingObsidian => 10,
+        Block::RedstoneTorch | Block::RedstoneWallTorch if lit => 7,
+        Block::MagmaBlock => 3,
+        Block::Campfire | Block::RedstoneLamp if lit => MAX_LIGHT,
+        Block::SoulCampfire if lit => 10,
+        Block::Light => state
+            .get("level")
+            .and_then(|level| level.parse().ok())
+            .unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// The light levels of a `16xHx16` column, with `y` starting at the bottom of the column
+#[derive(Debug, Clone, PartialEq)]
+pub struct LightGrid {
+    height: usize,
+    levels: Vec<u8>,
+}
+
+impl LightGrid {
+    fn new(height: usize) -> Self {
+        Self {
+            height,
+            levels: vec![0; height * 16 * 16],
+        }
+    }
+
+    fn index(x: usize, y: usize, z: usize) -> usize {
+        (y * 16 + z) * 16 + x
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> u8 {
+        self.levels[Self::index(x, y, z)]
+    }
+
+    fn set(&mut self, x: usize, y: usize, z: usize, level: u8) {
+        self.levels[Self::index(x, y, z)] = level;
+    }
+
+    /// Splits the column into the arrays for every section of `16` blocks
+    pub fn sections(&self) -> Vec<LightArray> {
+        self.levels
+            .chunks(16 * 16 * 16)
+            .map(|levels| {
+                let mut array = LightArray::default();
+                for (idx, level) in levels.iter().enumerate() {
+                    let (x, z, y) = (idx % 16, (idx / 16) % 16, idx / 256);
+                    array.set(x, y, z, *level);
+                }
+                array
+            })
+            .collect()
+    }
+
+    /// Spreads the light from all the queued positions to their neighbours
+    fn propagate<O>(&mut self, mut queue: VecDeque<(usize, usize, usize)>, opacity: O)
+    where
+        O: Fn(usize, usize, usize) -> u8,
+    {
+        while let Some((x, y, z)) = queue.pop_front() {
+            let level = self.get(x, y, z);
+
+            let neighbours = [
+                (x.checked_sub(1), Some(y), Some(z)),
+                ((x < 15).then_some(x + 1), Some(y), Some(z)),
+                (Some(x), y.checked_sub(1), Some(z)),
+                (Some(x), (y + 1 < self.height).then_some(y + 1), Some(z)),
+                (Some(x), Some(y), z.checked_sub(1)),
+                (Some(x), Some(y), (z < 15).then_some(z + 1)),
+            ];
+
+            for (nx, ny, nz) in neighbours {
+                let (Some(nx), Some(ny), Some(nz)) = (nx, ny, nz) else {
+                    continue;
+                };
+
+                let target = level.saturating_sub(opacity(nx, ny, nz).max(1));
+                if target > self.get(nx, ny, nz) {
+                    self.set(nx, ny, nz, target);
+                    queue.push_back((nx, ny, nz));
+                }
+            }
+        }
+    }
+}
+
+/// Calculates the sky light, which shines down from the top of the column
+pub fn sky_light<O>(height: usize, opacity: O) -> LightGrid
+where
+    O: Fn(usize, usize, usize) -> u8,
+{
+    let mut grid = LightGrid::new(height);
+    let mut queue = VecDeque::new();
+
+    for x in 0..16 {
+        for z in 0..16 {
+            let mut level = MAX_LIGHT;
+            for y in (0..height).rev() {
+                level = level.saturating_sub(opacity(x, y, z));
+                if level == 0 {
+                    break;
+                }
+
+                grid.set(x, y, z, level);
+                queue.push_back((x, y, z));
+            }
+        }
+    }
+
+    grid.propagate(queue, opacity);
+    grid
+}
+
+/// Calculates the block light, which is emitted by the blocks themselves
+pub fn block_light<O, E>(height: usize, opacity: O, emission: E) -> LightGrid
+where
+    O: Fn(usize, usize, usize) -> u8,
+    E: Fn(usize, usize, usize) -> u8,
+{
+    let mut grid = LightGrid::new(height);
+    let mut queue = VecDeque::new();
+
+    for y in 0..height {
+        for z in 0..16 {
+            for x in 0..16 {
+                let level = emission(x, y, z).min(MAX_LIGHT);
+                if level > 0 {
+                    grid.set(x, y, z, level);
+                    queue.push_back((x, y, z));
+                }
+            }
+        }
+    }
+
+    grid.propagate(queue, opacity);
+    grid
+}
+
+/// The sky and block light of a chunk
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkLight {
+    pub sky: LightGrid,
+    pub block: LightGrid,
+}
+
+impl ChunkLight {
+    /// The light data for the packets, including the sections below and above the world
+    pub fn light_data(&self) -> LightData {
+        let mut sky: Vec<Option<LightArray>> = vec![Some(LightArray::default())];
+        sky.extend(self.sky.sections().into_iter().map(Some));
+        sky.push(Some(LightArray::filled(MAX_LIGHT)));
+
+        let mut block: Vec<Option<LightArray>> = vec![Some(LightArray::default())];
+        block.extend(self.block.sections().into_iter().map(Some));
+        block.push(Some(LightArray::default()));
+
+        LightData::new(&sky, &block)
+    }
+}
+
+impl<const L: i16, const U: i16> Chunk<L, U> {
+    /// Calculates the light of the chunk
+    pub fn light(&self) -> ChunkLight {
+        let height = U.abs_diff(L) as usize;
+        let block = |x: usize, y: usize, z: usize| {
+            self.get_block(
+                ChunkCoordinate(x as u8),
+                L + y as i16,
+                ChunkCoordinate(z as u8),
+            )
+            .map(|b| b.block())
+        };
+
+        let opacity = |x, y, z| block(x, y, z).map(|b| b.light_opacity()).unwrap_or(0);
+        let emission = |x, y, z| block(x, y, z).map(|b| b.light_emission()).unwrap_or(0);
+
+        ChunkLight {
+            sky: sky_light(height, opacity),
+            block: block_light(height, opacity, emission),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::coordinates::WorldChunkCoordinate;
+
+    #[test]
+    fn sky_light_under_roof() {
+        // A roof at y=8 covering everything except x=0
+        let opacity = |x: usize, y: usize, _: usize| if y == 8 && x > 0 { 15 } else { 0 };
+        let grid = sky_light(16, opacity);
+
+        assert_eq!(15, grid.get(5, 9, 5));
+        assert_eq!(0, grid.get(5, 8, 5));
+        // The light directly below the opening and spreading sideways from it
+        assert_eq!(15, grid.get(0, 0, 0));
+        assert_eq!(14, grid.get(1, 7, 0));
+        assert_eq!(10, grid.get(5, 7, 0));
+        assert_eq!(1, grid.get(14, 7, 0));
+    }
+
+    #[test]
+    fn block_light_source() {
+        let grid = block_light(
+            16,
+            |_, _, _| 0,
+            |x, y, z| if (x, y, z) == (8, 8, 8) { 14 } else { 0 },
+        );
+
+        assert_eq!(14, grid.get(8, 8, 8));
+        assert_eq!(13, grid.get(8, 9, 8));
+        assert_eq!(11, grid.get(9, 9, 9));
+        assert_eq!(0, grid.get(0, 0, 0));
+    }
+
+    #[test]
+    fn state_emission() {
+        let furnace = Block::Furnace.default_state();
+
+        assert_eq!(
+            15,
+            BlockData::State(Block::Lava.default_state()).light_emission()
+        );
+        assert_eq!(
+            14,
+            BlockData::State(Block::Torch.default_state()).light_emission()
+        );
+        assert_eq!(0, BlockData::State(furnace).light_emission());
+        assert_eq!(
+            13,
+            BlockData::State(furnace.with("lit", "true").unwrap()).light_emission()
+        );
+        assert_eq!(
+            7,
+            BlockData::State(Block::Light.default_state().with("level", "7").unwrap())
+                .light_emission()
+        );
+        assert_eq!(
+            0,
+            BlockData::State(Block::Stone.default_state()).light_emission()
+        );
+    }
+
+    #[test]
+    fn chunk_block_light() {
+        // A glowstone block in the air above a stone floor
+        let chunk = Chunk::<0, 16>::from_fn(
+            WorldChunkCoordinate(0),
+            WorldChunkCoordinate(0),
+            |x, z, y| match (x, z, y) {
+                (_, _, 0) => BlockData::Stone,
+                (8, 8, 4) => BlockData::State(Block::Glowstone.default_state()),
+                _ => BlockData::Air,
+            },
+        );
+
+        let light = chunk.light();
+        assert_eq!(15, light.block.get(8, 4, 8));
+        assert_eq!(14, light.block.get(8, 5, 8));
+        assert_eq!(12, light.block.get(9, 3, 9));
+        assert_eq!(9, light.block.get(8, 4, 2));
+        // The light does not pass through the floor
+        assert_eq!(0, light.block.get(8, 0, 8));
+        assert_eq!(12, light.block.get(8, 1, 8));
+    }
+
+    #[test]
+    fn water_filters_light() {
+        let grid = sky_light(16, |_, y, _| if y < 4 { 1 } else { 0 });
+
+        assert_eq!(15, grid.get(0, 4, 0));
+        assert_eq!(14, grid.get(0, 3, 0));
+        assert_eq!(11, grid.get(0, 0, 0));
+    }
+
+    #[test]
+    fn chunk_light_data() {
+        let chunk = Chunk::<0, 32>::from_fn(
+            WorldChunkCoordinate(0),
+            WorldChunkCoordinate(0),
+            |_, _, y| {
+                if y < 16 {
+                    BlockData::Stone
+                } else {
+                    BlockData::Air
+                }
+            },
+        );
+
+        let light = chunk.light();
+        assert_eq!(15, light.sky.get(0, 16, 0));
+        assert_eq!(0, light.sky.get(0, 15, 0));
+
+        let data = light.light_data();
+        // Below the world and the stone section are completely dark
+        assert_eq!(
+            protocol::general::BitSet::from_iter([0, 1]),
+            data.empty_sky_light_mask
+        );
+        assert_eq!(
+            protocol::general::BitSet::from_iter([2, 3]),
+            data.sky_light_mask
+        );
+        assert_eq!(Some(LightArray::filled(15)), data.sky_light(2));
+        assert_eq!(
+            protocol::general::BitSet::from_iter([0, 1, 2, 3]),
+            data.empty_block_light_mask
+        );
+    }
+}